@@ -17,9 +17,52 @@
 //
 //  4. Use relative paths for configure otherwise msys/mingw might be
 //     confused with drives and such.
+//
+//  5. Configure MPFR with either --enable-<option> or
+//     --disable-<option> for each option in MPFR_OPTIONS, and cache
+//     builds with different options separately.
+//
+//  6. With the disable-assembly feature, configure GMP with
+//     --disable-assembly instead of --enable-fat, and configure GMP,
+//     MPFR and MPC with a --build triplet from configfsf.guess so that
+//     the compiler flags are not tuned for the build CPU. Cache such
+//     builds separately.
+//
+//  7. Write the output of configure, make and make check to log files
+//     in OUT_DIR/log instead of the cargo build output, and print the
+//     time taken by each step at the end.
+//
+//  8. Compile and run a C program against the headers, bundled or
+//     system, to get the sizes, alignments and field offsets of the
+//     types, and generate OUT_DIR/<lib>_layout.rs with compile-time
+//     size and alignment assertions and tests for the field offsets.
+//
+//  9. Collect the #[link_name] symbols declared in src/gmp.rs,
+//     src/mpfr.rs and src/mpc.rs, together with their #[cfg]
+//     attributes, and generate OUT_DIR/link_check.rs for the
+//     link_check test, which references every one of them.
+//
+// 10. With the linker-plugin-lto feature, configure GMP, MPFR and MPC
+//     with CC="clang -flto=thin" and the LLVM archiver and linker, so
+//     that the C code in the archives is LLVM bitcode. The major LLVM
+//     version of clang must match that of rustc, and it is part of the
+//     cache key so that bitcode archives for different versions of
+//     LLVM are cached separately.
+//
+// 11. Types that Rust cannot pass to or return from C functions, such
+//     as long double, are passed by pointer to small C wrappers in
+//     GLUE_C, which is compiled into libgmp_mpfr_sys_glue.a whenever
+//     the mpfr feature is enabled. This is not cached as it is quick.
+//     The same is done for __float128 and _Decimal64 when MPFR has
+//     float128 and decimal float support.
+//
+// 12. If rustc supports const generics, the configuration option
+//     const_generics is set so that types that store limbs inline,
+//     such as mpfr::FixedMpfr, are available.
 
 use dirs;
 
+use std::cell::RefCell;
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fs::{self, File};
@@ -30,6 +73,7 @@ use std::os::unix::fs as unix_fs;
 use std::os::windows::fs as windows_fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
 const GMP_DIR: &str = "gmp-6.2.0-c";
 const MPFR_DIR: &str = "mpfr-4.0.2-p1-c";
@@ -38,6 +82,15 @@ const GMP_VER: (i32, i32, i32) = (6, 2, 0);
 const MPFR_VER: (i32, i32, i32) = (4, 0, 2);
 const MPC_VER: (i32, i32, i32) = (1, 1, 0);
 
+// MPFR configure options that can be selected using the feature
+// mpfr-<option>, together with the function reporting the option.
+const MPFR_OPTIONS: [(&str, &str); 4] = [
+    ("float128", "mpfr_buildopt_float128_p"),
+    ("decimal-float", "mpfr_buildopt_decimal_p"),
+    ("shared-cache", "mpfr_buildopt_sharedcache_p"),
+    ("gmp-internals", "mpfr_buildopt_gmpinternals_p"),
+];
+
 #[derive(Clone, Copy, PartialEq)]
 enum Target {
     Mingw,
@@ -52,13 +105,18 @@ struct Environment {
     lib_dir: PathBuf,
     include_dir: PathBuf,
     build_dir: PathBuf,
+    log_dir: PathBuf,
     cache_dir: Option<PathBuf>,
     jobs: OsString,
     target: Target,
     version_prefix: String,
     version_patch: Option<u64>,
     use_system_libs: bool,
+    disable_assembly: bool,
+    lto_llvm_version: Option<String>,
+    mpfr_options: Vec<&'static str>,
     workaround_47048: Workaround47048,
+    timings: RefCell<Vec<(&'static str, &'static str, Duration)>>,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -85,7 +143,31 @@ fn main() {
         Some(c) => Some(PathBuf::from(c)),
         None => dirs::cache_dir().map(|c| c.join("gmp-mpfr-sys")),
     };
-    let cache_dir = cache_dir.map(|cache| cache.join(&version_prefix).join(host));
+    let disable_assembly = there_is_env("CARGO_FEATURE_DISABLE_ASSEMBLY");
+    let lto_llvm_version = if there_is_env("CARGO_FEATURE_LINKER_PLUGIN_LTO") {
+        Some(rustc_llvm_major(&rustc))
+    } else {
+        None
+    };
+    let mut build_options = Vec::new();
+    if disable_assembly {
+        build_options.push(String::from("disable-assembly"));
+    }
+    if let Some(ref llvm) = lto_llvm_version {
+        build_options.push(format!("linker-plugin-lto-llvm{}", llvm));
+    }
+    let mpfr_options = MPFR_OPTIONS
+        .iter()
+        .map(|&(option, _)| option)
+        .filter(|option| there_is_env(&feature_env(&format!("mpfr-{}", option))))
+        .collect::<Vec<_>>();
+    let cache_dir = cache_dir.map(|cache| {
+        let dir = cache.join(&version_prefix).join(host);
+        match cache_key(&build_options, &mpfr_options) {
+            Some(key) => dir.join(key),
+            None => dir,
+        }
+    });
 
     let target = target
         .into_string()
@@ -102,6 +184,9 @@ fn main() {
     if use_system_libs && (target == Target::Msvc || target == Target::Mingw) {
         panic!("the use-system-libs feature is not supported on this target");
     }
+    if use_system_libs && lto_llvm_version.is_some() {
+        panic!("the linker-plugin-lto feature cannot be used with the use-system-libs feature");
+    }
     let mut env = Environment {
         rustc,
         src_dir,
@@ -109,13 +194,18 @@ fn main() {
         lib_dir: out_dir.join("lib"),
         include_dir: out_dir.join("include"),
         build_dir: out_dir.join("build"),
+        log_dir: out_dir.join("log"),
         cache_dir,
         jobs: cargo_env("NUM_JOBS"),
         target,
         version_prefix,
         version_patch,
         use_system_libs,
+        disable_assembly,
+        lto_llvm_version,
+        mpfr_options,
         workaround_47048: Workaround47048::No,
+        timings: RefCell::new(Vec::new()),
     };
 
     // make sure we have target directories
@@ -123,12 +213,15 @@ fn main() {
     create_dir_or_panic(&env.include_dir);
 
     env.workaround_47048 = check_for_bug_47048(&env);
+    env.check_feature("const_generics", TRY_CONST_GENERICS, None);
 
     if env.use_system_libs {
         check_system_libs(&env);
     } else {
         compile_libs(&env);
     }
+    write_link_check(&env);
+    print_timings(&env);
 }
 
 fn check_system_libs(env: &Environment) {
@@ -159,7 +252,7 @@ fn check_system_libs(env: &Environment) {
     let feature_mpfr = there_is_env("CARGO_FEATURE_MPFR");
     let feature_mpc = there_is_env("CARGO_FEATURE_MPC");
 
-    if feature_mpfr {
+    let mpfr_options = if feature_mpfr {
         println!("$ #Check for system MPFR");
         create_file_or_panic(&try_dir.join("system_mpfr.c"), SYSTEM_MPFR_C);
 
@@ -182,7 +275,12 @@ fn check_system_libs(env: &Environment) {
             Some(&env.out_dir.join("mpfr_h.rs")),
         )
         .unwrap_or_else(|e| panic!("{}", e));
-    }
+        let options = system_mpfr_options(env, &try_dir.join("system_mpfr.out"));
+        write_mpfr_options(&options);
+        Some(options)
+    } else {
+        None
+    };
 
     if feature_mpc {
         println!("$ #Check for system MPC");
@@ -209,6 +307,12 @@ fn check_system_libs(env: &Environment) {
         .unwrap_or_else(|e| panic!("{}", e));
     }
 
+    let mpfr_options = mpfr_options.as_ref().map(|options| &options[..]);
+    check_layouts(env, &try_dir, None, mpfr_options, feature_mpc);
+    if let Some(mpfr_options) = mpfr_options {
+        build_glue(env, &try_dir, None, mpfr_options, feature_mpc);
+    }
+
     if !there_is_env("CARGO_FEATURE_CNODELETE") {
         if build_dir_existed {
             remove_dir_or_panic(&try_dir);
@@ -243,8 +347,10 @@ fn compile_libs(env: &Environment) {
     } = need_compile(env, &gmp_ah, &mpfr_ah, &mpc_ah);
     if compile_gmp {
         check_for_msvc(&env);
+        check_clang(env);
         remove_dir_or_panic(&env.build_dir);
         create_dir_or_panic(&env.build_dir);
+        create_dir_or_panic(&env.log_dir);
         link_dir(&env.src_dir.join(GMP_DIR), &env.build_dir.join("gmp-src"));
         let (ref a, ref h) = gmp_ah;
         build_gmp(&env, a, h);
@@ -263,20 +369,51 @@ fn compile_libs(env: &Environment) {
         if !there_is_env("CARGO_FEATURE_CNODELETE") {
             remove_dir_or_panic(&env.build_dir);
         }
-        if save_cache(&env, &gmp_ah, &mpfr_ah, &mpc_ah) {
+        let saved = env.time("cache", "save", || {
+            save_cache(&env, &gmp_ah, &mpfr_ah, &mpc_ah)
+        });
+        if saved {
             clear_cache_redundancies(&env, mpfr_ah.is_some(), mpc_ah.is_some());
         }
     }
     process_gmp_header(&gmp_ah.1, Some(&env.out_dir.join("gmp_h.rs")))
         .unwrap_or_else(|e| panic!("{}", e));
-    if let Some(ref mpfr_ah) = mpfr_ah {
+    let mpfr_options = mpfr_ah.as_ref().map(|mpfr_ah| {
         process_mpfr_header(&mpfr_ah.1, Some(&env.out_dir.join("mpfr_h.rs")))
             .unwrap_or_else(|e| panic!("{}", e));
-    }
+        // building with --with-gmp-build already makes MPFR report
+        // that it uses GMP internals
+        let mut options = env.mpfr_options.clone();
+        if !options.contains(&"gmp-internals") {
+            options.push("gmp-internals");
+        }
+        write_mpfr_options(&options);
+        options
+    });
     if let Some(ref mpc_ah) = mpc_ah {
         process_mpc_header(&mpc_ah.1, Some(&env.out_dir.join("mpc_h.rs")))
             .unwrap_or_else(|e| panic!("{}", e));
     }
+    let try_dir = env.out_dir.join("try_layout");
+    create_dir_or_panic(&try_dir);
+    let mpfr_options = mpfr_options.as_ref().map(|options| &options[..]);
+    check_layouts(
+        env,
+        &try_dir,
+        Some(&env.include_dir),
+        mpfr_options,
+        mpc_ah.is_some(),
+    );
+    if let Some(mpfr_options) = mpfr_options {
+        build_glue(
+            env,
+            &try_dir,
+            Some(&env.include_dir),
+            mpfr_options,
+            mpc_ah.is_some(),
+        );
+    }
+    remove_dir_or_panic(&try_dir);
     write_link_info(&env, mpfr_ah.is_some(), mpc_ah.is_some());
 }
 
@@ -332,7 +469,7 @@ fn need_compile(
             mpfr: false,
             mpc: false,
         };
-    } else if load_cache(env, gmp_ah, mpfr_ah, mpc_ah) {
+    } else if env.time("cache", "load", || load_cache(env, gmp_ah, mpfr_ah, mpc_ah)) {
         // if loading cache works, we're done
         return NeedCompile {
             gmp: false,
@@ -530,9 +667,18 @@ fn build_gmp(env: &Environment, lib: &Path, header: &Path) {
     let build_dir = env.build_dir.join("gmp-build");
     create_dir_or_panic(&build_dir);
     println!("$ cd {:?}", build_dir);
-    let conf = "../gmp-src/configure --enable-fat --disable-shared --with-pic";
-    configure(&build_dir, &OsString::from(conf));
-    make_and_check(env, &build_dir);
+    let mode = if env.disable_assembly {
+        "--disable-assembly"
+    } else {
+        "--enable-fat"
+    };
+    let conf = format!(
+        "../gmp-src/configure {} --disable-shared --with-pic{}",
+        mode,
+        common_conf_args(env)
+    );
+    configure(env, "gmp", &build_dir, &OsString::from(conf));
+    make_and_check(env, "gmp", &build_dir);
     let build_lib = build_dir.join(".libs").join("libgmp.a");
     copy_file_or_panic(&build_lib, &lib);
     let build_header = build_dir.join("gmp.h");
@@ -781,10 +927,23 @@ fn build_mpfr(env: &Environment, lib: &Path, header: &Path) {
         &env.build_dir.join("gmp-build"),
         &build_dir.join("gmp-build"),
     );
-    let conf = "../mpfr-src/configure --enable-thread-safe --disable-shared \
-                --with-gmp-build=../gmp-build --with-pic";
-    configure(&build_dir, &OsString::from(conf));
-    make_and_check(env, &build_dir);
+    let mut conf = String::from(
+        "../mpfr-src/configure --enable-thread-safe --disable-shared \
+         --with-gmp-build=../gmp-build --with-pic",
+    );
+    // options not selected are disabled explicitly, as some of them
+    // would otherwise be enabled if detected
+    for &(option, _) in &MPFR_OPTIONS {
+        let enable = if env.mpfr_options.contains(&option) {
+            "enable"
+        } else {
+            "disable"
+        };
+        conf.push_str(&format!(" --{}-{}", enable, option));
+    }
+    conf.push_str(&common_conf_args(env));
+    configure(env, "mpfr", &build_dir, &OsString::from(conf));
+    make_and_check(env, "mpfr", &build_dir);
     let build_lib = build_dir.join("src").join(".libs").join("libmpfr.a");
     copy_file_or_panic(&build_lib, &lib);
     let src_header = env.build_dir.join("mpfr-src").join("src").join("mpfr.h");
@@ -803,19 +962,379 @@ fn build_mpc(env: &Environment, lib: &Path, header: &Path) {
         &env.build_dir.join("mpfr-build"),
         &build_dir.join("mpfr-build"),
     );
-    let conf = "../mpc-src/configure --disable-shared \
-                --with-mpfr-include=../mpfr-src/src \
-                --with-mpfr-lib=../mpfr-build/src/.libs \
-                --with-gmp-include=../gmp-build \
-                --with-gmp-lib=../gmp-build/.libs --with-pic";
-    configure(&build_dir, &OsString::from(conf));
-    make_and_check(env, &build_dir);
+    let conf = format!(
+        "../mpc-src/configure --disable-shared \
+         --with-mpfr-include=../mpfr-src/src \
+         --with-mpfr-lib=../mpfr-build/src/.libs \
+         --with-gmp-include=../gmp-build \
+         --with-gmp-lib=../gmp-build/.libs --with-pic{}",
+        common_conf_args(env)
+    );
+    configure(env, "mpc", &build_dir, &OsString::from(conf));
+    make_and_check(env, "mpc", &build_dir);
     let build_lib = build_dir.join("src").join(".libs").join("libmpc.a");
     copy_file_or_panic(&build_lib, &lib);
     let src_header = env.build_dir.join("mpc-src").join("src").join("mpc.h");
     copy_file_or_panic(&src_header, &header);
 }
 
+fn system_mpfr_options(env: &Environment, out_file: &Path) -> Vec<&'static str> {
+    let mut reported = Vec::new();
+    let mut reader = open(out_file);
+    let mut buf = String::new();
+    while read_line(&mut reader, &mut buf, out_file) > 0 {
+        for &(option, function) in &MPFR_OPTIONS {
+            let s = format!("#define {} ", function.to_uppercase());
+            if let Some(start) = buf.find(&s) {
+                if buf[(start + s.len())..].trim().parse::<i32>().ok() != Some(0) {
+                    reported.push(option);
+                }
+            }
+        }
+        buf.clear();
+    }
+    drop(reader);
+
+    for option in &env.mpfr_options {
+        if !reported.contains(option) {
+            panic!(
+                "the mpfr-{} feature was selected, but the system MPFR was not built with it",
+                option
+            );
+        }
+    }
+    reported
+}
+
+fn write_mpfr_options(options: &[&str]) {
+    for option in options {
+        let name = format!("mpfr_{}", option.replace('-', "_"));
+        println!("cargo:rustc-cfg={}", name);
+        println!("cargo:{}=1", name);
+    }
+}
+
+fn check_layouts(
+    env: &Environment,
+    try_dir: &Path,
+    include_dir: Option<&Path>,
+    mpfr_options: Option<&[&str]>,
+    feature_mpc: bool,
+) {
+    println!("$ cd {:?}", try_dir);
+    println!("$ #Check layouts");
+    create_file_or_panic(&try_dir.join("layout.c"), LAYOUT_C);
+    let mut cmd = Command::new("gcc");
+    cmd.current_dir(try_dir);
+    if let Some(include_dir) = include_dir {
+        cmd.arg("-I").arg(include_dir);
+    }
+    if let Some(mpfr_options) = mpfr_options {
+        cmd.arg("-DLAYOUT_MPFR");
+        if mpfr_options.contains(&"float128") {
+            cmd.arg("-DLAYOUT_FLOAT128");
+        }
+        if mpfr_options.contains(&"decimal-float") {
+            cmd.arg("-DLAYOUT_DECIMAL_FLOAT");
+        }
+    }
+    if feature_mpc {
+        cmd.arg("-DLAYOUT_MPC");
+    }
+    cmd.arg("layout.c").arg("-o").arg("layout.exe");
+    execute(cmd);
+
+    cmd = Command::new(try_dir.join("layout.exe"));
+    cmd.current_dir(try_dir);
+    execute(cmd);
+    let layouts = read_layouts(&try_dir.join("layout.out"));
+    for layout in layouts.iter().filter(|layout| layout.name == "long_double") {
+        println!("cargo:rustc-cfg=long_double_size=\"{}\"", layout.size);
+        println!("cargo:rustc-cfg=long_double_align=\"{}\"", layout.align);
+    }
+
+    let mut libs = vec!["gmp"];
+    if mpfr_options.is_some() {
+        libs.push("mpfr");
+    }
+    if feature_mpc {
+        libs.push("mpc");
+    }
+    for lib in libs {
+        let mut code = String::from("// generated by build.rs from the C layouts\n\n");
+        let mut tests = String::new();
+        for layout in layouts.iter().filter(|layout| layout.lib == lib) {
+            let (name, size, align) = (&layout.name, layout.size, layout.align);
+            code.push_str(&format!(
+                "const _: [(); {}] = [(); ::core::mem::size_of::<{}>()];\n\
+                 const _: [(); {}] = [(); ::core::mem::align_of::<{}>()];\n",
+                size, name, align, name
+            ));
+            if layout.fields.is_empty() {
+                continue;
+            }
+            tests.push_str(&format!(
+                "\n    #[test]\n    \
+                 fn check_{0}_layout() {{\n        \
+                 let s: {0} = unsafe {{ ::core::mem::zeroed() }};\n        \
+                 let base = &s as *const {0} as usize;\n",
+                name
+            ));
+            for &(ref field, offset) in &layout.fields {
+                tests.push_str(&format!(
+                    "        assert_eq!(&s.{} as *const _ as usize - base, {});\n",
+                    field, offset
+                ));
+            }
+            tests.push_str("    }\n");
+        }
+        code.push_str("\n#[cfg(test)]\nmod layout_tests {\n    use super::*;\n");
+        code.push_str(&tests);
+        code.push_str("}\n");
+        let out_file = env.out_dir.join(format!("{}_layout.rs", lib));
+        let mut rs = create(&out_file);
+        write_flush(&mut rs, &code, &out_file);
+    }
+}
+
+fn build_glue(
+    env: &Environment,
+    try_dir: &Path,
+    include_dir: Option<&Path>,
+    mpfr_options: &[&str],
+    feature_mpc: bool,
+) {
+    println!("$ cd {:?}", try_dir);
+    println!("$ #Build glue");
+    create_file_or_panic(&try_dir.join("gmp_mpfr_sys_glue.c"), GLUE_C);
+    let (cc, ar) = if env.lto_llvm_version.is_some() {
+        ("clang", "llvm-ar")
+    } else {
+        ("gcc", "ar")
+    };
+    let mut cmd = Command::new(cc);
+    cmd.current_dir(try_dir).arg("-c").arg("-O2").arg("-fPIC");
+    if env.lto_llvm_version.is_some() {
+        cmd.arg("-flto=thin");
+    }
+    if let Some(include_dir) = include_dir {
+        cmd.arg("-I").arg(include_dir);
+    }
+    if mpfr_options.contains(&"float128") {
+        cmd.arg("-DGLUE_FLOAT128");
+    }
+    if mpfr_options.contains(&"decimal-float") {
+        cmd.arg("-DGLUE_DECIMAL_FLOAT");
+    }
+    if feature_mpc {
+        cmd.arg("-DGLUE_MPC");
+    }
+    cmd.arg("gmp_mpfr_sys_glue.c");
+    execute(cmd);
+
+    let lib = env.lib_dir.join("libgmp_mpfr_sys_glue.a");
+    let _ = fs::remove_file(&lib);
+    cmd = Command::new(ar);
+    cmd.current_dir(try_dir)
+        .arg("cr")
+        .arg(&lib)
+        .arg("gmp_mpfr_sys_glue.o");
+    execute(cmd);
+}
+
+struct Layout {
+    lib: String,
+    name: String,
+    size: usize,
+    align: usize,
+    fields: Vec<(String, usize)>,
+}
+
+fn read_layouts(out_file: &Path) -> Vec<Layout> {
+    let mut layouts = Vec::<Layout>::new();
+    let mut reader = open(out_file);
+    let mut buf = String::new();
+    while read_line(&mut reader, &mut buf, out_file) > 0 {
+        let parse = |s: &str| {
+            s.parse::<usize>()
+                .unwrap_or_else(|_| panic!("Cannot parse layout line: {:?}", buf))
+        };
+        let words = buf.split_whitespace().collect::<Vec<_>>();
+        match words[..] {
+            ["type", lib, name, size, align] => layouts.push(Layout {
+                lib: lib.to_string(),
+                name: name.to_string(),
+                size: parse(size),
+                align: parse(align),
+                fields: Vec::new(),
+            }),
+            ["field", name, offset] => {
+                let layout = layouts
+                    .last_mut()
+                    .unwrap_or_else(|| panic!("Field without type: {:?}", buf));
+                layout.fields.push((name.to_string(), parse(offset)));
+            }
+            _ => panic!("Cannot parse layout line: {:?}", buf),
+        }
+        buf.clear();
+    }
+    layouts
+}
+
+fn write_link_check(env: &Environment) {
+    let feature_mpc = there_is_env("CARGO_FEATURE_MPC");
+    let feature_mpfr = feature_mpc || there_is_env("CARGO_FEATURE_MPFR");
+    let mut modules = vec!["gmp"];
+    if feature_mpfr {
+        modules.push("mpfr");
+    }
+    if feature_mpc {
+        modules.push("mpc");
+    }
+    let mut decls = String::new();
+    let mut pushes = String::new();
+    let mut count = 0;
+    for module in modules {
+        let src = env.src_dir.join("src").join(format!("{}.rs", module));
+        println!("cargo:rerun-if-changed={}", src.display());
+        for symbol in link_names(&src) {
+            let ident = format!("{}_{}", module, count);
+            count += 1;
+            let cfgs = symbol
+                .cfgs
+                .iter()
+                .map(|cfg| format!("    {}\n", cfg))
+                .collect::<String>();
+            let (decl, addr) = if symbol.is_static {
+                (
+                    format!("static {}: u8;", ident),
+                    format!("unsafe {{ &{} as *const u8 as usize }}", ident),
+                )
+            } else {
+                (
+                    format!("fn {}();", ident),
+                    format!("{} as *const () as usize", ident),
+                )
+            };
+            decls.push_str(&format!(
+                "{}    #[link_name = \"{}\"]\n    {}\n",
+                cfgs, symbol.name, decl
+            ));
+            pushes.push_str(&format!(
+                "{}    symbols.push((\"{}\", {}));\n",
+                cfgs, symbol.name, addr
+            ));
+        }
+    }
+    let code = format!(
+        "// generated by build.rs from the #[link_name] attributes\n\n\
+         extern \"C\" {{\n{}}}\n\n\
+         fn symbols() -> Vec<(&'static str, usize)> {{\n    \
+         let mut symbols = Vec::new();\n{}    symbols\n}}\n",
+        decls, pushes
+    );
+    let out_file = env.out_dir.join("link_check.rs");
+    let mut rs = create(&out_file);
+    write_flush(&mut rs, &code, &out_file);
+}
+
+struct LinkName {
+    name: String,
+    is_static: bool,
+    cfgs: Vec<String>,
+}
+
+// Panics on anything it does not understand, so that link_check cannot
+// silently skip symbols.
+fn link_names(src: &Path) -> Vec<LinkName> {
+    let mut symbols = Vec::<LinkName>::new();
+    let mut block_cfgs = Vec::new();
+    let mut cfgs = Vec::new();
+    let mut link_name = None;
+    // brace depth inside an extern block, zero outside
+    let mut depth = 0usize;
+    // an attribute that spans several lines
+    let mut attr: Option<String> = None;
+    let mut reader = open(src);
+    let mut buf = String::new();
+    let mut line_num = 0;
+    while read_line(&mut reader, &mut buf, src) > 0 {
+        line_num += 1;
+        let mut line = buf.trim().to_string();
+        buf.clear();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        if let Some(mut partial) = attr.take() {
+            partial.push_str(&line);
+            if !line.ends_with(']') {
+                attr = Some(partial);
+                continue;
+            }
+            line = partial;
+        } else if line.starts_with("#[") && !line.ends_with(']') {
+            attr = Some(line);
+            continue;
+        }
+        let unexpected = || panic!("{}:{}: cannot parse {:?}", src.display(), line_num, line);
+        if line.starts_with("#[cfg(") {
+            cfgs.push(line);
+        } else if line.starts_with("#[link_name = ") {
+            if depth == 0 || link_name.is_some() {
+                unexpected();
+            }
+            let name = line
+                .trim_start_matches("#[link_name = ")
+                .trim_end_matches(']');
+            link_name = Some(name.trim_matches('"').to_string());
+        } else if line.starts_with("#[") {
+        } else if line == "extern \"C\"" {
+            // brace on the next line
+            unexpected();
+        } else if line.starts_with("extern \"C\" {") {
+            if depth != 0 || line != "extern \"C\" {" {
+                unexpected();
+            }
+            depth = 1;
+            block_cfgs = cfgs.split_off(0);
+        } else if depth > 0 {
+            let is_static = line.starts_with("pub static ") || line.starts_with("static ");
+            let is_fn = line.starts_with("pub fn ") || line.starts_with("fn ");
+            if let Some(name) = link_name.take() {
+                if !is_static && !is_fn {
+                    unexpected();
+                }
+                let mut all_cfgs = block_cfgs.clone();
+                all_cfgs.append(&mut cfgs);
+                let duplicate = symbols.iter().any(|s| s.name == name && s.cfgs == all_cfgs);
+                if !duplicate {
+                    symbols.push(LinkName {
+                        name,
+                        is_static,
+                        cfgs: all_cfgs,
+                    });
+                }
+            }
+            cfgs.clear();
+            depth += line.matches('{').count();
+            let closes = line.matches('}').count();
+            if closes > depth {
+                unexpected();
+            }
+            depth -= closes;
+            if depth == 0 {
+                block_cfgs.clear();
+            }
+        } else {
+            cfgs.clear();
+        }
+    }
+    if depth != 0 || attr.is_some() || link_name.is_some() {
+        panic!("{}: unexpected end of file", src.display());
+    }
+    symbols
+}
+
 fn write_link_info(env: &Environment, feature_mpfr: bool, feature_mpc: bool) {
     let out_str = env.out_dir.to_str().unwrap_or_else(|| {
         panic!(
@@ -838,8 +1357,25 @@ fn write_link_info(env: &Environment, feature_mpfr: bool, feature_mpc: bool) {
     println!("cargo:out_dir={}", out_str);
     println!("cargo:lib_dir={}", lib_str);
     println!("cargo:include_dir={}", include_str);
+    let build_mode = if env.use_system_libs {
+        "system"
+    } else if env.disable_assembly {
+        println!("cargo:rustc-cfg=disable_assembly");
+        "generic"
+    } else {
+        "fat"
+    };
+    println!("cargo:build_mode={}", build_mode);
+    if let Some(ref llvm) = env.lto_llvm_version {
+        println!("cargo:rustc-cfg=linker_plugin_lto");
+        println!("cargo:lto=thin");
+        println!("cargo:lto_llvm_version={}", llvm);
+    }
     println!("cargo:rustc-link-search=native={}", lib_str);
     let maybe_static = if env.use_system_libs { "" } else { "static=" };
+    if feature_mpfr {
+        println!("cargo:rustc-link-lib=static=gmp_mpfr_sys_glue");
+    }
     if feature_mpc {
         println!("cargo:rustc-link-lib={}mpc", maybe_static);
     }
@@ -856,7 +1392,42 @@ fn write_link_info(env: &Environment, feature_mpfr: bool, feature_mpc: bool) {
 }
 
 impl Environment {
-    #[allow(dead_code)]
+    fn time<T, F: FnOnce() -> T>(&self, lib: &'static str, phase: &'static str, f: F) -> T {
+        let start = Instant::now();
+        let ret = f();
+        let elapsed = start.elapsed();
+        self.timings.borrow_mut().push((lib, phase, elapsed));
+        ret
+    }
+
+    // Like execute, but the output goes to OUT_DIR/log/<lib>-<phase>.log
+    // and the time taken is recorded for the build summary.
+    fn execute_logged(&self, mut command: Command, lib: &'static str, phase: &'static str) {
+        let log = self.log_dir.join(format!("{}-{}.log", lib, phase));
+        println!("$ {:?} > {:?} 2>&1", command, log);
+        let stdout = File::create(&log).unwrap_or_else(|_| panic!("Cannot create file: {:?}", log));
+        let stderr = stdout
+            .try_clone()
+            .unwrap_or_else(|_| panic!("Cannot duplicate handle for: {:?}", log));
+        command.stdout(stdout).stderr(stderr);
+        let status = self.time(lib, phase, || {
+            command
+                .status()
+                .unwrap_or_else(|_| panic!("Unable to execute: {:?}", command))
+        });
+        if !status.success() {
+            print_log_tail(&log);
+            if let Some(code) = status.code() {
+                panic!(
+                    "Program failed with code {}: {:?}\nSee log file: {:?}",
+                    code, command, log
+                );
+            } else {
+                panic!("Program failed: {:?}\nSee log file: {:?}", command, log);
+            }
+        }
+    }
+
     fn check_feature(&self, name: &str, contents: &str, nightly_features: Option<&str>) {
         let try_dir = self.out_dir.join(format!("try_{}", name));
         let filename = format!("try_{}.rs", name);
@@ -911,6 +1482,96 @@ fn there_is_env(name: &str) -> bool {
     env::var_os(name).is_some()
 }
 
+fn feature_env(feature: &str) -> String {
+    format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"))
+}
+
+fn cache_key(build_options: &[String], mpfr_options: &[&str]) -> Option<String> {
+    let mut parts = build_options.to_vec();
+    // the cache only holds bundled builds, and building the bundled
+    // MPFR with --with-gmp-build always enables the GMP internals
+    for option in mpfr_options.iter().filter(|&&o| o != "gmp-internals") {
+        parts.push(format!("mpfr-{}", option));
+    }
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("+"))
+    }
+}
+
+fn common_conf_args(env: &Environment) -> String {
+    let mut args = String::new();
+    if env.disable_assembly {
+        // The build triplet from configfsf.guess names only the
+        // architecture, e.g. x86_64 rather than skylake as given by
+        // config.guess, so GMP uses its baseline compiler flags for the
+        // architecture.
+        args.push_str(" --build=$(sh ../gmp-src/configfsf.guess)");
+    }
+    if env.lto_llvm_version.is_some() {
+        args.push_str(
+            " CC='clang -flto=thin' AR=llvm-ar NM=llvm-nm RANLIB=llvm-ranlib \
+             LDFLAGS=-fuse-ld=lld",
+        );
+    }
+    args
+}
+
+fn rustc_llvm_major(rustc: &OsStr) -> String {
+    let mut cmd = Command::new(rustc);
+    cmd.arg("-vV");
+    let output = execute_output(cmd);
+    let version = output
+        .lines()
+        .filter_map(|line| {
+            let s = "LLVM version: ";
+            if line.starts_with(s) {
+                Some(line.trim_start_matches(s))
+            } else {
+                None
+            }
+        })
+        .next()
+        .unwrap_or_else(|| panic!("Cannot find LLVM version of rustc in:\n{}", output));
+    major_version(version)
+}
+
+fn check_clang(env: &Environment) {
+    let rustc_llvm = match env.lto_llvm_version {
+        Some(ref v) => v,
+        None => return,
+    };
+    let mut cmd = Command::new("clang");
+    cmd.arg("--version");
+    let output = execute_output(cmd);
+    let version = output
+        .lines()
+        .filter_map(|line| {
+            let s = "clang version ";
+            line.find(s).map(|start| &line[start + s.len()..])
+        })
+        .next()
+        .unwrap_or_else(|| panic!("Cannot find version of clang in:\n{}", output));
+    let clang_llvm = major_version(version);
+    if clang_llvm != *rustc_llvm {
+        panic!(
+            "the linker-plugin-lto feature needs clang with the same LLVM major version as \
+             rustc, but clang uses LLVM {} and rustc uses LLVM {}",
+            clang_llvm, rustc_llvm
+        );
+    }
+}
+
+fn major_version(version: &str) -> String {
+    version
+        .trim()
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
 fn check_for_msvc(env: &Environment) {
     if env.target == Target::Msvc {
         panic!("Windows MSVC target is not supported (linking would fail)");
@@ -1054,23 +1715,41 @@ fn copy_file_or_panic(src: &Path, dst: &Path) {
     });
 }
 
-fn configure(build_dir: &Path, conf_line: &OsStr) {
+fn configure(env: &Environment, lib: &'static str, build_dir: &Path, conf_line: &OsStr) {
     let mut conf = Command::new("sh");
     conf.current_dir(&build_dir).arg("-c").arg(conf_line);
-    execute(conf);
+    env.execute_logged(conf, lib, "configure");
 }
 
-fn make_and_check(env: &Environment, build_dir: &Path) {
+fn make_and_check(env: &Environment, lib: &'static str, build_dir: &Path) {
     let mut make = Command::new("make");
     make.current_dir(build_dir).arg("-j").arg(&env.jobs);
-    execute(make);
+    env.execute_logged(make, lib, "make");
     let mut make_check = Command::new("make");
     make_check
         .current_dir(build_dir)
         .arg("-j")
         .arg(&env.jobs)
         .arg("check");
-    execute(make_check);
+    env.execute_logged(make_check, lib, "check");
+}
+
+fn print_timings(env: &Environment) {
+    let timings = env.timings.borrow();
+    if timings.is_empty() {
+        return;
+    }
+    if env.log_dir.is_dir() {
+        println!("$ #Build summary, logs in {:?}", env.log_dir);
+    } else {
+        println!("$ #Build summary");
+    }
+    let mut total = Duration::from_secs(0);
+    for &(lib, phase, duration) in timings.iter() {
+        println!("{:<6}{:<11}{:>9.1} s", lib, phase, secs(duration));
+        total += duration;
+    }
+    println!("{:<17}{:>9.1} s", "total", secs(total));
 }
 
 #[cfg(unix)]
@@ -1113,6 +1792,36 @@ fn execute(mut command: Command) {
     }
 }
 
+fn secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_millis()) / 1000.0
+}
+
+fn print_log_tail(log: &Path) {
+    const TAIL_LINES: usize = 30;
+    let contents = match fs::read(log) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    let contents = String::from_utf8_lossy(&contents);
+    let lines = contents.lines().collect::<Vec<_>>();
+    let skip = lines.len().saturating_sub(TAIL_LINES);
+    eprintln!("Last lines of {:?}:", log);
+    for line in &lines[skip..] {
+        eprintln!("{}", line);
+    }
+}
+
+fn execute_output(mut command: Command) -> String {
+    println!("$ {:?}", command);
+    let output = command
+        .output()
+        .unwrap_or_else(|_| panic!("Unable to execute: {:?}", command));
+    if !output.status.success() {
+        panic!("Program failed: {:?}", command);
+    }
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
 fn open(name: &Path) -> BufReader<File> {
     let file = File::open(name).unwrap_or_else(|_| panic!("Cannot open file: {:?}", name));
     BufReader::new(file)
@@ -1177,6 +1886,14 @@ typedef FILE *__cdecl (*_f__acrt_iob_func)(unsigned index);
 _f__acrt_iob_func __MINGW_IMP_SYMBOL(__acrt_iob_func) = __acrt_iob_func;
 "#;
 
+// checks whether rustc supports const generics
+const TRY_CONST_GENERICS: &str = r#"// try_const_generics.rs
+pub struct Limbs<const N: usize>([u64; N]);
+fn main() {
+    let _ = Limbs::<2>([0; 2]);
+}
+"#;
+
 // prints part of the header
 const SYSTEM_GMP_C: &str = r##"/* system_gmp.c */
 #include <gmp.h>
@@ -1208,7 +1925,7 @@ int main(void) {
 }
 "##;
 
-// prints part of the header
+// prints part of the header and the build options
 const SYSTEM_MPFR_C: &str = r##"/* system_mpfr.c */
 #include <mpfr.h>
 #include <stdio.h>
@@ -1223,6 +1940,10 @@ int main(void) {
     fputs(DEFINE_STR(MPFR_VERSION_MINOR), f);
     fputs(DEFINE_STR(MPFR_VERSION_PATCHLEVEL), f);
     fputs(DEFINE_STR(MPFR_VERSION_STRING), f);
+    fprintf(f, "#define MPFR_BUILDOPT_FLOAT128_P %d\n", mpfr_buildopt_float128_p());
+    fprintf(f, "#define MPFR_BUILDOPT_DECIMAL_P %d\n", mpfr_buildopt_decimal_p());
+    fprintf(f, "#define MPFR_BUILDOPT_SHAREDCACHE_P %d\n", mpfr_buildopt_sharedcache_p());
+    fprintf(f, "#define MPFR_BUILDOPT_GMPINTERNALS_P %d\n", mpfr_buildopt_gmpinternals_p());
 
     fclose(f);
 
@@ -1230,6 +1951,190 @@ int main(void) {
 }
 "##;
 
+// prints the sizes, alignments and field offsets of the types
+const LAYOUT_C: &str = r##"/* layout.c */
+#include <stddef.h>
+#include <stdio.h>
+#include <gmp.h>
+#ifdef LAYOUT_MPFR
+#include <mpfr.h>
+#endif
+#ifdef LAYOUT_MPC
+#include <mpc.h>
+#endif
+
+#define ALIGNOF(t) offsetof(struct { char c; t x; }, x)
+#define TYPE(lib, rs, t) \
+    fprintf(f, "type " lib " " rs " %u %u\n", \
+            (unsigned) sizeof(t), (unsigned) ALIGNOF(t))
+#define FIELD(rs, t, field) \
+    fprintf(f, "field " rs " %u\n", (unsigned) offsetof(t, field))
+#define COMPLEX_FIELD(rs, t, part) \
+    do { \
+        t z; \
+        fprintf(f, "field " rs " %u\n", \
+                (unsigned) ((char *) &__##part##__ z - (char *) &z)); \
+    } while (0)
+
+int main(void) {
+    FILE *f = fopen("layout.out", "w");
+
+    TYPE("gmp", "exp_t", mp_exp_t);
+    TYPE("gmp", "limb_t", mp_limb_t);
+    TYPE("gmp", "size_t", mp_size_t);
+    TYPE("gmp", "bitcnt_t", mp_bitcnt_t);
+    TYPE("gmp", "mpz_t", __mpz_struct);
+    FIELD("alloc", __mpz_struct, _mp_alloc);
+    FIELD("size", __mpz_struct, _mp_size);
+    FIELD("d", __mpz_struct, _mp_d);
+    TYPE("gmp", "mpq_t", __mpq_struct);
+    FIELD("num", __mpq_struct, _mp_num);
+    FIELD("den", __mpq_struct, _mp_den);
+    TYPE("gmp", "mpf_t", __mpf_struct);
+    FIELD("prec", __mpf_struct, _mp_prec);
+    FIELD("size", __mpf_struct, _mp_size);
+    FIELD("exp", __mpf_struct, _mp_exp);
+    FIELD("d", __mpf_struct, _mp_d);
+    TYPE("gmp", "randstate_t", __gmp_randstate_struct);
+    FIELD("seed", __gmp_randstate_struct, _mp_seed);
+    FIELD("alg", __gmp_randstate_struct, _mp_alg);
+    FIELD("algdata", __gmp_randstate_struct, _mp_algdata);
+
+#ifdef LAYOUT_MPFR
+    TYPE("mpfr", "prec_t", mpfr_prec_t);
+    TYPE("mpfr", "exp_t", mpfr_exp_t);
+    TYPE("mpfr", "rnd_t", mpfr_rnd_t);
+    TYPE("mpfr", "long_double", long double);
+#ifdef LAYOUT_FLOAT128
+    TYPE("mpfr", "float128", __float128);
+#endif
+#ifdef LAYOUT_DECIMAL_FLOAT
+    TYPE("mpfr", "decimal64", _Decimal64);
+#endif
+    TYPE("mpfr", "mpfr_t", __mpfr_struct);
+    FIELD("prec", __mpfr_struct, _mpfr_prec);
+    FIELD("sign", __mpfr_struct, _mpfr_sign);
+    FIELD("exp", __mpfr_struct, _mpfr_exp);
+    FIELD("d", __mpfr_struct, _mpfr_d);
+#endif
+
+#ifdef LAYOUT_MPC
+    TYPE("mpc", "rnd_t", mpc_rnd_t);
+    TYPE("mpc", "double_complex", double _Complex);
+    COMPLEX_FIELD("re", double _Complex, real);
+    COMPLEX_FIELD("im", double _Complex, imag);
+    TYPE("mpc", "long_double_complex", long double _Complex);
+    COMPLEX_FIELD("re", long double _Complex, real);
+    COMPLEX_FIELD("im", long double _Complex, imag);
+    TYPE("mpc", "mpc_t", __mpc_struct);
+    FIELD("re", __mpc_struct, re);
+    FIELD("im", __mpc_struct, im);
+#endif
+
+    fclose(f);
+
+    return 0;
+}
+"##;
+
+// wrappers for functions with arguments or return values that cannot
+// be passed between Rust and C directly
+const GLUE_C: &str = r##"/* gmp_mpfr_sys_glue.c */
+#include <gmp.h>
+#ifdef GLUE_FLOAT128
+#define MPFR_WANT_FLOAT128
+#endif
+#ifdef GLUE_DECIMAL_FLOAT
+#define MPFR_WANT_DECIMAL_FLOATS
+#endif
+#include <mpfr.h>
+#ifdef GLUE_MPC
+#include <complex.h>
+#include <mpc.h>
+#endif
+
+/* long double */
+
+void gmp_mpfr_sys_ld_from_d(long double *rop, double op) {
+    *rop = op;
+}
+
+double gmp_mpfr_sys_ld_to_d(const long double *op) {
+    return *op;
+}
+
+int gmp_mpfr_sys_mpfr_set_ld(mpfr_ptr rop, const long double *op, mpfr_rnd_t rnd) {
+    return mpfr_set_ld(rop, *op, rnd);
+}
+
+void gmp_mpfr_sys_mpfr_get_ld(long double *rop, mpfr_srcptr op, mpfr_rnd_t rnd) {
+    *rop = mpfr_get_ld(op, rnd);
+}
+
+void gmp_mpfr_sys_mpfr_get_ld_2exp(long double *rop, long *exp, mpfr_srcptr op,
+                                   mpfr_rnd_t rnd) {
+    *rop = mpfr_get_ld_2exp(exp, op, rnd);
+}
+
+int gmp_mpfr_sys_mpfr_cmp_ld(mpfr_srcptr op1, const long double *op2) {
+    return mpfr_cmp_ld(op1, *op2);
+}
+
+#ifdef GLUE_FLOAT128
+/* __float128 */
+
+int gmp_mpfr_sys_mpfr_set_float128(mpfr_ptr rop, const __float128 *op,
+                                   mpfr_rnd_t rnd) {
+    return mpfr_set_float128(rop, *op, rnd);
+}
+
+void gmp_mpfr_sys_mpfr_get_float128(__float128 *rop, mpfr_srcptr op,
+                                    mpfr_rnd_t rnd) {
+    *rop = mpfr_get_float128(op, rnd);
+}
+#endif
+
+#ifdef GLUE_DECIMAL_FLOAT
+/* _Decimal64 */
+
+int gmp_mpfr_sys_mpfr_set_decimal64(mpfr_ptr rop, const _Decimal64 *op,
+                                    mpfr_rnd_t rnd) {
+    return mpfr_set_decimal64(rop, *op, rnd);
+}
+
+void gmp_mpfr_sys_mpfr_get_decimal64(_Decimal64 *rop, mpfr_srcptr op,
+                                     mpfr_rnd_t rnd) {
+    *rop = mpfr_get_decimal64(op, rnd);
+}
+#endif
+
+#ifdef GLUE_MPC
+int gmp_mpfr_sys_mpc_set_ld(mpc_ptr rop, const long double *op, mpc_rnd_t rnd) {
+    return mpc_set_ld(rop, *op, rnd);
+}
+
+int gmp_mpfr_sys_mpc_set_ld_ld(mpc_ptr rop, const long double *op1,
+                               const long double *op2, mpc_rnd_t rnd) {
+    return mpc_set_ld_ld(rop, *op1, *op2, rnd);
+}
+
+int gmp_mpfr_sys_mpc_pow_ld(mpc_ptr rop, mpc_srcptr op1, const long double *op2,
+                            mpc_rnd_t rnd) {
+    return mpc_pow_ld(rop, op1, *op2, rnd);
+}
+
+int gmp_mpfr_sys_mpc_set_ldc(mpc_ptr rop, const long double _Complex *op,
+                             mpc_rnd_t rnd) {
+    return mpc_set_ldc(rop, *op, rnd);
+}
+
+void gmp_mpfr_sys_mpc_get_ldc(long double _Complex *rop, mpc_srcptr op,
+                              mpc_rnd_t rnd) {
+    *rop = mpc_get_ldc(op, rnd);
+}
+#endif
+"##;
+
 // prints part of the header
 const SYSTEM_MPC_C: &str = r##"/* system_mpc.c */
 #include <mpc.h>
@@ -0,0 +1,539 @@
+// Copyright © 2017–2020 University of Malta
+
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License
+// as published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License and a copy of the GNU General Public License along with
+// this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Helpers for the display adapters such as gmp::DisplayMpz.
+//
+// The digits are written by the get_str functions into a temporary
+// buffer, and the output is then described as a list of parts so that
+// its length is known before any padding is written. Long runs of
+// zeros and exponents are never stored in a buffer.
+
+use crate::gmp;
+use core::fmt::{Alignment, Formatter, Result as FmtResult, Write};
+use core::{ptr, slice, str};
+use libc::c_int;
+
+// Buffers up to this length are on the stack.
+const STACK_LEN: usize = 256;
+
+// A buffer allocated using the GMP memory functions.
+struct GmpBuf {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Drop for GmpBuf {
+    fn drop(&mut self) {
+        unsafe {
+            let mut free_func = None;
+            gmp::get_memory_functions(ptr::null_mut(), ptr::null_mut(), &mut free_func);
+            let free_func = free_func.expect("no GMP free function");
+            free_func(self.ptr as *mut _, self.len);
+        }
+    }
+}
+
+// Calls f with a zeroed buffer of len bytes, which is on the stack if
+// it is short enough, and otherwise allocated using the GMP memory
+// functions.
+pub(crate) fn with_buf<R>(len: usize, f: impl FnOnce(&mut [u8]) -> R) -> R {
+    if len <= STACK_LEN {
+        let mut stack = [0u8; STACK_LEN];
+        return f(&mut stack[..len]);
+    }
+    unsafe {
+        let mut alloc_func = None;
+        gmp::get_memory_functions(&mut alloc_func, ptr::null_mut(), ptr::null_mut());
+        let alloc_func = alloc_func.expect("no GMP allocation function");
+        let buf = GmpBuf {
+            ptr: alloc_func(len) as *mut u8,
+            len,
+        };
+        ptr::write_bytes(buf.ptr, 0, len);
+        f(slice::from_raw_parts_mut(buf.ptr, len))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Part<'a> {
+    Str(&'a str),
+    Zeros(usize),
+    // an exponent marker followed by the exponent in decimal
+    Exp(char, i64),
+}
+
+impl Part<'_> {
+    fn len(self) -> usize {
+        match self {
+            Part::Str(s) => s.len(),
+            Part::Zeros(n) => n,
+            Part::Exp(marker, exp) => {
+                let mut len = marker.len_utf8() + if exp < 0 { 2 } else { 1 };
+                let mut rest = exp / 10;
+                while rest != 0 {
+                    len += 1;
+                    rest /= 10;
+                }
+                len
+            }
+        }
+    }
+
+    fn write(self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Part::Str(s) => f.write_str(s),
+            Part::Zeros(mut n) => {
+                const ZEROS: &str = "00000000000000000000000000000000";
+                while n > 0 {
+                    let chunk = if n < ZEROS.len() { n } else { ZEROS.len() };
+                    f.write_str(&ZEROS[..chunk])?;
+                    n -= chunk;
+                }
+                Ok(())
+            }
+            Part::Exp(marker, exp) => {
+                f.write_char(marker)?;
+                write!(f, "{}", exp)
+            }
+        }
+    }
+}
+
+// Enough parts for two floating-point numbers in a complex number.
+const MAX_PARTS: usize = 24;
+
+pub(crate) struct Parts<'a> {
+    list: [Part<'a>; MAX_PARTS],
+    len: usize,
+}
+
+impl<'a> Parts<'a> {
+    pub(crate) fn new() -> Parts<'a> {
+        Parts {
+            list: [Part::Zeros(0); MAX_PARTS],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, part: Part<'a>) {
+        self.list[self.len] = part;
+        self.len += 1;
+    }
+
+    #[cfg(feature = "mpc")]
+    pub(crate) fn extend(&mut self, parts: &[Part<'a>]) {
+        for &part in parts {
+            self.push(part);
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[Part<'a>] {
+        &self.list[..self.len]
+    }
+}
+
+pub(crate) fn sign(f: &Formatter<'_>, negative: bool) -> &'static str {
+    if negative {
+        "-"
+    } else if f.sign_plus() {
+        "+"
+    } else {
+        ""
+    }
+}
+
+// Writes the sign, the prefix if the alternate flag is set, and the
+// parts, padded to the width. Padding with zeros is only done if
+// zero_pad is true, otherwise the fill character is used.
+pub(crate) fn pad(
+    f: &mut Formatter<'_>,
+    sign: &str,
+    prefix: &str,
+    parts: &[Part],
+    zero_pad: bool,
+) -> FmtResult {
+    let prefix = if f.alternate() { prefix } else { "" };
+    let len = sign.len() + prefix.len() + parts.iter().map(|p| p.len()).sum::<usize>();
+    let padding = match f.width() {
+        Some(width) if width > len => width - len,
+        _ => 0,
+    };
+    let (before, zeros, after) = if padding == 0 {
+        (0, 0, 0)
+    } else if zero_pad && f.sign_aware_zero_pad() {
+        (0, padding, 0)
+    } else {
+        match f.align() {
+            Some(Alignment::Left) => (0, 0, padding),
+            Some(Alignment::Center) => (padding / 2, 0, padding - padding / 2),
+            Some(Alignment::Right) | None => (padding, 0, 0),
+        }
+    };
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(sign)?;
+    f.write_str(prefix)?;
+    Part::Zeros(zeros).write(f)?;
+    for &part in parts {
+        part.write(f)?;
+    }
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+// Rounds the decimal digits to n digits, to nearest with ties to even.
+// Returns true if the rounding carried into a new digit, in which case
+// the first n digits are one followed by zeros.
+pub(crate) fn round_decimal(digits: &mut [u8], n: usize) -> bool {
+    let dropped = digits[n];
+    let up = dropped > b'5'
+        || (dropped == b'5'
+            && (digits[n + 1..].iter().any(|&d| d != b'0') || (digits[n - 1] & 1) == 1));
+    if !up {
+        return false;
+    }
+    for d in digits[..n].iter_mut().rev() {
+        if *d == b'9' {
+            *d = b'0';
+        } else {
+            *d += 1;
+            return false;
+        }
+    }
+    digits[0] = b'1';
+    true
+}
+
+// Pushes the digits in scientific notation, with frac digits after the
+// point if frac is not None, otherwise with all the digits.
+pub(crate) fn push_scientific<'a>(
+    parts: &mut Parts<'a>,
+    digits: &'a str,
+    exp: i64,
+    marker: char,
+    frac: Option<usize>,
+) {
+    parts.push(Part::Str(&digits[..1]));
+    let rest = &digits[1..];
+    let frac = frac.unwrap_or(rest.len());
+    if frac > 0 {
+        parts.push(Part::Str("."));
+        parts.push(Part::Str(rest));
+        parts.push(Part::Zeros(frac - rest.len()));
+    }
+    parts.push(Part::Exp(marker, exp));
+}
+
+// Pushes the digits in positional notation, where the point is exp
+// digits after the start of the digits. There are frac digits after
+// the point if frac is not None, otherwise all the digits are written.
+fn push_positional<'a>(parts: &mut Parts<'a>, digits: &'a str, exp: i64, frac: Option<usize>) {
+    let (lead_zeros, frac_digits) = if exp > 0 {
+        let int_len = exp as usize;
+        if digits.len() <= int_len {
+            parts.push(Part::Str(digits));
+            parts.push(Part::Zeros(int_len - digits.len()));
+            (0, "")
+        } else {
+            parts.push(Part::Str(&digits[..int_len]));
+            (0, &digits[int_len..])
+        }
+    } else {
+        parts.push(Part::Str("0"));
+        (exp.wrapping_neg() as usize, digits)
+    };
+    let (mut lead_zeros, mut frac_digits) = (lead_zeros, frac_digits);
+    let frac = frac.unwrap_or(lead_zeros + frac_digits.len());
+    // only needed if the digits were not rounded at the right place
+    if lead_zeros >= frac {
+        lead_zeros = frac;
+        frac_digits = "";
+    } else if lead_zeros + frac_digits.len() > frac {
+        frac_digits = &frac_digits[..frac - lead_zeros];
+    }
+    let frac_len = lead_zeros + frac_digits.len();
+    if frac > 0 {
+        parts.push(Part::Str("."));
+        parts.push(Part::Zeros(lead_zeros));
+        parts.push(Part::Str(frac_digits));
+        parts.push(Part::Zeros(frac - frac_len));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(not(feature = "mpfr"), allow(dead_code))]
+pub(crate) enum Class {
+    Nan,
+    Inf,
+    Zero,
+    Normal,
+}
+
+// A floating-point number that can write its significand digits.
+pub(crate) trait FloatDigits {
+    fn class(&self) -> Class;
+    fn negative(&self) -> bool;
+    // Returns a buffer length that is large enough for get with the
+    // same base and number of digits, where zero digits means the
+    // default number of digits.
+    fn buf_len(&self, base: c_int, n: usize) -> usize;
+    // Writes the significand digits rounded to nearest into buf, and
+    // returns the start and end of the digits without any sign, and
+    // the exponent such that the value is 0.digits × base^exp.
+    fn get(&self, buf: &mut [u8], base: c_int, n: usize) -> (usize, usize, i64);
+    // Returns the exponent like get, but without rounding up.
+    fn exp(&self, base: c_int) -> i64;
+    // Checks whether the significand 0.digits is more than one half.
+    fn above_half(&self, base: c_int) -> bool;
+}
+
+// Checks whether the significand 0.digits is more than one half,
+// returning None if it is exactly one half.
+pub(crate) fn digits_above_half(digits: &[u8], base: c_int) -> Option<bool> {
+    let half = base as u8 / 2;
+    let first = match digits[0] {
+        d @ b'0'..=b'9' => d - b'0',
+        d => (d | 0x20) - b'a' + 10,
+    };
+    if first != half {
+        Some(first > half)
+    } else if digits[1..].iter().any(|&d| d != b'0') {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Style {
+    // like %g with enough digits to read the number back
+    General,
+    // positional with this number of digits after the point
+    Fixed(usize),
+    // scientific with this number of digits after the point, or with
+    // enough digits to read the number back
+    Exp(Option<usize>),
+}
+
+// Calls g with the sign and the parts of x, and whether x is finite.
+pub(crate) fn with_float<T, R>(
+    x: &T,
+    plus: bool,
+    base: c_int,
+    upper: bool,
+    style: Style,
+    g: impl FnOnce(&str, &[Part], bool) -> R,
+) -> R
+where
+    T: FloatDigits + ?Sized,
+{
+    let sign = if x.negative() {
+        "-"
+    } else if plus {
+        "+"
+    } else {
+        ""
+    };
+    let marker = match (base, upper) {
+        (10, false) => 'e',
+        (10, true) => 'E',
+        _ => '@',
+    };
+    match x.class() {
+        Class::Nan => return g("", &[Part::Str("NaN")], false),
+        Class::Inf => return g(sign, &[Part::Str("inf")], false),
+        Class::Zero => {
+            let mut parts = Parts::new();
+            match style {
+                Style::General => push_positional(&mut parts, "0", 1, None),
+                Style::Fixed(frac) => push_positional(&mut parts, "0", 1, Some(frac)),
+                Style::Exp(frac) => push_scientific(&mut parts, "0", 0, marker, frac),
+            }
+            return g(sign, parts.as_slice(), true);
+        }
+        Class::Normal => {}
+    }
+    let n = match style {
+        Style::General | Style::Exp(None) => 0,
+        Style::Exp(Some(frac)) => frac + 1,
+        Style::Fixed(frac) => {
+            let n = x.exp(base) + frac as i64;
+            if n <= 0 {
+                // the result is either zero or one in the last place
+                let mut parts = Parts::new();
+                let one = n == 0 && x.above_half(base);
+                if frac == 0 {
+                    parts.push(Part::Str(if one { "1" } else { "0" }));
+                } else {
+                    parts.push(Part::Str("0."));
+                    if one {
+                        parts.push(Part::Zeros(frac - 1));
+                        parts.push(Part::Str("1"));
+                    } else {
+                        parts.push(Part::Zeros(frac));
+                    }
+                }
+                return g(sign, parts.as_slice(), true);
+            }
+            n as usize
+        }
+    };
+    with_buf(x.buf_len(base, n), |buf| {
+        let (start, end, exp) = x.get(buf, base, n);
+        let digits = &mut buf[start..end];
+        if upper {
+            digits.make_ascii_uppercase();
+        }
+        let len = match style {
+            Style::General | Style::Exp(None) => {
+                let zeros = digits[1..].iter().rev().take_while(|&&d| d == b'0').count();
+                digits.len() - zeros
+            }
+            _ => digits.len(),
+        };
+        let digits = str::from_utf8(&digits[..len]).expect("digits not ASCII");
+        let mut parts = Parts::new();
+        match style {
+            Style::General => {
+                let all_digits = (end - start) as i64;
+                if exp - 1 < -4 || exp > all_digits {
+                    push_scientific(&mut parts, digits, exp - 1, marker, None);
+                } else {
+                    push_positional(&mut parts, digits, exp, None);
+                }
+            }
+            Style::Fixed(frac) => push_positional(&mut parts, digits, exp, Some(frac)),
+            Style::Exp(frac) => push_scientific(&mut parts, digits, exp - 1, marker, frac),
+        }
+        g(sign, parts.as_slice(), true)
+    })
+}
+
+// Formats x using the style for the precision, where a negative base
+// means upper case.
+pub(crate) fn fmt_float<T>(
+    x: &T,
+    f: &mut Formatter<'_>,
+    base: c_int,
+    exp_style: bool,
+    prefix: &str,
+) -> FmtResult
+where
+    T: FloatDigits + ?Sized,
+{
+    let style = float_style(f, exp_style);
+    with_float(
+        x,
+        f.sign_plus(),
+        base.abs(),
+        base < 0,
+        style,
+        |sign, parts, finite| pad(f, sign, if finite { prefix } else { "" }, parts, finite),
+    )
+}
+
+// Formats a complex number as "(re im)" like mpc_get_str, where a
+// negative base means upper case.
+#[cfg(feature = "mpc")]
+pub(crate) fn fmt_complex<T>(
+    re: &T,
+    im: &T,
+    f: &mut Formatter<'_>,
+    base: c_int,
+    exp_style: bool,
+    prefix: &str,
+) -> FmtResult
+where
+    T: FloatDigits + ?Sized,
+{
+    let style = float_style(f, exp_style);
+    let (plus, upper) = (f.sign_plus(), base < 0);
+    let prefix = if f.alternate() { prefix } else { "" };
+    with_float(
+        re,
+        plus,
+        base.abs(),
+        upper,
+        style,
+        |re_sign, re_parts, re_finite| {
+            with_float(
+                im,
+                plus,
+                base.abs(),
+                upper,
+                style,
+                |im_sign, im_parts, im_finite| {
+                    let mut parts = Parts::new();
+                    parts.push(Part::Str("("));
+                    parts.push(Part::Str(re_sign));
+                    parts.push(Part::Str(if re_finite { prefix } else { "" }));
+                    parts.extend(re_parts);
+                    parts.push(Part::Str(" "));
+                    parts.push(Part::Str(im_sign));
+                    parts.push(Part::Str(if im_finite { prefix } else { "" }));
+                    parts.extend(im_parts);
+                    parts.push(Part::Str(")"));
+                    pad(f, "", "", parts.as_slice(), false)
+                },
+            )
+        },
+    )
+}
+
+fn float_style(f: &Formatter<'_>, exp_style: bool) -> Style {
+    match (exp_style, f.precision()) {
+        (true, frac) => Style::Exp(frac),
+        (false, Some(frac)) => Style::Fixed(frac),
+        (false, None) => Style::General,
+    }
+}
+
+// Implements the formatting traits for a display adapter using a
+// function with the same parameters as fmt_float except the number,
+// which is the field of the adapter. Without exponent notation, only
+// Display and the traits for bases 2, 8 and 16 are implemented.
+macro_rules! impl_fmt_traits {
+    ($Adapter:ident, $fmt:path) => {
+        impl_fmt_traits! { $Adapter, $fmt, no_exp }
+        impl core::fmt::LowerExp for $Adapter<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                $fmt(self.0, f, 10, true, "")
+            }
+        }
+        impl core::fmt::UpperExp for $Adapter<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                $fmt(self.0, f, -10, true, "")
+            }
+        }
+    };
+    ($Adapter:ident, $fmt:path, no_exp) => {
+        impl_fmt_traits! { $Adapter, $fmt; Display, 10, ""; Binary, 2, "0b"; Octal, 8, "0o" }
+        impl_fmt_traits! { $Adapter, $fmt; LowerHex, 16, "0x"; UpperHex, -16, "0x" }
+    };
+    ($Adapter:ident, $fmt:path; $($Trait:ident, $base:expr, $prefix:expr);*) => {
+        $(
+            impl core::fmt::$Trait for $Adapter<'_> {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    $fmt(self.0, f, $base, false, $prefix)
+                }
+            }
+        )*
+    };
+}
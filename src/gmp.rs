@@ -37,14 +37,25 @@ unsafe {
 */
 #![allow(non_camel_case_types)]
 
+use crate::display::{self, Class, FloatDigits, Part, Parts};
+#[cfg(feature = "owned")]
+use core::fmt::Display;
 use core::{
-    cmp::Ordering,
+    cell::Cell,
+    cmp::{self, Ordering},
+    convert::TryFrom,
     fmt::{Debug, Formatter, Result as FmtResult},
-    mem::MaybeUninit,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ops::Deref,
+    slice, str,
 };
 use libc::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_ushort, c_void, FILE};
+#[cfg(feature = "std")]
+use std::{string::String, vec::Vec};
 
 include!(concat!(env!("OUT_DIR"), "/gmp_h.rs"));
+include!(concat!(env!("OUT_DIR"), "/gmp_layout.rs"));
 
 extern "C" {
     /// See: [`mp_bits_per_limb`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/GMP-Basics.html#index-mp_005fbits_005fper_005flimb)
@@ -66,6 +77,20 @@ extern "C" {
 pub const CC: *const c_char = GMP_CC;
 /// See: [`__GMP_CFLAGS`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/GMP-Basics.html#index-_005f_005fGMP_005fCFLAGS)
 pub const CFLAGS: *const c_char = GMP_CFLAGS;
+/// How the linked GMP library was built.
+///
+///   * `"fat"`: built by this crate with `--enable-fat`, the default.
+///   * `"generic"`: built by this crate with `--disable-assembly`,
+///     using the `disable-assembly` feature.
+///   * `"system"`: a system library, using the `use-system-libs`
+///     feature.
+pub const BUILD_MODE: &str = BUILD_MODE_STR;
+#[cfg(feature = "use-system-libs")]
+const BUILD_MODE_STR: &str = "system";
+#[cfg(all(not(feature = "use-system-libs"), disable_assembly))]
+const BUILD_MODE_STR: &str = "generic";
+#[cfg(all(not(feature = "use-system-libs"), not(disable_assembly)))]
+const BUILD_MODE_STR: &str = "fat";
 
 /// See: [`GMP_NAIL_BITS`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Low_002dlevel-Functions.html#index-GMP_005fNAIL_005fBITS)
 pub const NAIL_BITS: c_int = GMP_NAIL_BITS;
@@ -98,7 +123,7 @@ pub type bitcnt_t = c_ulong;
 /// internals may change in new minor releases of this crate, though
 /// they will be kept unchanged for patch releases. Any code that
 /// makes use of these internals should list the dependency as
-/// `version = "~1.2"` inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// `version = "~2.0"` inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 #[repr(C)]
@@ -121,7 +146,7 @@ pub struct mpz_t {
 /// internals may change in new minor releases of this crate, though
 /// they will be kept unchanged for patch releases. Any code that
 /// makes use of these internals should list the dependency as
-/// `version = "~1.2"` inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// `version = "~2.0"` inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 ///
@@ -143,7 +168,7 @@ pub struct mpq_t {
 /// internals may change in new minor releases of this crate, though
 /// they will be kept unchanged for patch releases. Any code that
 /// makes use of these internals should list the dependency as
-/// `version = "~1.2"` inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// `version = "~2.0"` inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 #[repr(C)]
@@ -167,7 +192,7 @@ pub struct mpf_t {
 /// internals may change in new minor releases of this crate, though
 /// they will be kept unchanged for patch releases. Any code that
 /// makes use of these internals should list the dependency as
-/// `version = "~1.2"` inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// `version = "~2.0"` inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 #[repr(C)]
@@ -199,8 +224,8 @@ impl Debug for randstate_t {
 /// This type is considered internal details. These internals may
 /// change in new minor releases of this crate, though they will be
 /// kept unchanged for patch releases. Any code that makes use of
-/// these internals should list the dependency as `version = "~1.2"`
-/// inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// these internals should list the dependency as `version = "~2.0"`
+/// inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 /// [`randstate_t`]: struct.randstate_t.html
@@ -233,8 +258,8 @@ impl Debug for randseed_t {
 /// This type is considered internal details. These internals may
 /// change in new minor releases of this crate, though they will be
 /// kept unchanged for patch releases. Any code that makes use of
-/// these internals should list the dependency as `version = "~1.2"`
-/// inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// these internals should list the dependency as `version = "~2.0"`
+/// inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 /// [`algdata`]: struct.randstate_t.html#structfield.algdata
@@ -253,6 +278,126 @@ pub struct randfnptr_t {
     pub iset: Option<unsafe extern "C" fn(dst: *mut randstate_t, src: *const randstate_t)>,
 }
 
+/// A wrapper to format an [`mpz_t`] using `core::fmt`.
+///
+/// `Display` writes the number in decimal, `Binary`, `Octal`,
+/// `LowerHex` and `UpperHex` write it in base 2, 8 and 16 with a
+/// minus sign if it is negative, and `LowerExp` and `UpperExp`
+/// write it in decimal exponent notation. The width, fill, alignment
+/// and the `+`, `#` and `0` flags are supported like for primitive
+/// integers. With exponent notation, the precision is the number of
+/// digits after the point, rounded to nearest with ties to even.
+///
+/// The number must be initialized. Numbers with up to about 250
+/// digits are formatted using the stack only; longer numbers use a
+/// temporary buffer allocated using the GMP memory functions.
+///
+/// # Examples
+///
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use gmp_mpfr_sys::gmp::{self, DisplayMpz};
+/// unsafe {
+///     let mut z = MaybeUninit::uninit();
+///     gmp::mpz_init_set_si(z.as_mut_ptr(), -255);
+///     let mut z = z.assume_init();
+///     assert_eq!(format!("{}", DisplayMpz(&z)), "-255");
+///     assert_eq!(format!("{:#x}", DisplayMpz(&z)), "-0xff");
+///     assert_eq!(format!("{:>8}", DisplayMpz(&z)), "    -255");
+///     assert_eq!(format!("{:.1e}", DisplayMpz(&z)), "-2.6e2");
+///     gmp::mpz_clear(&mut z);
+/// }
+/// ```
+///
+/// [`mpz_t`]: struct.mpz_t.html
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayMpz<'a>(pub &'a mpz_t);
+
+/// A wrapper to format an [`mpq_t`] using `core::fmt`.
+///
+/// The number is written like [`mpq_get_str`] writes it, that is
+/// without the denominator if it is one, using base 10 for
+/// `Display` and base 2, 8 and 16 for `Binary`, `Octal`,
+/// `LowerHex` and `UpperHex`. `LowerExp` and `UpperExp` write
+/// the numerator and the denominator each in decimal exponent
+/// notation, like [`DisplayMpz`] does, with the precision applying to
+/// both. The width, fill, alignment and the `+`, `#` and `0` flags are
+/// supported like for primitive integers; the prefix for the `#` flag
+/// is written once, before the numerator.
+///
+/// The number must be initialized. Numbers with up to about 250
+/// digits are formatted using the stack only; longer numbers use a
+/// temporary buffer allocated using the GMP memory functions.
+///
+/// # Examples
+///
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use gmp_mpfr_sys::gmp::{self, DisplayMpq};
+/// unsafe {
+///     let mut q = MaybeUninit::uninit();
+///     gmp::mpq_init(q.as_mut_ptr());
+///     let mut q = q.assume_init();
+///     gmp::mpq_set_si(&mut q, -15, 16);
+///     assert_eq!(format!("{}", DisplayMpq(&q)), "-15/16");
+///     assert_eq!(format!("{:+X}", DisplayMpq(&q)), "-F/10");
+///     assert_eq!(format!("{:e}", DisplayMpq(&q)), "-1.5e1/1.6e1");
+///     gmp::mpq_set_si(&mut q, 5, 1);
+///     assert_eq!(format!("{:+05}", DisplayMpq(&q)), "+0005");
+///     gmp::mpq_clear(&mut q);
+/// }
+/// ```
+///
+/// [`DisplayMpz`]: struct.DisplayMpz.html
+/// [`mpq_get_str`]: fn.mpq_get_str.html
+/// [`mpq_t`]: struct.mpq_t.html
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayMpq<'a>(pub &'a mpq_t);
+
+/// A wrapper to format an [`mpf_t`] using `core::fmt`.
+///
+/// `Display` writes the number in decimal, `Binary`, `Octal`,
+/// `LowerHex` and `UpperHex` write it in base 2, 8 and 16, and
+/// `LowerExp` and `UpperExp` write it in decimal exponent
+/// notation. Without a precision, the number is written with the
+/// digits from [`mpf_get_str`] without trailing zeros, and except for
+/// exponent notation, in positional notation unless the exponent is
+/// less than −4 or not less than the number of digits, like the `%g`
+/// conversion in C. With a precision, it is the number of digits after
+/// the point. Exponents are written in decimal after `e` or `E` for
+/// base 10, and after `@` for the other bases, where they are powers
+/// of the base. The width, fill, alignment and the `+`, `#` and `0`
+/// flags are supported like for primitive floating-point numbers.
+///
+/// Like [`mpf_get_str`], the digits are not always correctly rounded.
+///
+/// The number must be initialized. Numbers with up to about 250
+/// digits are formatted using the stack only; longer numbers use a
+/// temporary buffer allocated using the GMP memory functions.
+///
+/// # Examples
+///
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use gmp_mpfr_sys::gmp::{self, DisplayMpf};
+/// unsafe {
+///     let mut f = MaybeUninit::uninit();
+///     gmp::mpf_init2(f.as_mut_ptr(), 64);
+///     let mut f = f.assume_init();
+///     gmp::mpf_set_d(&mut f, -1.25);
+///     assert_eq!(format!("{}", DisplayMpf(&f)), "-1.25");
+///     assert_eq!(format!("{:08.3}", DisplayMpf(&f)), "-001.250");
+///     assert_eq!(format!("{:e}", DisplayMpf(&f)), "-1.25e0");
+///     assert_eq!(format!("{:b}", DisplayMpf(&f)), "-1.01");
+///     gmp::mpf_clear(&mut f);
+/// }
+/// ```
+///
+/// [`mpf_get_str`]: fn.mpf_get_str.html
+/// [`mpf_t`]: struct.mpf_t.html
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayMpf<'a>(pub &'a mpf_t);
+
 // Types for function declarations in this file.
 
 type mpz_srcptr = *const mpz_t;
@@ -893,6 +1038,95 @@ extern "C" {
     #[link_name = "__gmpz_fits_sshort_p"]
     pub fn mpz_fits_sshort_p(op: mpz_srcptr) -> c_int;
 }
+const U128_LIMBS: usize = 128 / LIMB_BITS as usize;
+const I128_MAX: u128 = !0 >> 1;
+
+unsafe fn mpz_set_u128_abs(rop: mpz_ptr, abs: u128, neg: bool) {
+    let limbs = slice::from_raw_parts_mut(mpz_limbs_write(rop, U128_LIMBS as size_t), U128_LIMBS);
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        *limb = (abs >> (i * LIMB_BITS as usize)) as limb_t;
+    }
+    let size = U128_LIMBS as size_t;
+    mpz_limbs_finish(rop, if neg { -size } else { size });
+}
+
+/// Sets the value of `rop` from `op`, like [`mpz_set_ui`] for
+/// `u128`.
+///
+/// [`mpz_set_ui`]: fn.mpz_set_ui.html
+#[inline]
+pub unsafe fn mpz_set_u128(rop: mpz_ptr, op: u128) {
+    mpz_set_u128_abs(rop, op, false);
+}
+/// Sets the value of `rop` from `op`, like [`mpz_set_si`] for
+/// `i128`.
+///
+/// [`mpz_set_si`]: fn.mpz_set_si.html
+#[inline]
+pub unsafe fn mpz_set_i128(rop: mpz_ptr, op: i128) {
+    mpz_set_u128_abs(rop, op.wrapping_abs() as u128, op < 0);
+}
+/// Returns the 128 least significant bits of the absolute value of
+/// `op`, like [`mpz_get_ui`] for `u128`.
+///
+/// [`mpz_get_ui`]: fn.mpz_get_ui.html
+pub unsafe fn mpz_get_u128(op: mpz_srcptr) -> u128 {
+    let size = cmp::min(mpz_size(op), U128_LIMBS);
+    let mut abs = 0;
+    for i in 0..size {
+        abs |= u128::from(*(*op).d.add(i)) << (i * LIMB_BITS as usize);
+    }
+    abs
+}
+/// Returns `op` as an `i128`, like [`mpz_get_si`].
+///
+/// If `op` does not fit, the result has the sign of `op` and the 127
+/// least significant bits of the absolute value of `op`, as with
+/// [`mpz_get_si`]. Use [`mpz_fits_i128_p`] to check first.
+///
+/// [`mpz_fits_i128_p`]: fn.mpz_fits_i128_p.html
+/// [`mpz_get_si`]: fn.mpz_get_si.html
+pub unsafe fn mpz_get_i128(op: mpz_srcptr) -> i128 {
+    let abs = mpz_get_u128(op);
+    if (*op).size >= 0 {
+        (abs & I128_MAX) as i128
+    } else {
+        -1 - (abs.wrapping_sub(1) & I128_MAX) as i128
+    }
+}
+/// Returns non-zero if `op` fits in a `u128`, like
+/// [`mpz_fits_ulong_p`].
+///
+/// [`mpz_fits_ulong_p`]: fn.mpz_fits_ulong_p.html
+#[inline]
+pub unsafe fn mpz_fits_u128_p(op: mpz_srcptr) -> c_int {
+    let size = (*op).size;
+    if size >= 0 && size as usize <= U128_LIMBS {
+        1
+    } else {
+        0
+    }
+}
+/// Returns non-zero if `op` fits in an `i128`, like
+/// [`mpz_fits_slong_p`].
+///
+/// [`mpz_fits_slong_p`]: fn.mpz_fits_slong_p.html
+pub unsafe fn mpz_fits_i128_p(op: mpz_srcptr) -> c_int {
+    if mpz_size(op) > U128_LIMBS {
+        return 0;
+    }
+    let abs = mpz_get_u128(op);
+    let max = if (*op).size < 0 {
+        I128_MAX + 1
+    } else {
+        I128_MAX
+    };
+    if abs <= max {
+        1
+    } else {
+        0
+    }
+}
 /// See: [`mpz_odd_p`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Integer-Functions.html#index-mpz_005fodd_005fp)
 #[inline]
 pub unsafe extern "C" fn mpz_odd_p(op: mpz_srcptr) -> c_int {
@@ -954,6 +1188,617 @@ extern "C" {
     pub fn mpz_roinit_n(x: mpz_ptr, xp: mp_srcptr, xs: size_t) -> mpz_srcptr;
 }
 
+// Read-only Views
+
+/// A read-only view of an integer as an [`mpz_t`], which does not
+/// allocate.
+///
+/// The view can be created from a slice of limbs, which it borrows
+/// using [`mpz_roinit_n`], or from a primitive integer, whose limbs
+/// are stored inline in the view. It dereferences to an [`mpz_t`], so
+/// that a reference to it can be passed as a source operand to any
+/// function in this module. It must never be passed as a destination
+/// operand.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::gmp::{self, MpzRef};
+/// let limbs = [3, 1];
+/// let view = MpzRef::from_limbs(&limbs, true);
+/// let big = MpzRef::from_i128(-((1i128 << gmp::LIMB_BITS) + 3));
+/// unsafe {
+///     assert_eq!(gmp::mpz_cmp(&*view, &*big), 0);
+///     assert_eq!(gmp::mpz_cmp_si(&*MpzRef::from_u64(7), 7), 0);
+/// }
+/// ```
+///
+/// [`mpz_roinit_n`]: fn.mpz_roinit_n.html
+/// [`mpz_t`]: struct.mpz_t.html
+#[derive(Clone, Debug)]
+pub struct MpzRef<'a> {
+    // d points to inline when is_inline is true, and is fixed on
+    // access if the view has moved
+    raw: Cell<mpz_t>,
+    inline: [limb_t; U128_LIMBS],
+    is_inline: bool,
+    phantom: PhantomData<&'a [limb_t]>,
+}
+
+impl<'a> MpzRef<'a> {
+    /// Creates a view of the integer with the absolute value stored in
+    /// `limbs`, least significant limb first, and which is negative if
+    /// `negative` is true.
+    ///
+    /// High zero limbs are ignored, so that the view is normalized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limbs` has more limbs than an [`mpz_t`] can hold.
+    ///
+    /// [`mpz_t`]: struct.mpz_t.html
+    pub fn from_limbs(limbs: &'a [limb_t], negative: bool) -> MpzRef<'a> {
+        assert!(c_int::try_from(limbs.len()).is_ok(), "too many limbs");
+        let size = limbs.len() as size_t;
+        let mut raw = MaybeUninit::uninit();
+        let raw = unsafe {
+            mpz_roinit_n(
+                raw.as_mut_ptr(),
+                limbs.as_ptr(),
+                if negative { -size } else { size },
+            );
+            raw.assume_init()
+        };
+        MpzRef {
+            raw: Cell::new(raw),
+            inline: [0; U128_LIMBS],
+            is_inline: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns a const pointer to the inner [`mpz_t`].
+    ///
+    /// [`mpz_t`]: struct.mpz_t.html
+    #[inline]
+    pub fn as_raw(&self) -> *const mpz_t {
+        &**self
+    }
+}
+
+impl MpzRef<'static> {
+    fn from_u128_abs(abs: u128, negative: bool) -> MpzRef<'static> {
+        let mut inline = [0; U128_LIMBS];
+        let mut size = 0;
+        for (i, limb) in inline.iter_mut().enumerate() {
+            *limb = (abs >> (i * LIMB_BITS as usize)) as limb_t;
+            if *limb != 0 {
+                size = i as c_int + 1;
+            }
+        }
+        MpzRef {
+            raw: Cell::new(mpz_t {
+                alloc: 0,
+                size: if negative { -size } else { size },
+                d: inline.as_ptr() as *mut limb_t,
+            }),
+            inline,
+            is_inline: true,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a view of a `u64` value.
+    #[inline]
+    pub fn from_u64(val: u64) -> MpzRef<'static> {
+        MpzRef::from_u128_abs(u128::from(val), false)
+    }
+
+    /// Creates a view of an `i64` value.
+    #[inline]
+    pub fn from_i64(val: i64) -> MpzRef<'static> {
+        MpzRef::from_u128_abs(u128::from(val.wrapping_abs() as u64), val < 0)
+    }
+
+    /// Creates a view of a `u128` value.
+    #[inline]
+    pub fn from_u128(val: u128) -> MpzRef<'static> {
+        MpzRef::from_u128_abs(val, false)
+    }
+
+    /// Creates a view of an `i128` value.
+    #[inline]
+    pub fn from_i128(val: i128) -> MpzRef<'static> {
+        MpzRef::from_u128_abs(val.wrapping_abs() as u128, val < 0)
+    }
+}
+
+impl Deref for MpzRef<'_> {
+    type Target = mpz_t;
+    #[inline]
+    fn deref(&self) -> &mpz_t {
+        let raw = self.raw.as_ptr();
+        unsafe {
+            if self.is_inline {
+                // A view of a primitive keeps its limbs in the inline
+                // array, so d is stale only after the view was moved.
+                // The move ended every borrow of the view, so no
+                // mpz_t reference from an earlier deref sees this.
+                let d = self.inline.as_ptr() as *mut limb_t;
+                if (*raw).d != d {
+                    (*raw).d = d;
+                }
+            }
+            &*raw
+        }
+    }
+}
+
+// The view does not modify the limbs, and the pointer into inline is
+// fixed on access after moving to another thread.
+unsafe impl Send for MpzRef<'_> {}
+
+// Raw Format Without Streams
+
+const LIMB_BYTES: usize = mem::size_of::<limb_t>();
+
+fn ceil_div(n: usize, d: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        (n - 1) / d + 1
+    }
+}
+
+unsafe fn raw_magnitude_len(op: mpz_srcptr) -> usize {
+    let size = mpz_size(op);
+    if size == 0 {
+        return 0;
+    }
+    let top = *mpz_limbs_read(op).add(size - 1);
+    (size - 1) * LIMB_BYTES + LIMB_BYTES - top.leading_zeros() as usize / 8
+}
+
+/// Returns the number of bytes [`mpz_to_raw`] and [`mpz_out_raw`] write
+/// for `op`.
+///
+/// This is four bytes for the size followed by the bytes of the
+/// magnitude.
+///
+/// [`mpz_out_raw`]: fn.mpz_out_raw.html
+/// [`mpz_to_raw`]: fn.mpz_to_raw.html
+#[inline]
+pub unsafe fn mpz_raw_len(op: mpz_srcptr) -> usize {
+    4 + raw_magnitude_len(op)
+}
+
+/// Writes `op` into the beginning of `buf` in the format of
+/// [`mpz_out_raw`], and returns the number of bytes written.
+///
+/// The output is byte-for-byte identical to what [`mpz_out_raw`]
+/// writes to a stream: the byte count of the magnitude as a four-byte
+/// big-endian number, negated if `op` is negative, followed by the
+/// magnitude in big-endian order.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than [`mpz_raw_len`]`(op)`, or if the
+/// magnitude of `op` does not fit in 2<sup>31</sup>&nbsp;&minus;&nbsp;1
+/// bytes.
+///
+/// [`mpz_out_raw`]: fn.mpz_out_raw.html
+/// [`mpz_raw_len`]: fn.mpz_raw_len.html
+pub unsafe fn mpz_to_raw(op: mpz_srcptr, buf: &mut [u8]) -> usize {
+    let bytes = raw_magnitude_len(op);
+    assert!(bytes <= 0x7fff_ffff, "magnitude too large for raw format");
+    let len = 4 + bytes;
+    assert!(buf.len() >= len, "buffer too small for raw format");
+    let ssize = if (*op).size < 0 {
+        -(bytes as i32)
+    } else {
+        bytes as i32
+    };
+    buf[..4].copy_from_slice(&ssize.to_be_bytes());
+    if bytes == 0 {
+        return len;
+    }
+    let limbs = slice::from_raw_parts(mpz_limbs_read(op), mpz_size(op));
+    let mut end = len;
+    for limb in limbs {
+        let n = cmp::min(LIMB_BYTES, end - 4);
+        buf[end - n..end].copy_from_slice(&limb.to_be_bytes()[LIMB_BYTES - n..]);
+        end -= n;
+    }
+    len
+}
+
+/// Reads an integer in the format of [`mpz_out_raw`] from the
+/// beginning of `buf` into `rop`, and returns the number of bytes read.
+///
+/// This accepts the same input as [`mpz_inp_raw`]. If `buf` is too
+/// short to hold the size and the magnitude, `None` is returned and
+/// `rop` is not modified.
+///
+/// [`mpz_inp_raw`]: fn.mpz_inp_raw.html
+/// [`mpz_out_raw`]: fn.mpz_out_raw.html
+pub unsafe fn mpz_from_raw(rop: mpz_ptr, buf: &[u8]) -> Option<usize> {
+    if buf.len() < 4 {
+        return None;
+    }
+    let ssize = i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    let bytes = if ssize < 0 {
+        ssize.wrapping_neg() as u32 as usize
+    } else {
+        ssize as usize
+    };
+    if buf.len() - 4 < bytes {
+        return None;
+    }
+    let len = 4 + bytes;
+    let size = ceil_div(bytes, LIMB_BYTES);
+    if size > 0 {
+        let limbs = slice::from_raw_parts_mut(mpz_limbs_write(rop, size as size_t), size);
+        let mut end = len;
+        for limb in limbs {
+            let n = cmp::min(LIMB_BYTES, end - 4);
+            let mut be = limb_t::to_be_bytes(0);
+            be[LIMB_BYTES - n..].copy_from_slice(&buf[end - n..end]);
+            *limb = limb_t::from_be_bytes(be);
+            end -= n;
+        }
+    }
+    let size = size as size_t;
+    mpz_limbs_finish(rop, if ssize < 0 { -size } else { size });
+    Some(len)
+}
+
+// Byte Order Import and Export
+
+/// Returns the number of bytes required to store the absolute value of
+/// `op`, which is zero if `op` is zero.
+#[inline]
+pub unsafe fn mpz_bytes_len(op: mpz_srcptr) -> usize {
+    if (*op).size == 0 {
+        0
+    } else {
+        let bits = mpz_sizeinbase(op, 2);
+        ceil_div(bits, 8)
+    }
+}
+
+/// Returns the number of bytes required to store `op` in two's
+/// complement, which is zero if `op` is zero.
+pub unsafe fn mpz_signed_bytes_len(op: mpz_srcptr) -> usize {
+    if (*op).size == 0 {
+        return 0;
+    }
+    let bits = mpz_sizeinbase(op, 2);
+    // -2^n needs only n + 1 bits, other values need a sign bit
+    let is_neg_power_of_two = (*op).size < 0 && mpz_scan1(op, 0) as usize == bits - 1;
+    if is_neg_power_of_two {
+        ceil_div(bits, 8)
+    } else {
+        bits / 8 + 1
+    }
+}
+
+// Fast path when the bytes are in the same order as the limbs.
+#[cfg(all(target_endian = "little", not(nails)))]
+unsafe fn import_le(rop: mpz_ptr, bytes: &[u8]) {
+    let size = ceil_div(bytes.len(), LIMB_BYTES);
+    if size > 0 {
+        let d = mpz_limbs_write(rop, size as size_t);
+        *d.add(size - 1) = 0;
+        core::ptr::copy_nonoverlapping(bytes.as_ptr(), d as *mut u8, bytes.len());
+    }
+    mpz_limbs_finish(rop, size as size_t);
+}
+
+#[cfg(not(all(target_endian = "little", not(nails))))]
+unsafe fn import_le(rop: mpz_ptr, bytes: &[u8]) {
+    mpz_import(
+        rop,
+        bytes.len(),
+        -1,
+        1,
+        0,
+        0,
+        bytes.as_ptr() as *const c_void,
+    );
+}
+
+// Fast path when the bytes are in the same order as the limbs.
+#[cfg(all(target_endian = "little", not(nails)))]
+unsafe fn export_le(op: mpz_srcptr, buf: &mut [u8], len: usize) {
+    if len > 0 {
+        let bytes = slice::from_raw_parts(mpz_limbs_read(op) as *const u8, len);
+        buf[..len].copy_from_slice(bytes);
+    }
+}
+
+#[cfg(not(all(target_endian = "little", not(nails))))]
+unsafe fn export_le(op: mpz_srcptr, buf: &mut [u8], len: usize) {
+    let mut count = 0;
+    mpz_export(buf.as_mut_ptr() as *mut c_void, &mut count, -1, 1, 0, 0, op);
+    debug_assert_eq!(count, len);
+}
+
+// Writes the absolute value of op into the whole of buf, padded with
+// zeros, and returns whether it fits.
+unsafe fn export_bytes(op: mpz_srcptr, buf: &mut [u8], big_endian: bool) -> bool {
+    let len = mpz_bytes_len(op);
+    if len > buf.len() {
+        return false;
+    }
+    if big_endian {
+        let (pad, bytes) = buf.split_at_mut(buf.len() - len);
+        for b in pad {
+            *b = 0;
+        }
+        let mut count = 0;
+        mpz_export(
+            bytes.as_mut_ptr() as *mut c_void,
+            &mut count,
+            1,
+            1,
+            0,
+            0,
+            op,
+        );
+        debug_assert_eq!(count, len);
+    } else {
+        export_le(op, buf, len);
+        for b in &mut buf[len..] {
+            *b = 0;
+        }
+    }
+    true
+}
+
+// Negates a two's complement number in place.
+fn negate_bytes<'a, I>(bytes: I)
+where
+    I: Iterator<Item = &'a mut u8>,
+{
+    let mut carry = true;
+    for b in bytes {
+        let (val, overflow) = (!*b).overflowing_add(u8::from(carry));
+        *b = val;
+        carry = overflow;
+    }
+}
+
+unsafe fn export_signed_bytes(op: mpz_srcptr, buf: &mut [u8], big_endian: bool) -> bool {
+    if mpz_signed_bytes_len(op) > buf.len() || !export_bytes(op, buf, big_endian) {
+        return false;
+    }
+    if (*op).size < 0 {
+        if big_endian {
+            negate_bytes(buf.iter_mut().rev());
+        } else {
+            negate_bytes(buf.iter_mut());
+        }
+    }
+    true
+}
+
+// Sets rop to the two's complement number stored in len bytes when
+// rop has been set to the same bytes interpreted as unsigned.
+unsafe fn adjust_signed(rop: mpz_ptr, len: usize, msb: Option<&u8>) {
+    if let Some(&msb) = msb {
+        if msb & 0x80 != 0 {
+            // u - 2^bits = -((-u - 1) mod 2^bits + 1)
+            let bits = (len * 8) as bitcnt_t;
+            mpz_com(rop, rop);
+            mpz_fdiv_r_2exp(rop, rop, bits);
+            mpz_add_ui(rop, rop, 1);
+            mpz_neg(rop, rop);
+        }
+    }
+}
+
+/// Sets `rop` to the unsigned number stored in `bytes` in big-endian
+/// order.
+///
+/// This is [`mpz_import`] with one byte per word and the most
+/// significant byte first.
+///
+/// [`mpz_import`]: fn.mpz_import.html
+#[inline]
+pub unsafe fn mpz_from_be_bytes(rop: mpz_ptr, bytes: &[u8]) {
+    mpz_import(
+        rop,
+        bytes.len(),
+        1,
+        1,
+        0,
+        0,
+        bytes.as_ptr() as *const c_void,
+    );
+}
+/// Sets `rop` to the unsigned number stored in `bytes` in
+/// little-endian order.
+///
+/// This is [`mpz_import`] with one byte per word and the least
+/// significant byte first. On little-endian targets the bytes are
+/// copied straight into the limbs.
+///
+/// [`mpz_import`]: fn.mpz_import.html
+#[inline]
+pub unsafe fn mpz_from_le_bytes(rop: mpz_ptr, bytes: &[u8]) {
+    import_le(rop, bytes);
+}
+/// Sets `rop` to the two's complement number stored in `bytes` in
+/// big-endian order.
+///
+/// An empty slice is zero.
+pub unsafe fn mpz_from_signed_be_bytes(rop: mpz_ptr, bytes: &[u8]) {
+    mpz_from_be_bytes(rop, bytes);
+    adjust_signed(rop, bytes.len(), bytes.first());
+}
+/// Sets `rop` to the two's complement number stored in `bytes` in
+/// little-endian order.
+///
+/// An empty slice is zero.
+pub unsafe fn mpz_from_signed_le_bytes(rop: mpz_ptr, bytes: &[u8]) {
+    mpz_from_le_bytes(rop, bytes);
+    adjust_signed(rop, bytes.len(), bytes.last());
+}
+/// Writes the absolute value of `op` into the whole of `buf` in
+/// big-endian order, padded with leading zeros.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than [`mpz_bytes_len`]`(op)`.
+///
+/// [`mpz_bytes_len`]: fn.mpz_bytes_len.html
+pub unsafe fn mpz_to_be_bytes_into(op: mpz_srcptr, buf: &mut [u8]) {
+    assert!(export_bytes(op, buf, true), "buffer too small");
+}
+/// Writes the absolute value of `op` into the whole of `buf` in
+/// little-endian order, padded with trailing zeros.
+///
+/// On little-endian targets the bytes are copied straight from the
+/// limbs.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than [`mpz_bytes_len`]`(op)`.
+///
+/// [`mpz_bytes_len`]: fn.mpz_bytes_len.html
+pub unsafe fn mpz_to_le_bytes_into(op: mpz_srcptr, buf: &mut [u8]) {
+    assert!(export_bytes(op, buf, false), "buffer too small");
+}
+/// Writes `op` into the whole of `buf` in two's complement and
+/// big-endian order, sign extended.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than [`mpz_signed_bytes_len`]`(op)`.
+///
+/// [`mpz_signed_bytes_len`]: fn.mpz_signed_bytes_len.html
+pub unsafe fn mpz_to_signed_be_bytes_into(op: mpz_srcptr, buf: &mut [u8]) {
+    assert!(export_signed_bytes(op, buf, true), "buffer too small");
+}
+/// Writes `op` into the whole of `buf` in two's complement and
+/// little-endian order, sign extended.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than [`mpz_signed_bytes_len`]`(op)`.
+///
+/// [`mpz_signed_bytes_len`]: fn.mpz_signed_bytes_len.html
+pub unsafe fn mpz_to_signed_le_bytes_into(op: mpz_srcptr, buf: &mut [u8]) {
+    assert!(export_signed_bytes(op, buf, false), "buffer too small");
+}
+/// Returns the absolute value of `op` in big-endian order, without
+/// leading zeros.
+///
+/// This function is only available if the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub unsafe fn mpz_to_be_bytes_vec(op: mpz_srcptr) -> Vec<u8> {
+    let mut v = std::vec![0; mpz_bytes_len(op)];
+    mpz_to_be_bytes_into(op, &mut v);
+    v
+}
+/// Returns the absolute value of `op` in little-endian order, without
+/// trailing zeros.
+///
+/// This function is only available if the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub unsafe fn mpz_to_le_bytes_vec(op: mpz_srcptr) -> Vec<u8> {
+    let mut v = std::vec![0; mpz_bytes_len(op)];
+    mpz_to_le_bytes_into(op, &mut v);
+    v
+}
+/// Returns `op` in two's complement and big-endian order, using the
+/// least number of bytes.
+///
+/// This function is only available if the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub unsafe fn mpz_to_signed_be_bytes_vec(op: mpz_srcptr) -> Vec<u8> {
+    let mut v = std::vec![0; mpz_signed_bytes_len(op)];
+    mpz_to_signed_be_bytes_into(op, &mut v);
+    v
+}
+/// Returns `op` in two's complement and little-endian order, using the
+/// least number of bytes.
+///
+/// This function is only available if the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub unsafe fn mpz_to_signed_le_bytes_vec(op: mpz_srcptr) -> Vec<u8> {
+    let mut v = std::vec![0; mpz_signed_bytes_len(op)];
+    mpz_to_signed_le_bytes_into(op, &mut v);
+    v
+}
+
+// Digit Strings Without Allocation
+
+#[inline]
+fn check_get_str_base(base: c_int) {
+    assert!(
+        (2..=62).contains(&base) || (-36..=-2).contains(&base),
+        "base out of range"
+    );
+}
+
+// Returns the string before the NUL terminator written by one of the
+// get_str functions.
+pub(crate) unsafe fn str_before_nul(buf: &[u8]) -> &str {
+    let len = buf.iter().position(|&b| b == 0).expect("no NUL terminator");
+    str::from_utf8_unchecked(&buf[..len])
+}
+
+/// Returns a buffer length that is large enough for
+/// [`mpz_get_str_into`], including space for the sign and the NUL
+/// terminator.
+///
+/// Like [`mpz_sizeinbase`], the length can be one more than needed.
+///
+/// # Panics
+///
+/// Panics if `base` is not valid for [`mpz_get_str`].
+///
+/// [`mpz_get_str_into`]: fn.mpz_get_str_into.html
+/// [`mpz_get_str`]: fn.mpz_get_str.html
+/// [`mpz_sizeinbase`]: fn.mpz_sizeinbase.html
+pub unsafe fn mpz_get_str_len(base: c_int, op: mpz_srcptr) -> usize {
+    check_get_str_base(base);
+    mpz_sizeinbase(op, base.abs()) + 2
+}
+/// Writes the digits of `op` into `buf` like [`mpz_get_str`], and
+/// returns the written string without the NUL terminator.
+///
+/// # Panics
+///
+/// Panics if `base` is not valid for [`mpz_get_str`], or if `buf` is
+/// shorter than [`mpz_get_str_len`]`(base, op)`.
+///
+/// [`mpz_get_str_len`]: fn.mpz_get_str_len.html
+/// [`mpz_get_str`]: fn.mpz_get_str.html
+pub unsafe fn mpz_get_str_into(buf: &mut [u8], base: c_int, op: mpz_srcptr) -> &str {
+    assert!(buf.len() >= mpz_get_str_len(base, op), "buffer too small");
+    mpz_get_str(buf.as_mut_ptr() as *mut c_char, base, op);
+    str_before_nul(buf)
+}
+/// Returns the digits of `op` like [`mpz_get_str`], without using the
+/// GMP memory functions.
+///
+/// This function is only available if the `std` feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `base` is not valid for [`mpz_get_str`].
+///
+/// [`mpz_get_str`]: fn.mpz_get_str.html
+#[cfg(feature = "std")]
+pub unsafe fn mpz_get_string(base: c_int, op: mpz_srcptr) -> String {
+    let mut v = std::vec![0; mpz_get_str_len(base, op)];
+    let len = mpz_get_str_into(&mut v, base, op).len();
+    v.truncate(len);
+    String::from_utf8_unchecked(v)
+}
+
 // Rational numbers
 
 extern "C" {
@@ -1103,6 +1948,32 @@ pub unsafe extern "C" fn mpq_denref(op: mpq_ptr) -> mpz_ptr {
 pub unsafe extern "C" fn mpq_denref_const(op: mpq_srcptr) -> mpz_srcptr {
     (op as mpz_srcptr).offset(1)
 }
+/// Sets the value of `rop` to `op1`/`op2`, like [`mpq_set_ui`] for
+/// `u128`.
+///
+/// As with [`mpq_set_ui`], [`mpq_canonicalize`] must be called if
+/// `op1` and `op2` have common factors.
+///
+/// [`mpq_canonicalize`]: fn.mpq_canonicalize.html
+/// [`mpq_set_ui`]: fn.mpq_set_ui.html
+#[inline]
+pub unsafe fn mpq_set_u128(rop: mpq_ptr, op1: u128, op2: u128) {
+    mpz_set_u128(mpq_numref(rop), op1);
+    mpz_set_u128(mpq_denref(rop), op2);
+}
+/// Sets the value of `rop` to `op1`/`op2`, like [`mpq_set_si`] for
+/// `i128` and `u128`.
+///
+/// As with [`mpq_set_si`], [`mpq_canonicalize`] must be called if
+/// `op1` and `op2` have common factors.
+///
+/// [`mpq_canonicalize`]: fn.mpq_canonicalize.html
+/// [`mpq_set_si`]: fn.mpq_set_si.html
+#[inline]
+pub unsafe fn mpq_set_i128(rop: mpq_ptr, op1: i128, op2: u128) {
+    mpz_set_i128(mpq_numref(rop), op1);
+    mpz_set_u128(mpq_denref(rop), op2);
+}
 extern "C" {
     /// See: [`mpq_get_num`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Rational-Number-Functions.html#index-mpq_005fget_005fnum)
     #[link_name = "__gmpq_get_num"]
@@ -1127,6 +1998,55 @@ extern "C" {
     pub fn mpq_inp_str(rop: mpq_ptr, stream: *mut FILE, base: c_int) -> usize;
 }
 
+/// Returns a buffer length that is large enough for
+/// [`mpq_get_str_into`], including space for the sign, the slash and
+/// the NUL terminator.
+///
+/// # Panics
+///
+/// Panics if `base` is not valid for [`mpq_get_str`].
+///
+/// [`mpq_get_str_into`]: fn.mpq_get_str_into.html
+/// [`mpq_get_str`]: fn.mpq_get_str.html
+pub unsafe fn mpq_get_str_len(base: c_int, op: mpq_srcptr) -> usize {
+    check_get_str_base(base);
+    mpz_sizeinbase(mpq_numref_const(op), base.abs())
+        + mpz_sizeinbase(mpq_denref_const(op), base.abs())
+        + 3
+}
+/// Writes the digits of `op` into `buf` like [`mpq_get_str`], and
+/// returns the written string without the NUL terminator.
+///
+/// # Panics
+///
+/// Panics if `base` is not valid for [`mpq_get_str`], or if `buf` is
+/// shorter than [`mpq_get_str_len`]`(base, op)`.
+///
+/// [`mpq_get_str_len`]: fn.mpq_get_str_len.html
+/// [`mpq_get_str`]: fn.mpq_get_str.html
+pub unsafe fn mpq_get_str_into(buf: &mut [u8], base: c_int, op: mpq_srcptr) -> &str {
+    assert!(buf.len() >= mpq_get_str_len(base, op), "buffer too small");
+    mpq_get_str(buf.as_mut_ptr() as *mut c_char, base, op);
+    str_before_nul(buf)
+}
+/// Returns the digits of `op` like [`mpq_get_str`], without using the
+/// GMP memory functions.
+///
+/// This function is only available if the `std` feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `base` is not valid for [`mpq_get_str`].
+///
+/// [`mpq_get_str`]: fn.mpq_get_str.html
+#[cfg(feature = "std")]
+pub unsafe fn mpq_get_string(base: c_int, op: mpq_srcptr) -> String {
+    let mut v = std::vec![0; mpq_get_str_len(base, op)];
+    let len = mpq_get_str_into(&mut v, base, op).len();
+    v.truncate(len);
+    String::from_utf8_unchecked(v)
+}
+
 // Floating-point numbers
 
 // Initialization Functions
@@ -1774,10 +2694,651 @@ extern "C" {
     );
 }
 
+// Scoped Guards
+
+/// A guard that sets the default precision of [`mpf_t`] numbers and
+/// restores the previous default precision when dropped, including on
+/// unwinding.
+///
+/// Unlike the [MPFR] state, the default precision is global to the
+/// process, so other threads initializing numbers with [`mpf_init`]
+/// while the guard is alive also use the new precision.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::gmp::{self, MpfDefaultPrecGuard};
+/// let prec = unsafe { gmp::mpf_get_default_prec() };
+/// {
+///     let _guard = MpfDefaultPrecGuard::new(1000);
+///     assert!(unsafe { gmp::mpf_get_default_prec() } >= 1000);
+/// }
+/// assert_eq!(unsafe { gmp::mpf_get_default_prec() }, prec);
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`mpf_init`]: fn.mpf_init.html
+/// [`mpf_t`]: struct.mpf_t.html
+#[must_use = "the previous default precision is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct MpfDefaultPrecGuard {
+    prec: bitcnt_t,
+}
+
+impl MpfDefaultPrecGuard {
+    /// Saves the current default precision and sets it to at least
+    /// `prec` bits using [`mpf_set_default_prec`].
+    ///
+    /// [`mpf_set_default_prec`]: fn.mpf_set_default_prec.html
+    pub fn new(prec: bitcnt_t) -> MpfDefaultPrecGuard {
+        unsafe {
+            let guard = MpfDefaultPrecGuard {
+                prec: mpf_get_default_prec(),
+            };
+            mpf_set_default_prec(prec);
+            guard
+        }
+    }
+}
+
+impl Drop for MpfDefaultPrecGuard {
+    fn drop(&mut self) {
+        unsafe {
+            mpf_set_default_prec(self.prec);
+        }
+    }
+}
+
+// Formatting
+
+fn fmt_mpz(
+    op: &mpz_t,
+    f: &mut Formatter<'_>,
+    base: c_int,
+    exp_style: bool,
+    prefix: &str,
+) -> FmtResult {
+    if exp_style {
+        return fmt_mpz_exp(op, f, base < 0);
+    }
+    unsafe {
+        display::with_buf(mpz_get_str_len(base, op), |buf| {
+            let s = mpz_get_str_into(buf, base, op);
+            let negative = s.starts_with('-');
+            let digits = if negative { &s[1..] } else { s };
+            let sign = display::sign(f, negative);
+            display::pad(f, sign, prefix, &[Part::Str(digits)], true)
+        })
+    }
+}
+
+fn fmt_mpz_exp(op: &mpz_t, f: &mut Formatter<'_>, upper: bool) -> FmtResult {
+    unsafe {
+        display::with_buf(mpz_get_str_len(10, op), |buf| {
+            let mut parts = Parts::new();
+            let negative = push_mpz_exp(&mut parts, buf, op, f, upper);
+            let sign = display::sign(f, negative);
+            display::pad(f, sign, "", parts.as_slice(), true)
+        })
+    }
+}
+
+// Writes op in decimal into buf, which must have a length of at least
+// mpz_get_str_len(10, op), and pushes its absolute value in scientific
+// notation with the precision of f. Returns whether op is negative.
+unsafe fn push_mpz_exp<'a>(
+    parts: &mut Parts<'a>,
+    buf: &'a mut [u8],
+    op: &mpz_t,
+    f: &Formatter<'_>,
+    upper: bool,
+) -> bool {
+    let len = mpz_get_str_into(buf, 10, op).len();
+    let negative = buf[0] == b'-';
+    let digits = &mut buf[if negative { 1 } else { 0 }..len];
+    let mut exp = digits.len() as i64 - 1;
+    let n = match f.precision() {
+        Some(frac) => frac + 1,
+        None => {
+            let zeros = digits[1..].iter().rev().take_while(|&&d| d == b'0').count();
+            digits.len() - zeros
+        }
+    };
+    if n < digits.len() && display::round_decimal(digits, n) {
+        exp += 1;
+    }
+    let kept = cmp::min(n, digits.len());
+    let digits: &'a [u8] = digits;
+    let digits = str::from_utf8(&digits[..kept]).expect("digits not ASCII");
+    let marker = if upper { 'E' } else { 'e' };
+    display::push_scientific(parts, digits, exp, marker, Some(n - 1));
+    negative
+}
+
+impl_fmt_traits! { DisplayMpz, fmt_mpz }
+
+fn fmt_mpq(
+    op: &mpq_t,
+    f: &mut Formatter<'_>,
+    base: c_int,
+    exp_style: bool,
+    prefix: &str,
+) -> FmtResult {
+    if exp_style {
+        return fmt_mpq_exp(op, f, base < 0);
+    }
+    unsafe {
+        display::with_buf(mpq_get_str_len(base, op), |buf| {
+            let s = mpq_get_str_into(buf, base, op);
+            let negative = s.starts_with('-');
+            let digits = if negative { &s[1..] } else { s };
+            let sign = display::sign(f, negative);
+            display::pad(f, sign, prefix, &[Part::Str(digits)], true)
+        })
+    }
+}
+
+// Writes the numerator and the denominator, if it is not one, in
+// decimal exponent notation.
+fn fmt_mpq_exp(op: &mpq_t, f: &mut Formatter<'_>, upper: bool) -> FmtResult {
+    unsafe {
+        let num = &*mpq_numref_const(op);
+        let den = &*mpq_denref_const(op);
+        display::with_buf(mpz_get_str_len(10, num), |num_buf| {
+            display::with_buf(mpz_get_str_len(10, den), |den_buf| {
+                let mut parts = Parts::new();
+                let negative = push_mpz_exp(&mut parts, num_buf, num, f, upper);
+                if mpz_cmp_ui(den, 1) != 0 {
+                    parts.push(Part::Str("/"));
+                    push_mpz_exp(&mut parts, den_buf, den, f, upper);
+                }
+                let sign = display::sign(f, negative);
+                display::pad(f, sign, "", parts.as_slice(), true)
+            })
+        })
+    }
+}
+
+impl_fmt_traits! { DisplayMpq, fmt_mpq }
+
+// Returns the number of digits that mpf_get_str writes when n_digits
+// is zero, or more.
+fn mpf_default_digits(op: &mpf_t, base: c_int) -> usize {
+    let bits = (op.prec as usize + 1) * LIMB_BITS as usize;
+    let min_bits_per_digit = 31 - (base as u32).leading_zeros() as usize;
+    bits / min_bits_per_digit + 2
+}
+
+impl FloatDigits for mpf_t {
+    fn class(&self) -> Class {
+        if self.size == 0 {
+            Class::Zero
+        } else {
+            Class::Normal
+        }
+    }
+
+    fn negative(&self) -> bool {
+        self.size < 0
+    }
+
+    fn buf_len(&self, base: c_int, n: usize) -> usize {
+        if n == 0 {
+            mpf_default_digits(self, base) + 2
+        } else {
+            n + 2
+        }
+    }
+
+    fn get(&self, buf: &mut [u8], base: c_int, n: usize) -> (usize, usize, i64) {
+        let mut exp = 0;
+        unsafe {
+            mpf_get_str(buf.as_mut_ptr() as *mut c_char, &mut exp, base, n, self);
+            let len = str_before_nul(buf).len();
+            let start = if buf[0] == b'-' { 1 } else { 0 };
+            (start, len, i64::from(exp))
+        }
+    }
+
+    fn exp(&self, base: c_int) -> i64 {
+        display::with_buf(self.buf_len(base, 0), |buf| self.get(buf, base, 0).2)
+    }
+
+    fn above_half(&self, base: c_int) -> bool {
+        display::with_buf(self.buf_len(base, 0), |buf| {
+            let (start, end, _) = self.get(buf, base, 0);
+            display::digits_above_half(&buf[start..end], base).unwrap_or(false)
+        })
+    }
+}
+
+fn fmt_mpf(
+    op: &mpf_t,
+    f: &mut Formatter<'_>,
+    base: c_int,
+    exp_style: bool,
+    prefix: &str,
+) -> FmtResult {
+    display::fmt_float(op, f, base, exp_style, prefix)
+}
+
+impl_fmt_traits! { DisplayMpf, fmt_mpf }
+
+// Owned types
+
+/// An owned, initialized [`mpz_t`] that is cleared when dropped.
+///
+/// The value can be passed to any function in this module using
+/// [`as_raw`] and [`as_raw_mut`]. Cloning uses [`mpz_init_set`], and
+/// `clone_from` uses [`mpz_set`] so that the existing allocation is
+/// reused when it is large enough.
+///
+/// The arithmetic, bitwise, shift and comparison operators are
+/// implemented for owned and borrowed integers, and for the primitive
+/// integer types up to 64 bits including `isize` and `usize`, using
+/// functions such as [`mpz_add`], [`mpz_add_ui`] and [`mpz_cmp_ui`].
+/// A 64-bit primitive that does not fit in `c_ulong`, which has 32
+/// bits on some targets, is converted to a temporary integer. When an
+/// operand is owned, its allocation is reused for the result. Like for
+/// primitive integers, division and remainder truncate, that is they
+/// use [`mpz_tdiv_q`] and [`mpz_tdiv_r`] rather than the floor
+/// functions, and panic if the divisor is zero, while right shifts
+/// round towards −∞. `Hash`
+/// is implemented using the sign and the limbs.
+///
+/// This type is only available if the `owned` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::gmp::{self, Mpz};
+/// let mut z = Mpz::new();
+/// unsafe {
+///     gmp::mpz_set_ui(z.as_raw_mut(), 15);
+///     gmp::mpz_mul_ui(z.as_raw_mut(), z.as_raw(), 3);
+/// }
+/// let copy = z.clone();
+/// assert_eq!(unsafe { gmp::mpz_get_ui(copy.as_raw()) }, 45);
+/// assert_eq!(format!("{}", copy), "45");
+///
+/// let sum = (z << 2) + &copy - 1u32;
+/// assert_eq!(sum, 224);
+/// assert_eq!(-&sum % 10, -4);
+/// assert!(sum > copy);
+/// ```
+///
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`as_raw`]: #method.as_raw
+/// [`mpz_add_ui`]: fn.mpz_add_ui.html
+/// [`mpz_add`]: fn.mpz_add.html
+/// [`mpz_cmp_ui`]: fn.mpz_cmp_ui.html
+/// [`mpz_init_set`]: fn.mpz_init_set.html
+/// [`mpz_set`]: fn.mpz_set.html
+/// [`mpz_t`]: struct.mpz_t.html
+/// [`mpz_tdiv_q`]: fn.mpz_tdiv_q.html
+/// [`mpz_tdiv_r`]: fn.mpz_tdiv_r.html
+#[cfg(feature = "owned")]
+pub struct Mpz {
+    inner: mpz_t,
+}
+
+#[cfg(feature = "owned")]
+impl_owned! { Mpz, mpz_t, mpz_clear }
+
+#[cfg(feature = "owned")]
+impl Mpz {
+    /// Creates an integer with the value 0 using [`mpz_init`].
+    ///
+    /// [`mpz_init`]: fn.mpz_init.html
+    #[inline]
+    pub fn new() -> Mpz {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            mpz_init(inner.as_mut_ptr());
+            Mpz {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Default for Mpz {
+    #[inline]
+    fn default() -> Mpz {
+        Mpz::new()
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Clone for Mpz {
+    #[inline]
+    fn clone(&self) -> Mpz {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            mpz_init_set(inner.as_mut_ptr(), &self.inner);
+            Mpz {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Mpz) {
+        unsafe {
+            mpz_set(&mut self.inner, &source.inner);
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Debug for Mpz {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpz(&self.inner), f)
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Display for Mpz {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpz(&self.inner), f)
+    }
+}
+
+/// An owned, initialized [`mpq_t`] that is cleared when dropped.
+///
+/// The value can be passed to any function in this module using
+/// [`as_raw`] and [`as_raw_mut`]. Cloning and `clone_from` use
+/// [`mpq_set`].
+///
+/// This type is only available if the `owned` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::gmp::{self, Mpq};
+/// let mut q = Mpq::new();
+/// unsafe {
+///     gmp::mpq_set_si(q.as_raw_mut(), -6, 8);
+///     gmp::mpq_canonicalize(q.as_raw_mut());
+/// }
+/// assert_eq!(format!("{}", q.clone()), "-3/4");
+/// ```
+///
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`as_raw`]: #method.as_raw
+/// [`mpq_set`]: fn.mpq_set.html
+/// [`mpq_t`]: struct.mpq_t.html
+#[cfg(feature = "owned")]
+pub struct Mpq {
+    inner: mpq_t,
+}
+
+#[cfg(feature = "owned")]
+impl_owned! { Mpq, mpq_t, mpq_clear }
+
+#[cfg(feature = "owned")]
+impl Mpq {
+    /// Creates a rational number with the value 0 using [`mpq_init`].
+    ///
+    /// [`mpq_init`]: fn.mpq_init.html
+    #[inline]
+    pub fn new() -> Mpq {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            mpq_init(inner.as_mut_ptr());
+            Mpq {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Default for Mpq {
+    #[inline]
+    fn default() -> Mpq {
+        Mpq::new()
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Clone for Mpq {
+    #[inline]
+    fn clone(&self) -> Mpq {
+        let mut ret = Mpq::new();
+        ret.clone_from(self);
+        ret
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Mpq) {
+        unsafe {
+            mpq_set(&mut self.inner, &source.inner);
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Debug for Mpq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpq(&self.inner), f)
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Display for Mpq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpq(&self.inner), f)
+    }
+}
+
+/// An owned, initialized [`mpf_t`] that is cleared when dropped.
+///
+/// The value can be passed to any function in this module using
+/// [`as_raw`] and [`as_raw_mut`]. Cloning preserves the precision:
+/// unlike [`mpf_init_set`], which uses the default precision, it uses
+/// [`mpf_init2`] with the precision of the source followed by
+/// [`mpf_set`]. `clone_from` sets the precision to that of the
+/// source if they differ.
+///
+/// This type is only available if the `owned` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::gmp::{self, Mpf};
+/// let mut f = Mpf::new(200);
+/// unsafe {
+///     gmp::mpf_set_d(f.as_raw_mut(), 2.5);
+/// }
+/// let copy = f.clone();
+/// unsafe {
+///     assert_eq!(gmp::mpf_get_prec(copy.as_raw()), gmp::mpf_get_prec(f.as_raw()));
+/// }
+/// assert_eq!(format!("{}", copy), "2.5");
+/// ```
+///
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`as_raw`]: #method.as_raw
+/// [`mpf_init2`]: fn.mpf_init2.html
+/// [`mpf_init_set`]: fn.mpf_init_set.html
+/// [`mpf_set`]: fn.mpf_set.html
+/// [`mpf_t`]: struct.mpf_t.html
+#[cfg(feature = "owned")]
+pub struct Mpf {
+    inner: mpf_t,
+}
+
+#[cfg(feature = "owned")]
+impl_owned! { Mpf, mpf_t, mpf_clear }
+
+#[cfg(feature = "owned")]
+impl Mpf {
+    /// Creates a floating-point number with the value 0 and a
+    /// precision of at least `prec` bits using [`mpf_init2`].
+    ///
+    /// [`mpf_init2`]: fn.mpf_init2.html
+    #[inline]
+    pub fn new(prec: bitcnt_t) -> Mpf {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            mpf_init2(inner.as_mut_ptr(), prec);
+            Mpf {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Default for Mpf {
+    /// Creates a floating-point number with the value 0 and the
+    /// default precision using [`mpf_init`].
+    ///
+    /// [`mpf_init`]: fn.mpf_init.html
+    #[inline]
+    fn default() -> Mpf {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            mpf_init(inner.as_mut_ptr());
+            Mpf {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Clone for Mpf {
+    #[inline]
+    fn clone(&self) -> Mpf {
+        let mut ret = Mpf::new(unsafe { mpf_get_prec(&self.inner) });
+        ret.clone_from(self);
+        ret
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Mpf) {
+        unsafe {
+            let prec = mpf_get_prec(&source.inner);
+            if mpf_get_prec(&self.inner) != prec {
+                mpf_set_prec(&mut self.inner, prec);
+            }
+            mpf_set(&mut self.inner, &source.inner);
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Debug for Mpf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpf(&self.inner), f)
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Display for Mpf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpf(&self.inner), f)
+    }
+}
+
+/// An owned, initialized [`randstate_t`] that is cleared when
+/// dropped.
+///
+/// The state can be passed to any function in this module using
+/// [`as_raw`] and [`as_raw_mut`]. States initialized with algorithms
+/// other than the default can be owned using [`from_raw`]. Cloning
+/// uses [`randinit_set`], so that the clone generates the same
+/// sequence.
+///
+/// This type is only available if the `owned` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::gmp::{self, Mpz, RandState};
+/// let mut state = RandState::new();
+/// unsafe {
+///     gmp::randseed_ui(state.as_raw_mut(), 42);
+/// }
+/// let mut copy = state.clone();
+/// let (mut a, mut b) = (Mpz::new(), Mpz::new());
+/// unsafe {
+///     gmp::mpz_urandomb(a.as_raw_mut(), state.as_raw_mut(), 100);
+///     gmp::mpz_urandomb(b.as_raw_mut(), copy.as_raw_mut(), 100);
+///     assert_eq!(gmp::mpz_cmp(a.as_raw(), b.as_raw()), 0);
+/// }
+/// ```
+///
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`as_raw`]: #method.as_raw
+/// [`from_raw`]: #method.from_raw
+/// [`randinit_set`]: fn.randinit_set.html
+/// [`randstate_t`]: struct.randstate_t.html
+#[cfg(feature = "owned")]
+pub struct RandState {
+    inner: randstate_t,
+}
+
+#[cfg(feature = "owned")]
+impl_owned! { RandState, randstate_t, randclear }
+
+#[cfg(feature = "owned")]
+impl RandState {
+    /// Creates a random state with the default algorithm using
+    /// [`randinit_default`].
+    ///
+    /// [`randinit_default`]: fn.randinit_default.html
+    #[inline]
+    pub fn new() -> RandState {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            randinit_default(inner.as_mut_ptr());
+            RandState {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Default for RandState {
+    #[inline]
+    fn default() -> RandState {
+        RandState::new()
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Clone for RandState {
+    #[inline]
+    fn clone(&self) -> RandState {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            randinit_set(inner.as_mut_ptr(), &self.inner);
+            RandState {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Debug for RandState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("RandState")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::gmp;
-    use core::mem;
+    use core::mem::{self, MaybeUninit};
+    use libc::{c_char, c_void};
 
     #[test]
     fn check_mpq_num_den_offsets() {
@@ -1825,4 +3386,563 @@ mod tests {
             assert_eq!(unsafe { tests::str_from_cstr(gmp::version) }, version);
         }
     }
+
+    #[test]
+    fn check_raw() {
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init(z.as_mut_ptr());
+            let mut z = z.assume_init();
+            let mut w = MaybeUninit::uninit();
+            gmp::mpz_init(w.as_mut_ptr());
+            let mut w = w.assume_init();
+            let file = libc::tmpfile();
+            assert!(!file.is_null());
+            let mut from_c = [0u8; 64];
+            let mut from_rust = [0u8; 64];
+            let values: &[&[u8]] = &[
+                b"0\0",
+                b"1\0",
+                b"-1\0",
+                b"255\0",
+                b"-256\0",
+                b"0xffffffff\0",
+                b"-0x100000000\0",
+                b"0xffffffffffffffff\0",
+                b"0x10000000000000000\0",
+                b"-0x123456789abcdef0123456789abcdef0123\0",
+                b"0x80000000000000000000000000000000000000000000000000\0",
+            ];
+            for value in values {
+                let ret = gmp::mpz_set_str(&mut z, value.as_ptr() as *const c_char, 0);
+                assert_eq!(ret, 0);
+
+                libc::rewind(file);
+                let c_len = gmp::mpz_out_raw(file, &z);
+                libc::rewind(file);
+                let read = libc::fread(from_c.as_mut_ptr() as *mut c_void, 1, c_len, file);
+                assert_eq!(read, c_len);
+
+                let len = gmp::mpz_to_raw(&z, &mut from_rust);
+                assert_eq!(len, gmp::mpz_raw_len(&z));
+                assert_eq!(&from_rust[..len], &from_c[..c_len]);
+
+                gmp::mpz_set_si(&mut w, 7);
+                assert_eq!(gmp::mpz_from_raw(&mut w, &from_c[..len - 1]), None);
+                assert_eq!(gmp::mpz_cmp_si(&w, 7), 0);
+                assert_eq!(gmp::mpz_from_raw(&mut w, &from_c), Some(len));
+                assert_eq!(gmp::mpz_cmp(&w, &z), 0);
+
+                gmp::mpz_set_si(&mut w, 7);
+                libc::rewind(file);
+                let written = libc::fwrite(from_rust.as_ptr() as *const c_void, 1, len, file);
+                assert_eq!(written, len);
+                libc::rewind(file);
+                assert_eq!(gmp::mpz_inp_raw(&mut w, file), len);
+                assert_eq!(gmp::mpz_cmp(&w, &z), 0);
+            }
+
+            // leading zero bytes in the magnitude are accepted
+            let padded = [0xff, 0xff, 0xff, 0xfb, 0, 0, 0, 1, 2, 0xff];
+            assert_eq!(gmp::mpz_from_raw(&mut w, &padded), Some(9));
+            assert_eq!(gmp::mpz_cmp_si(&w, -0x0102), 0);
+
+            libc::fclose(file);
+            gmp::mpz_clear(&mut w);
+            gmp::mpz_clear(&mut z);
+        }
+    }
+
+    #[test]
+    fn check_u128() {
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init(z.as_mut_ptr());
+            let mut z = z.assume_init();
+            let mut w = MaybeUninit::uninit();
+            gmp::mpz_init(w.as_mut_ptr());
+            let mut w = w.assume_init();
+
+            let umax = !0u128;
+            let imax = (umax >> 1) as i128;
+            let imin = -imax - 1;
+            let unsigned: [(u128, &[u8]); 5] = [
+                (0, b"0\0"),
+                (1, b"1\0"),
+                (1 << 64, b"0x10000000000000000\0"),
+                (imax as u128, b"0x7fffffffffffffffffffffffffffffff\0"),
+                (umax, b"0xffffffffffffffffffffffffffffffff\0"),
+            ];
+            for &(u, s) in &unsigned {
+                gmp::mpz_set_u128(&mut z, u);
+                assert_eq!(gmp::mpz_set_str(&mut w, s.as_ptr() as *const c_char, 0), 0);
+                assert_eq!(gmp::mpz_cmp(&z, &w), 0);
+                assert_eq!(gmp::mpz_get_u128(&z), u);
+                assert_ne!(gmp::mpz_fits_u128_p(&z), 0);
+                assert_eq!(gmp::mpz_fits_i128_p(&z) != 0, u <= imax as u128);
+            }
+            let signed: [(i128, &[u8]); 4] = [
+                (-1, b"-1\0"),
+                (-(1 << 64), b"-0x10000000000000000\0"),
+                (imin + 1, b"-0x7fffffffffffffffffffffffffffffff\0"),
+                (imin, b"-0x80000000000000000000000000000000\0"),
+            ];
+            for &(i, s) in &signed {
+                gmp::mpz_set_i128(&mut z, i);
+                assert_eq!(gmp::mpz_set_str(&mut w, s.as_ptr() as *const c_char, 0), 0);
+                assert_eq!(gmp::mpz_cmp(&z, &w), 0);
+                assert_eq!(gmp::mpz_get_i128(&z), i);
+                assert_eq!(gmp::mpz_get_u128(&z), i.wrapping_abs() as u128);
+                assert_ne!(gmp::mpz_fits_i128_p(&z), 0);
+                assert_eq!(gmp::mpz_fits_u128_p(&z), 0);
+            }
+
+            // like mpz_get_si, values that do not fit keep their sign
+            gmp::mpz_set_u128(&mut z, umax);
+            assert_eq!(gmp::mpz_get_i128(&z), imax);
+            gmp::mpz_neg(&mut z, &z);
+            assert_eq!(gmp::mpz_fits_i128_p(&z), 0);
+            assert_eq!(gmp::mpz_get_i128(&z), imin + 1);
+            gmp::mpz_mul_2exp(&mut z, &z, 1);
+            assert_eq!(gmp::mpz_fits_u128_p(&z), 0);
+            assert_eq!(gmp::mpz_get_u128(&z), umax - 1);
+
+            let mut q = MaybeUninit::uninit();
+            gmp::mpq_init(q.as_mut_ptr());
+            let mut q = q.assume_init();
+            gmp::mpq_set_i128(&mut q, -(1 << 100), umax);
+            assert_eq!(gmp::mpz_get_i128(gmp::mpq_numref_const(&q)), -(1 << 100));
+            assert_eq!(gmp::mpz_get_u128(gmp::mpq_denref_const(&q)), umax);
+            gmp::mpq_set_u128(&mut q, umax, 1 << 70);
+            assert_eq!(gmp::mpz_get_u128(gmp::mpq_numref_const(&q)), umax);
+            assert_eq!(gmp::mpz_get_u128(gmp::mpq_denref_const(&q)), 1 << 70);
+            gmp::mpq_clear(&mut q);
+
+            gmp::mpz_clear(&mut w);
+            gmp::mpz_clear(&mut z);
+        }
+    }
+
+    #[test]
+    fn check_bytes() {
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init(z.as_mut_ptr());
+            let mut z = z.assume_init();
+            let mut w = MaybeUninit::uninit();
+            gmp::mpz_init(w.as_mut_ptr());
+            let mut w = w.assume_init();
+
+            let mut seed = 1u32;
+            let mut be = [0u8; 40];
+            let mut le = [0u8; 40];
+            let mut buf = [0u8; 43];
+            for len in 0..=be.len() {
+                for pattern in 0..5 {
+                    for (i, b) in be[..len].iter_mut().enumerate() {
+                        *b = match pattern {
+                            0 => 0xff,
+                            1 if i == 0 => 0x80,
+                            2 if i == 0 => 0x01,
+                            1 | 2 => 0,
+                            3 if i == 0 => 0x00,
+                            _ => {
+                                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                                (seed >> 16) as u8
+                            }
+                        };
+                    }
+                    let be = &be[..len];
+                    let le = &mut le[..len];
+                    for (l, b) in le.iter_mut().zip(be.iter().rev()) {
+                        *l = *b;
+                    }
+
+                    // unsigned import agrees with mpz_import for all word
+                    // sizes and both byte orders
+                    gmp::mpz_from_be_bytes(&mut z, be);
+                    gmp::mpz_from_le_bytes(&mut w, le);
+                    assert_eq!(gmp::mpz_cmp(&z, &w), 0);
+                    for &size in &[1, 2, 4, 8] {
+                        if len % size != 0 {
+                            continue;
+                        }
+                        let ptr = be.as_ptr() as *const c_void;
+                        gmp::mpz_import(&mut w, len / size, 1, size, 1, 0, ptr);
+                        assert_eq!(gmp::mpz_cmp(&z, &w), 0);
+                        let ptr = le.as_ptr() as *const c_void;
+                        gmp::mpz_import(&mut w, len / size, -1, size, -1, 0, ptr);
+                        assert_eq!(gmp::mpz_cmp(&z, &w), 0);
+                    }
+
+                    // unsigned export pads with zeros
+                    let stripped = be.iter().position(|&b| b != 0).unwrap_or(len);
+                    assert_eq!(gmp::mpz_bytes_len(&z), len - stripped);
+                    let pad = buf.len() - len;
+                    gmp::mpz_to_be_bytes_into(&z, &mut buf);
+                    assert!(buf[..pad].iter().all(|&b| b == 0));
+                    assert_eq!(&buf[pad..], be);
+                    gmp::mpz_to_le_bytes_into(&z, &mut buf);
+                    assert_eq!(&buf[..len], &*le);
+                    assert!(buf[len..].iter().all(|&b| b == 0));
+                    gmp::mpz_neg(&mut w, &z);
+                    gmp::mpz_to_be_bytes_into(&w, &mut buf[pad..]);
+                    assert_eq!(&buf[pad..], be);
+
+                    // signed import and export round trip
+                    gmp::mpz_from_signed_be_bytes(&mut z, be);
+                    gmp::mpz_from_signed_le_bytes(&mut w, le);
+                    assert_eq!(gmp::mpz_cmp(&z, &w), 0);
+                    let neg = len > 0 && be[0] & 0x80 != 0;
+                    assert_eq!(gmp::mpz_sgn(&z) < 0, neg);
+                    if len <= 16 {
+                        let mut i128_bytes = [if neg { 0xff } else { 0 }; 16];
+                        i128_bytes[16 - len..].copy_from_slice(be);
+                        gmp::mpz_set_i128(&mut w, i128::from_be_bytes(i128_bytes));
+                        assert_eq!(gmp::mpz_cmp(&z, &w), 0);
+                    }
+                    let sign_byte = if neg { 0xff } else { 0 };
+                    let redundant = be
+                        .windows(2)
+                        .take_while(|p| p[0] == sign_byte && (p[1] & 0x80 != 0) == neg)
+                        .count();
+                    let min_len = if len == 0 || be.iter().all(|&b| b == 0) {
+                        0
+                    } else {
+                        len - redundant
+                    };
+                    assert_eq!(gmp::mpz_signed_bytes_len(&z), min_len);
+                    gmp::mpz_to_signed_be_bytes_into(&z, &mut buf);
+                    assert!(buf[..pad].iter().all(|&b| b == sign_byte));
+                    assert_eq!(&buf[pad..], be);
+                    gmp::mpz_to_signed_le_bytes_into(&z, &mut buf);
+                    assert_eq!(&buf[..len], &*le);
+                    assert!(buf[len..].iter().all(|&b| b == sign_byte));
+
+                    #[cfg(feature = "std")]
+                    {
+                        gmp::mpz_from_be_bytes(&mut w, be);
+                        assert_eq!(gmp::mpz_to_be_bytes_vec(&w), &be[stripped..]);
+                        assert_eq!(gmp::mpz_to_le_bytes_vec(&w), &le[..len - stripped]);
+                        assert_eq!(gmp::mpz_to_signed_be_bytes_vec(&z), &be[len - min_len..]);
+                        assert_eq!(gmp::mpz_to_signed_le_bytes_vec(&z), &le[..min_len]);
+                    }
+                }
+            }
+
+            gmp::mpz_clear(&mut w);
+            gmp::mpz_clear(&mut z);
+        }
+    }
+
+    #[test]
+    fn check_get_str_into() {
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init(z.as_mut_ptr());
+            let mut z = z.assume_init();
+            let mut q = MaybeUninit::uninit();
+            gmp::mpq_init(q.as_mut_ptr());
+            let mut q = q.assume_init();
+
+            let mut from_c = [0u8; 256];
+            let mut buf = [0u8; 256];
+            for i in 0..6 {
+                match i {
+                    0 => gmp::mpz_set_ui(&mut z, 0),
+                    1 => gmp::mpz_set_si(&mut z, -1),
+                    2 => gmp::mpz_set_ui(&mut z, 255),
+                    3 => gmp::mpz_set_si(&mut z, -256),
+                    4 => gmp::mpz_ui_pow_ui(&mut z, 3, 100),
+                    _ => {
+                        gmp::mpz_ui_pow_ui(&mut z, 2, 200);
+                        gmp::mpz_sub_ui(&mut z, &z, 1);
+                        gmp::mpz_neg(&mut z, &z);
+                    }
+                }
+                gmp::mpq_set_z(&mut q, &z);
+                gmp::mpz_set_ui(gmp::mpq_denref(&mut q), 243);
+                gmp::mpq_canonicalize(&mut q);
+                for &base in &[2, 8, 10, 16, -16, 36, -36, 37, 62] {
+                    let len = gmp::mpz_get_str_len(base, &z);
+                    let s = gmp::mpz_get_str_into(&mut buf[..len], base, &z);
+                    gmp::mpz_get_str(from_c.as_mut_ptr() as *mut c_char, base, &z);
+                    assert_eq!(s, gmp::str_before_nul(&from_c));
+
+                    let len = gmp::mpq_get_str_len(base, &q);
+                    let s = gmp::mpq_get_str_into(&mut buf[..len], base, &q);
+                    gmp::mpq_get_str(from_c.as_mut_ptr() as *mut c_char, base, &q);
+                    assert_eq!(s, gmp::str_before_nul(&from_c));
+
+                    #[cfg(feature = "std")]
+                    {
+                        let expected = gmp::str_before_nul(&from_c);
+                        assert_eq!(gmp::mpq_get_string(base, &q), expected);
+                        gmp::mpz_get_str(from_c.as_mut_ptr() as *mut c_char, base, &z);
+                        let expected = gmp::str_before_nul(&from_c);
+                        assert_eq!(gmp::mpz_get_string(base, &z), expected);
+                    }
+                }
+            }
+
+            gmp::mpq_clear(&mut q);
+            gmp::mpz_clear(&mut z);
+        }
+    }
+
+    #[test]
+    fn check_display() {
+        use crate::tests::check_fmt;
+        use gmp::{DisplayMpf, DisplayMpq, DisplayMpz};
+
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init(z.as_mut_ptr());
+            let mut z = z.assume_init();
+            check_fmt!("{}", DisplayMpz(&z), "0");
+            check_fmt!("{:+}", DisplayMpz(&z), "+0");
+            check_fmt!("{:e}", DisplayMpz(&z), "0e0");
+            check_fmt!("{:.2e}", DisplayMpz(&z), "0.00e0");
+            gmp::mpz_set_si(&mut z, -255);
+            check_fmt!("{}", DisplayMpz(&z), "-255");
+            check_fmt!("{:b}", DisplayMpz(&z), "-11111111");
+            check_fmt!("{:#o}", DisplayMpz(&z), "-0o377");
+            check_fmt!("{:x}", DisplayMpz(&z), "-ff");
+            check_fmt!("{:#X}", DisplayMpz(&z), "-0xFF");
+            check_fmt!("{:08}", DisplayMpz(&z), "-0000255");
+            check_fmt!("{:#08x}", DisplayMpz(&z), "-0x000ff");
+            check_fmt!("{:<6}|", DisplayMpz(&z), "-255  |");
+            check_fmt!("{:*^9}", DisplayMpz(&z), "**-255***");
+            check_fmt!("{:3}", DisplayMpz(&z), "-255");
+            check_fmt!("{:e}", DisplayMpz(&z), "-2.55e2");
+            check_fmt!("{:E}", DisplayMpz(&z), "-2.55E2");
+            check_fmt!("{:010.1e}", DisplayMpz(&z), "-00002.6e2");
+            gmp::mpz_set_ui(&mut z, 1000);
+            check_fmt!("{:e}", DisplayMpz(&z), "1e3");
+            check_fmt!("{:+.1e}", DisplayMpz(&z), "+1.0e3");
+            // ties to even
+            gmp::mpz_set_ui(&mut z, 12345);
+            check_fmt!("{:.3e}", DisplayMpz(&z), "1.234e4");
+            gmp::mpz_set_ui(&mut z, 12350);
+            check_fmt!("{:.2e}", DisplayMpz(&z), "1.24e4");
+            gmp::mpz_set_ui(&mut z, 99999);
+            check_fmt!("{:.1e}", DisplayMpz(&z), "1.0e5");
+            check_fmt!("{:.0e}", DisplayMpz(&z), "1e5");
+            check_fmt!("{:.6e}", DisplayMpz(&z), "9.999900e4");
+            // longer than the stack buffer
+            gmp::mpz_ui_pow_ui(&mut z, 10, 300);
+            gmp::mpz_sub_ui(&mut z, &z, 1);
+            let mut buf = [0u8; 310];
+            let len = gmp::mpz_get_str_len(10, &z);
+            let expected = gmp::mpz_get_str_into(&mut buf[..len], 10, &z);
+            check_fmt!("{}", DisplayMpz(&z), expected);
+            check_fmt!("{:.3e}", DisplayMpz(&z), "1.000e300");
+            // "9." followed by 299 nines
+            buf[1] = b'.';
+            buf[300] = b'9';
+            buf[301..305].copy_from_slice(b"e299");
+            let expected = core::str::from_utf8(&buf[..305]).unwrap();
+            check_fmt!("{:e}", DisplayMpz(&z), expected);
+            gmp::mpz_clear(&mut z);
+
+            let mut q = MaybeUninit::uninit();
+            gmp::mpq_init(q.as_mut_ptr());
+            let mut q = q.assume_init();
+            gmp::mpq_set_si(&mut q, -15, 16);
+            check_fmt!("{}", DisplayMpq(&q), "-15/16");
+            check_fmt!("{:#b}", DisplayMpq(&q), "-0b1111/10000");
+            check_fmt!("{:x}", DisplayMpq(&q), "-f/10");
+            check_fmt!("{:X}", DisplayMpq(&q), "-F/10");
+            check_fmt!("{:>8}", DisplayMpq(&q), "  -15/16");
+            check_fmt!("{:08}", DisplayMpq(&q), "-0015/16");
+            check_fmt!("{:e}", DisplayMpq(&q), "-1.5e1/1.6e1");
+            check_fmt!("{:.0E}", DisplayMpq(&q), "-2E1/2E1");
+            check_fmt!("{:>14e}", DisplayMpq(&q), "  -1.5e1/1.6e1");
+            gmp::mpq_set_si(&mut q, 5, 1);
+            check_fmt!("{:+}", DisplayMpq(&q), "+5");
+            check_fmt!("{:#o}", DisplayMpq(&q), "0o5");
+            check_fmt!("{:e}", DisplayMpq(&q), "5e0");
+            gmp::mpq_clear(&mut q);
+
+            let mut f = MaybeUninit::uninit();
+            gmp::mpf_init2(f.as_mut_ptr(), 64);
+            let mut f = f.assume_init();
+            check_fmt!("{}", DisplayMpf(&f), "0");
+            check_fmt!("{:.2}", DisplayMpf(&f), "0.00");
+            check_fmt!("{:e}", DisplayMpf(&f), "0e0");
+            check_fmt!("{:+.1e}", DisplayMpf(&f), "+0.0e0");
+            gmp::mpf_set_d(&mut f, -1.25);
+            check_fmt!("{}", DisplayMpf(&f), "-1.25");
+            // mpf_get_str rounds ties away from zero
+            check_fmt!("{:.1}", DisplayMpf(&f), "-1.3");
+            check_fmt!("{:.0}", DisplayMpf(&f), "-1");
+            check_fmt!("{:08.3}", DisplayMpf(&f), "-001.250");
+            check_fmt!("{:^9}", DisplayMpf(&f), "  -1.25  ");
+            check_fmt!("{:e}", DisplayMpf(&f), "-1.25e0");
+            check_fmt!("{:.3E}", DisplayMpf(&f), "-1.250E0");
+            check_fmt!("{:#b}", DisplayMpf(&f), "-0b1.01");
+            check_fmt!("{:x}", DisplayMpf(&f), "-1.4");
+            gmp::mpf_set_d(&mut f, 0.0009765625);
+            check_fmt!("{}", DisplayMpf(&f), "0.0009765625");
+            check_fmt!("{:.3}", DisplayMpf(&f), "0.001");
+            check_fmt!("{:.2}", DisplayMpf(&f), "0.00");
+            check_fmt!("{:x}", DisplayMpf(&f), "0.004");
+            gmp::mpf_set_d(&mut f, 9.5367431640625e-7);
+            check_fmt!("{}", DisplayMpf(&f), "9.5367431640625e-7");
+            check_fmt!("{:X}", DisplayMpf(&f), "1@-5");
+            check_fmt!("{:.6}", DisplayMpf(&f), "0.000001");
+            check_fmt!("{:.5}", DisplayMpf(&f), "0.00000");
+            gmp::mpf_set_d(&mut f, 0.75);
+            check_fmt!("{:.0}", DisplayMpf(&f), "1");
+            gmp::mpf_set_d(&mut f, 1024.0);
+            check_fmt!("{}", DisplayMpf(&f), "1024");
+            check_fmt!("{:.1}", DisplayMpf(&f), "1024.0");
+            check_fmt!("{:o}", DisplayMpf(&f), "2@3");
+            check_fmt!("{:.0o}", DisplayMpf(&f), "2000");
+            gmp::mpf_clear(&mut f);
+        }
+    }
+
+    #[test]
+    fn check_mpf_default_prec_guard() {
+        let prec = unsafe { gmp::mpf_get_default_prec() };
+        {
+            let _guard = gmp::MpfDefaultPrecGuard::new(prec + 1000);
+            unsafe {
+                assert!(gmp::mpf_get_default_prec() >= prec + 1000);
+                let mut f = MaybeUninit::uninit();
+                gmp::mpf_init(f.as_mut_ptr());
+                let mut f = f.assume_init();
+                assert!(gmp::mpf_get_prec(&f) >= prec + 1000);
+                gmp::mpf_clear(&mut f);
+            }
+        }
+        assert_eq!(unsafe { gmp::mpf_get_default_prec() }, prec);
+    }
+
+    #[cfg(feature = "owned")]
+    #[test]
+    fn check_owned() {
+        use crate::tests::format;
+        use gmp::{Mpf, Mpq, Mpz, RandState};
+
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<Mpz>();
+        send_sync::<Mpq>();
+        send_sync::<Mpf>();
+        send_sync::<RandState>();
+
+        unsafe {
+            let mut a = Mpz::new();
+            assert_eq!(gmp::mpz_sgn(a.as_raw()), 0);
+            gmp::mpz_set_si(a.as_raw_mut(), -12345);
+            let mut b = a.clone();
+            gmp::mpz_neg(b.as_raw_mut(), b.as_raw());
+            assert_eq!(gmp::mpz_get_si(a.as_raw()), -12345);
+            assert_eq!(gmp::mpz_get_si(b.as_raw()), 12345);
+            // clone_from reuses the allocation
+            gmp::mpz_ui_pow_ui(b.as_raw_mut(), 2, 1000);
+            let limbs = (*b.as_raw()).d;
+            b.clone_from(&a);
+            assert_eq!((*b.as_raw()).d, limbs);
+            assert_eq!(gmp::mpz_cmp(a.as_raw(), b.as_raw()), 0);
+            assert_eq!(
+                format(format_args!("{:?} {}", a, b)).as_str(),
+                "-12345 -12345"
+            );
+            let raw = b.into_raw();
+            let b = Mpz::from_raw(raw);
+            assert_eq!(gmp::mpz_get_si(b.as_raw()), -12345);
+            let mut raw = b.into_raw();
+            gmp::mpz_clear(&mut raw);
+
+            let mut q = Mpq::default();
+            gmp::mpq_set_si(q.as_raw_mut(), 3, 4);
+            let r = q.clone();
+            gmp::mpq_inv(q.as_raw_mut(), q.as_raw());
+            assert_eq!(format(format_args!("{} {}", q, r)).as_str(), "4/3 3/4");
+
+            let mut f = Mpf::new(256);
+            gmp::mpf_set_d(f.as_raw_mut(), 1.5);
+            let g = f.clone();
+            assert_eq!(gmp::mpf_get_prec(g.as_raw()), gmp::mpf_get_prec(f.as_raw()));
+            let mut h = Mpf::new(32);
+            assert!(gmp::mpf_get_prec(h.as_raw()) < gmp::mpf_get_prec(f.as_raw()));
+            h.clone_from(&f);
+            assert_eq!(gmp::mpf_get_prec(h.as_raw()), gmp::mpf_get_prec(f.as_raw()));
+            assert_eq!(gmp::mpf_cmp(h.as_raw(), g.as_raw()), 0);
+            assert_eq!(format(format_args!("{}", h)).as_str(), "1.5");
+
+            let mut state = RandState::new();
+            gmp::randseed_ui(state.as_raw_mut(), 7);
+            let mut copy = state.clone();
+            let (mut x, mut y) = (Mpz::new(), Mpz::new());
+            for _ in 0..3 {
+                gmp::mpz_urandomb(x.as_raw_mut(), state.as_raw_mut(), 200);
+                gmp::mpz_urandomb(y.as_raw_mut(), copy.as_raw_mut(), 200);
+                assert_eq!(gmp::mpz_cmp(x.as_raw(), y.as_raw()), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn check_mpz_ref() {
+        use core::convert::TryFrom;
+        use gmp::MpzRef;
+
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init(z.as_mut_ptr());
+            let mut z = z.assume_init();
+
+            let limbs = [5, 0, 7, 0, 0];
+            let view = MpzRef::from_limbs(&limbs, true);
+            assert_eq!(view.size, -3);
+            gmp::mpz_set_ui(&mut z, 7);
+            gmp::mpz_mul_2exp(&mut z, &z, 2 * gmp::LIMB_BITS as gmp::bitcnt_t);
+            gmp::mpz_add_ui(&mut z, &z, 5);
+            gmp::mpz_neg(&mut z, &z);
+            assert_eq!(gmp::mpz_cmp(&*view, &z), 0);
+            let empty = MpzRef::from_limbs(&[], true);
+            assert_eq!(gmp::mpz_sgn(empty.as_raw()), 0);
+
+            let i128_max = (!0u128 >> 1) as i128;
+            let vals = [
+                0i128,
+                1,
+                -1,
+                -(1 << 63),
+                i128::from(!0u64),
+                i128_max,
+                -i128_max - 1,
+            ];
+            for &val in &vals {
+                gmp::mpz_set_i128(&mut z, val);
+                assert_eq!(gmp::mpz_cmp(&*MpzRef::from_i128(val), &z), 0);
+                if val >= 0 {
+                    let view = MpzRef::from_u128(val as u128);
+                    assert_eq!(gmp::mpz_cmp(&*view, &z), 0);
+                }
+                if let Ok(val) = i64::try_from(val) {
+                    assert_eq!(gmp::mpz_cmp(&*MpzRef::from_i64(val), &z), 0);
+                }
+                if let Ok(val) = u64::try_from(val) {
+                    assert_eq!(gmp::mpz_cmp(&*MpzRef::from_u64(val), &z), 0);
+                }
+            }
+            gmp::mpz_set_u128(&mut z, !0);
+            assert_eq!(gmp::mpz_cmp(&*MpzRef::from_u128(!0), &z), 0);
+
+            // the inline limbs are found after moving or cloning
+            let views = [MpzRef::from_u128(!0), MpzRef::from_i64(-3)];
+            let cloned = {
+                let moved = views;
+                assert_eq!(gmp::mpz_cmp(&*moved[0], &z), 0);
+                moved[1].clone()
+            };
+            assert_eq!(gmp::mpz_cmp_si(&*cloned, -3), 0);
+
+            gmp::mpz_clear(&mut z);
+        }
+    }
 }
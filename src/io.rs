@@ -0,0 +1,724 @@
+// Copyright © 2017–2020 University of Malta
+
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License
+// as published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License and a copy of the GNU General Public License along with
+// this program. If not, see <https://www.gnu.org/licenses/>.
+
+/*!
+Stream input and output through `Read` and `Write` instead of
+`FILE` pointers.
+
+This module is only available if the `std` feature is enabled, on
+targets where the C library can create a `FILE` from callbacks, that
+is using `fopencookie` on Linux, and using `funopen` on macOS, iOS and
+the BSDs.
+
+Every function wraps the reader or writer in a temporary `FILE`,
+calls the C function with the same name, and closes the `FILE`
+before returning. Errors from the reader or writer are returned as
+they are, and panics in the reader or writer are propagated once the
+`FILE` is closed. If the C function itself reports an error, the
+error kind is `UnexpectedEof` if the reader reached the end of its
+input, `InvalidData` for other input errors, and `Other` for
+output errors.
+
+The input functions read one byte at a time, so that they do not
+consume input beyond the number, except that like the C functions
+they consume the byte that terminates a number in text form.
+
+The arguments other than the reader or writer are passed to the C
+function unchanged, so the same rules apply to them as when calling
+the C function directly; for example all pointers must point to
+initialized numbers.
+
+# Examples
+
+```rust
+use core::mem::MaybeUninit;
+use gmp_mpfr_sys::{gmp, io};
+unsafe {
+    let mut z = MaybeUninit::uninit();
+    gmp::mpz_init_set_si(z.as_mut_ptr(), -0xff);
+    let mut z = z.assume_init();
+    let mut out = Vec::new();
+    let written = io::mpz_out_str(&mut out, 16, &z).unwrap();
+    assert_eq!(written, 3);
+    assert_eq!(out, b"-ff");
+    let mut input: &[u8] = b"12345 and more";
+    io::mpz_inp_str(&mut z, &mut input, 10).unwrap();
+    assert_eq!(gmp::mpz_get_si(&z), 12345);
+    assert_eq!(input, b"and more");
+    gmp::mpz_clear(&mut z);
+}
+```
+
+*/
+
+use crate::gmp::{self, mpf_t, mpq_t, mpz_t};
+#[cfg(feature = "mpc")]
+use crate::mpc::{self, mpc_t};
+#[cfg(feature = "mpfr")]
+use crate::mpfr::{self, mpfr_t};
+use libc::{c_char, c_int, c_void, FILE};
+use std::any::Any;
+use std::boxed::Box;
+use std::format;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::string::String;
+
+/// See: [`mpz_out_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Integer-Functions.html#index-mpz_005fout_005fstr)
+///
+/// Returns the number of bytes written.
+pub unsafe fn mpz_out_str<W: Write + ?Sized>(
+    writer: &mut W,
+    base: c_int,
+    op: *const mpz_t,
+) -> Result<usize> {
+    write_with(writer, "mpz_out_str", |file| {
+        nonzero(gmp::mpz_out_str(file, base, op))
+    })
+}
+
+/// See: [`mpz_inp_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Integer-Functions.html#index-mpz_005finp_005fstr)
+///
+/// Returns the number of bytes read.
+pub unsafe fn mpz_inp_str<R: Read + ?Sized>(
+    rop: *mut mpz_t,
+    reader: &mut R,
+    base: c_int,
+) -> Result<usize> {
+    read_with(reader, "mpz_inp_str", |file| {
+        nonzero(gmp::mpz_inp_str(rop, file, base))
+    })
+}
+
+/// See: [`mpz_out_raw`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Integer-Functions.html#index-mpz_005fout_005fraw)
+///
+/// Returns the number of bytes written.
+pub unsafe fn mpz_out_raw<W: Write + ?Sized>(writer: &mut W, op: *const mpz_t) -> Result<usize> {
+    write_with(writer, "mpz_out_raw", |file| {
+        nonzero(gmp::mpz_out_raw(file, op))
+    })
+}
+
+/// See: [`mpz_inp_raw`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Integer-Functions.html#index-mpz_005finp_005fraw)
+///
+/// Returns the number of bytes read.
+pub unsafe fn mpz_inp_raw<R: Read + ?Sized>(rop: *mut mpz_t, reader: &mut R) -> Result<usize> {
+    read_with(reader, "mpz_inp_raw", |file| {
+        nonzero(gmp::mpz_inp_raw(rop, file))
+    })
+}
+
+/// See: [`mpq_out_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Rational-Number-Functions.html#index-mpq_005fout_005fstr)
+///
+/// Returns the number of bytes written.
+pub unsafe fn mpq_out_str<W: Write + ?Sized>(
+    writer: &mut W,
+    base: c_int,
+    op: *const mpq_t,
+) -> Result<usize> {
+    write_with(writer, "mpq_out_str", |file| {
+        nonzero(gmp::mpq_out_str(file, base, op))
+    })
+}
+
+/// See: [`mpq_inp_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Rational-Number-Functions.html#index-mpq_005finp_005fstr)
+///
+/// Returns the number of bytes read.
+pub unsafe fn mpq_inp_str<R: Read + ?Sized>(
+    rop: *mut mpq_t,
+    reader: &mut R,
+    base: c_int,
+) -> Result<usize> {
+    read_with(reader, "mpq_inp_str", |file| {
+        nonzero(gmp::mpq_inp_str(rop, file, base))
+    })
+}
+
+/// See: [`mpf_out_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Floating_002dpoint-Functions.html#index-mpf_005fout_005fstr)
+///
+/// Returns the number of bytes written.
+pub unsafe fn mpf_out_str<W: Write + ?Sized>(
+    writer: &mut W,
+    base: c_int,
+    n_digits: usize,
+    op: *const mpf_t,
+) -> Result<usize> {
+    write_with(writer, "mpf_out_str", |file| {
+        nonzero(gmp::mpf_out_str(file, base, n_digits, op))
+    })
+}
+
+/// See: [`mpf_inp_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Floating_002dpoint-Functions.html#index-mpf_005finp_005fstr)
+///
+/// Returns the number of bytes read.
+pub unsafe fn mpf_inp_str<R: Read + ?Sized>(
+    rop: *mut mpf_t,
+    reader: &mut R,
+    base: c_int,
+) -> Result<usize> {
+    read_with(reader, "mpf_inp_str", |file| {
+        nonzero(gmp::mpf_inp_str(rop, file, base))
+    })
+}
+
+/// See: [`mpfr_out_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fout_005fstr)
+///
+/// Returns the number of bytes written.
+///
+/// This function is only available if the `mpfr` feature is enabled.
+#[cfg(feature = "mpfr")]
+pub unsafe fn mpfr_out_str<W: Write + ?Sized>(
+    writer: &mut W,
+    base: c_int,
+    n: usize,
+    op: *const mpfr_t,
+    rnd: mpfr::rnd_t,
+) -> Result<usize> {
+    write_with(writer, "mpfr_out_str", |file| {
+        nonzero(mpfr::out_str(file, base, n, op, rnd))
+    })
+}
+
+/// See: [`mpfr_inp_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005finp_005fstr)
+///
+/// Returns the number of bytes read.
+///
+/// This function is only available if the `mpfr` feature is enabled.
+#[cfg(feature = "mpfr")]
+pub unsafe fn mpfr_inp_str<R: Read + ?Sized>(
+    rop: *mut mpfr_t,
+    reader: &mut R,
+    base: c_int,
+    rnd: mpfr::rnd_t,
+) -> Result<usize> {
+    read_with(reader, "mpfr_inp_str", |file| {
+        nonzero(mpfr::inp_str(rop, file, base, rnd))
+    })
+}
+
+/// See: [`mpfr_fpif_export`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffpif_005fexport)
+///
+/// This function is only available if the `mpfr` feature is enabled.
+#[cfg(feature = "mpfr")]
+pub unsafe fn mpfr_fpif_export<W: Write + ?Sized>(writer: &mut W, op: *mut mpfr_t) -> Result<()> {
+    write_with(writer, "mpfr_fpif_export", |file| {
+        zero(mpfr::fpif_export(file, op))
+    })
+}
+
+/// See: [`mpfr_fpif_import`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffpif_005fimport)
+///
+/// This function is only available if the `mpfr` feature is enabled.
+#[cfg(feature = "mpfr")]
+pub unsafe fn mpfr_fpif_import<R: Read + ?Sized>(op: *mut mpfr_t, reader: &mut R) -> Result<()> {
+    read_with(reader, "mpfr_fpif_import", |file| {
+        zero(mpfr::fpif_import(op, file))
+    })
+}
+
+/// See: [`mpc_out_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fout_005fstr)
+///
+/// Returns the number of bytes written.
+///
+/// This function is only available if the `mpc` feature is enabled.
+#[cfg(feature = "mpc")]
+pub unsafe fn mpc_out_str<W: Write + ?Sized>(
+    writer: &mut W,
+    base: c_int,
+    n_digits: usize,
+    op: *const mpc_t,
+    rnd: mpc::rnd_t,
+) -> Result<usize> {
+    write_with(writer, "mpc_out_str", |file| {
+        nonzero(mpc::out_str(file, base, n_digits, op, rnd))
+    })
+}
+
+/// See: [`mpc_inp_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005finp_005fstr)
+///
+/// Returns the inexact value and the number of bytes read.
+///
+/// This function is only available if the `mpc` feature is enabled.
+#[cfg(feature = "mpc")]
+pub unsafe fn mpc_inp_str<R: Read + ?Sized>(
+    rop: *mut mpc_t,
+    reader: &mut R,
+    base: c_int,
+    rnd: mpc::rnd_t,
+) -> Result<(c_int, usize)> {
+    read_with(reader, "mpc_inp_str", |file| {
+        let mut read = 0;
+        match mpc::inp_str(rop, file, &mut read, base, rnd) {
+            -1 => None,
+            inex => Some((inex, read)),
+        }
+    })
+}
+
+fn nonzero(n: usize) -> Option<usize> {
+    if n == 0 {
+        None
+    } else {
+        Some(n)
+    }
+}
+
+#[cfg(feature = "mpfr")]
+fn zero(ret: c_int) -> Option<()> {
+    if ret == 0 {
+        Some(())
+    } else {
+        None
+    }
+}
+
+enum Stream<'a> {
+    Read(&'a mut dyn Read),
+    Write(&'a mut dyn Write),
+}
+
+struct Cookie<'a> {
+    stream: Stream<'a>,
+    error: Option<Error>,
+    panic: Option<Box<dyn Any + Send>>,
+    eof: bool,
+}
+
+impl Cookie<'_> {
+    // returns None on error, which is then stored in self
+    fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let reader = match self.stream {
+            Stream::Read(ref mut reader) => reader,
+            Stream::Write(_) => return None,
+        };
+        loop {
+            match panic::catch_unwind(AssertUnwindSafe(|| reader.read(buf))) {
+                Ok(Ok(n)) => {
+                    if n == 0 {
+                        self.eof = true;
+                    }
+                    return Some(n);
+                }
+                Ok(Err(ref e)) if e.kind() == ErrorKind::Interrupted => {}
+                Ok(Err(e)) => {
+                    self.error = Some(e);
+                    return None;
+                }
+                Err(payload) => {
+                    self.panic = Some(payload);
+                    return None;
+                }
+            }
+        }
+    }
+
+    // writes the whole buffer, as the C stream treats a short write as
+    // an error; returns None on error, which is then stored in self
+    fn write(&mut self, buf: &[u8]) -> Option<usize> {
+        let writer = match self.stream {
+            Stream::Write(ref mut writer) => writer,
+            Stream::Read(_) => return None,
+        };
+        let mut rest = buf;
+        while !rest.is_empty() {
+            match panic::catch_unwind(AssertUnwindSafe(|| writer.write(rest))) {
+                Ok(Ok(0)) => {
+                    let msg = "failed to write whole buffer";
+                    self.error = Some(Error::new(ErrorKind::WriteZero, msg));
+                    return None;
+                }
+                Ok(Ok(n)) => rest = &rest[n..],
+                Ok(Err(ref e)) if e.kind() == ErrorKind::Interrupted => {}
+                Ok(Err(e)) => {
+                    self.error = Some(e);
+                    return None;
+                }
+                Err(payload) => {
+                    self.panic = Some(payload);
+                    return None;
+                }
+            }
+        }
+        Some(buf.len())
+    }
+}
+
+unsafe fn cookie_read(cookie: *mut c_void, buf: *mut c_char, size: usize) -> Option<usize> {
+    let cookie = &mut *(cookie as *mut Cookie);
+    cookie.read(slice::from_raw_parts_mut(buf as *mut u8, size))
+}
+
+unsafe fn cookie_write(cookie: *mut c_void, buf: *const c_char, size: usize) -> Option<usize> {
+    let cookie = &mut *(cookie as *mut Cookie);
+    cookie.write(slice::from_raw_parts(buf as *const u8, size))
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use libc::{c_char, c_int, c_void, FILE};
+
+    type ReadFn = unsafe extern "C" fn(*mut c_void, *mut c_char, usize) -> isize;
+    type WriteFn = unsafe extern "C" fn(*mut c_void, *const c_char, usize) -> isize;
+    type SeekFn = unsafe extern "C" fn(*mut c_void, *mut i64, c_int) -> c_int;
+    type CloseFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+    #[repr(C)]
+    struct CookieIoFunctions {
+        read: Option<ReadFn>,
+        write: Option<WriteFn>,
+        seek: Option<SeekFn>,
+        close: Option<CloseFn>,
+    }
+
+    extern "C" {
+        fn fopencookie(
+            cookie: *mut c_void,
+            mode: *const c_char,
+            io_funcs: CookieIoFunctions,
+        ) -> *mut FILE;
+    }
+
+    unsafe extern "C" fn read(cookie: *mut c_void, buf: *mut c_char, size: usize) -> isize {
+        super::cookie_read(cookie, buf, size).map_or(-1, |n| n as isize)
+    }
+
+    unsafe extern "C" fn write(cookie: *mut c_void, buf: *const c_char, size: usize) -> isize {
+        super::cookie_write(cookie, buf, size).map_or(-1, |n| n as isize)
+    }
+
+    pub unsafe fn open(cookie: *mut c_void, write_mode: bool) -> *mut FILE {
+        let (mode, funcs) = if write_mode {
+            let funcs = CookieIoFunctions {
+                read: None,
+                write: Some(write),
+                seek: None,
+                close: None,
+            };
+            (b"w\0", funcs)
+        } else {
+            let funcs = CookieIoFunctions {
+                read: Some(read),
+                write: None,
+                seek: None,
+                close: None,
+            };
+            (b"r\0", funcs)
+        };
+        fopencookie(cookie, mode.as_ptr() as *const c_char, funcs)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use core::cmp;
+    use libc::{c_char, c_int, c_void, FILE};
+
+    type ReadFn = unsafe extern "C" fn(*mut c_void, *mut c_char, c_int) -> c_int;
+    type WriteFn = unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_int;
+    type SeekFn = unsafe extern "C" fn(*mut c_void, i64, c_int) -> i64;
+    type CloseFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+    extern "C" {
+        fn funopen(
+            cookie: *const c_void,
+            readfn: Option<ReadFn>,
+            writefn: Option<WriteFn>,
+            seekfn: Option<SeekFn>,
+            closefn: Option<CloseFn>,
+        ) -> *mut FILE;
+    }
+
+    unsafe extern "C" fn read(cookie: *mut c_void, buf: *mut c_char, size: c_int) -> c_int {
+        let size = cmp::max(size, 0) as usize;
+        super::cookie_read(cookie, buf, size).map_or(-1, |n| n as c_int)
+    }
+
+    unsafe extern "C" fn write(cookie: *mut c_void, buf: *const c_char, size: c_int) -> c_int {
+        let size = cmp::max(size, 0) as usize;
+        super::cookie_write(cookie, buf, size).map_or(-1, |n| n as c_int)
+    }
+
+    pub unsafe fn open(cookie: *mut c_void, write_mode: bool) -> *mut FILE {
+        if write_mode {
+            funopen(cookie, None, Some(write), None, None)
+        } else {
+            funopen(cookie, Some(read), None, None, None)
+        }
+    }
+}
+
+// the same value on all the targets that have fopencookie or funopen
+const _IONBF: c_int = 2;
+
+fn read_with<R, T, F>(reader: &mut R, name: &str, f: F) -> Result<T>
+where
+    R: Read + ?Sized,
+    F: FnOnce(*mut FILE) -> Option<T>,
+{
+    let mut reader = reader;
+    with_file(Stream::Read(&mut reader), name, f)
+}
+
+fn write_with<W, T, F>(writer: &mut W, name: &str, f: F) -> Result<T>
+where
+    W: Write + ?Sized,
+    F: FnOnce(*mut FILE) -> Option<T>,
+{
+    let mut writer = writer;
+    with_file(Stream::Write(&mut writer), name, f)
+}
+
+fn with_file<T, F>(stream: Stream, name: &str, f: F) -> Result<T>
+where
+    F: FnOnce(*mut FILE) -> Option<T>,
+{
+    let write_mode = match stream {
+        Stream::Read(_) => false,
+        Stream::Write(_) => true,
+    };
+    let mut cookie = Cookie {
+        stream,
+        error: None,
+        panic: None,
+        eof: false,
+    };
+    let (ret, closed) = unsafe {
+        let file = sys::open(&mut cookie as *mut Cookie as *mut c_void, write_mode);
+        if file.is_null() {
+            return Err(Error::last_os_error());
+        }
+        if !write_mode {
+            libc::setvbuf(file, ptr::null_mut(), _IONBF, 0);
+        }
+        let ret = f(file);
+        // for output, this flushes the buffer to the writer
+        let closed = libc::fclose(file) == 0;
+        (ret, closed)
+    };
+    if let Some(payload) = cookie.panic.take() {
+        panic::resume_unwind(payload);
+    }
+    if let Some(error) = cookie.error.take() {
+        return Err(error);
+    }
+    if let Stream::Write(ref mut writer) = cookie.stream {
+        writer.flush()?;
+    }
+    match ret {
+        Some(ret) if closed => Ok(ret),
+        _ => {
+            // The cookie functions do not set errno, so the error is
+            // not taken from it.
+            let (kind, msg) = if !closed {
+                (ErrorKind::Other, String::from("failed to flush stream"))
+            } else if cookie.eof {
+                (ErrorKind::UnexpectedEof, format!("{} failed", name))
+            } else if write_mode {
+                (ErrorKind::Other, format!("{} failed", name))
+            } else {
+                (ErrorKind::InvalidData, format!("{} failed", name))
+            };
+            Err(Error::new(kind, msg))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{gmp, io};
+    use core::mem::MaybeUninit;
+    use std::io::{Error, ErrorKind, Result, Write};
+    use std::vec::Vec;
+
+    struct FailWriter;
+
+    impl Write for FailWriter {
+        fn write(&mut self, _buf: &[u8]) -> Result<usize> {
+            Err(Error::new(ErrorKind::BrokenPipe, "fail"))
+        }
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    // accepts one byte per call, like a socket or pipe with a full
+    // buffer
+    struct ByteWriter(Vec<u8>);
+
+    impl Write for ByteWriter {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.0.extend(buf.iter().take(1));
+            Ok(if buf.is_empty() { 0 } else { 1 })
+        }
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct FlushFailWriter;
+
+    impl Write for FlushFailWriter {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> Result<()> {
+            Err(Error::new(ErrorKind::BrokenPipe, "fail"))
+        }
+    }
+
+    #[test]
+    fn check_short_writes() {
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init_set_si(z.as_mut_ptr(), -12345);
+            let mut z = z.assume_init();
+
+            let mut out = ByteWriter(Vec::new());
+            assert_eq!(io::mpz_out_str(&mut out, 10, &z).unwrap(), 6);
+            assert_eq!(out.0, b"-12345");
+            out.0.clear();
+            assert_eq!(io::mpz_out_raw(&mut out, &z).unwrap(), 6);
+            assert_eq!(out.0, [0xff, 0xff, 0xff, 0xfe, 0x30, 0x39]);
+
+            let err = io::mpz_out_str(&mut FlushFailWriter, 10, &z).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+
+            gmp::mpz_clear(&mut z);
+        }
+    }
+
+    #[test]
+    fn check_mpz() {
+        unsafe {
+            let mut z = MaybeUninit::uninit();
+            gmp::mpz_init_set_si(z.as_mut_ptr(), -12345);
+            let mut z = z.assume_init();
+
+            let mut out = Vec::new();
+            assert_eq!(io::mpz_out_str(&mut out, 10, &z).unwrap(), 6);
+            assert_eq!(out, b"-12345");
+            out.clear();
+            assert_eq!(io::mpz_out_raw(&mut out, &z).unwrap(), 6);
+            assert_eq!(out, [0xff, 0xff, 0xff, 0xfe, 0x30, 0x39]);
+
+            let mut input = &out[..];
+            gmp::mpz_set_ui(&mut z, 0);
+            assert_eq!(io::mpz_inp_raw(&mut z, &mut input).unwrap(), 6);
+            assert_eq!(gmp::mpz_get_si(&z), -12345);
+            assert!(input.is_empty());
+            let err = io::mpz_inp_raw(&mut z, &mut input).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+            let mut input: &[u8] = b" 0x1f,2";
+            assert_eq!(io::mpz_inp_str(&mut z, &mut input, 0).unwrap(), 5);
+            assert_eq!(gmp::mpz_get_si(&z), 31);
+            assert_eq!(input, b"2");
+            let mut input: &[u8] = b"z";
+            let err = io::mpz_inp_str(&mut z, &mut input, 10).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+            let err = io::mpz_out_str(&mut FailWriter, 10, &z).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+
+            gmp::mpz_clear(&mut z);
+        }
+    }
+
+    #[test]
+    fn check_mpq_mpf() {
+        unsafe {
+            let mut q = MaybeUninit::uninit();
+            gmp::mpq_init(q.as_mut_ptr());
+            let mut q = q.assume_init();
+            let mut input: &[u8] = b"-22/7";
+            assert_eq!(io::mpq_inp_str(&mut q, &mut input, 10).unwrap(), 5);
+            let mut out = Vec::new();
+            io::mpq_out_str(&mut out, 16, &q).unwrap();
+            assert_eq!(out, b"-16/7");
+            gmp::mpq_clear(&mut q);
+
+            let mut f = MaybeUninit::uninit();
+            gmp::mpf_init2(f.as_mut_ptr(), 64);
+            let mut f = f.assume_init();
+            let mut input: &[u8] = b"1.5e3";
+            assert_eq!(io::mpf_inp_str(&mut f, &mut input, 10).unwrap(), 5);
+            out.clear();
+            io::mpf_out_str(&mut out, 10, 0, &f).unwrap();
+            assert_eq!(out, b"0.15e4");
+            gmp::mpf_clear(&mut f);
+        }
+    }
+
+    #[cfg(feature = "mpfr")]
+    #[test]
+    fn check_mpfr() {
+        use crate::mpfr;
+
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 53);
+            let mut f = f.assume_init();
+            let mut input: &[u8] = b"0.375";
+            let rnd = mpfr::rnd_t::RNDN;
+            assert_eq!(io::mpfr_inp_str(&mut f, &mut input, 10, rnd).unwrap(), 5);
+            let mut out = Vec::new();
+            io::mpfr_out_str(&mut out, 2, 0, &f, rnd).unwrap();
+            assert_eq!(
+                out,
+                b"1.1000000000000000000000000000000000000000000000000000e-2"
+            );
+
+            out.clear();
+            io::mpfr_fpif_export(&mut out, &mut f).unwrap();
+            let mut g = MaybeUninit::uninit();
+            mpfr::init2(g.as_mut_ptr(), 10);
+            let mut g = g.assume_init();
+            let mut input = &out[..];
+            io::mpfr_fpif_import(&mut g, &mut input).unwrap();
+            assert!(input.is_empty());
+            assert_eq!(mpfr::get_prec(&g), 53);
+            assert_eq!(mpfr::cmp(&f, &g), 0);
+            let mut input = &out[..out.len() - 1];
+            let err = io::mpfr_fpif_import(&mut g, &mut input).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+            mpfr::clear(&mut g);
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[cfg(feature = "mpc")]
+    #[test]
+    fn check_mpc() {
+        use crate::mpc;
+
+        unsafe {
+            let mut c = MaybeUninit::uninit();
+            mpc::init2(c.as_mut_ptr(), 53);
+            let mut c = c.assume_init();
+            let mut input: &[u8] = b"(1.5 -2) rest";
+            let (inex, read) = io::mpc_inp_str(&mut c, &mut input, 10, mpc::RNDNN).unwrap();
+            assert_eq!((inex, read), (0, 8));
+            assert_eq!(input, b" rest");
+            let mut out = Vec::new();
+            io::mpc_out_str(&mut out, 10, 2, &c, mpc::RNDNN).unwrap();
+            assert_eq!(out, b"(1.5 -2.0)");
+            mpc::clear(&mut c);
+        }
+    }
+}
@@ -43,6 +43,10 @@ This crate contains three modules:
 The versions provided by this crate release are [GMP] version 6.2.0,
 [MPFR] version 4.0.2-p1, and [MPC] version 1.1.0.
 
+The [`parse`] module parses numbers from byte slices, which do not
+need to be NUL terminated, and reports the byte offset and the reason
+when the input is not valid.
+
 If you want a high-level API, consider using [Rug][rug crate], a crate
 which provides integers and floating-point numbers with arbitrary
 precision and correct rounding:
@@ -72,6 +76,18 @@ Unlike in the C libraries, the types [`gmp::mpz_t`], [`gmp::mpq_t`],
 [`mpc::mpc_t`] are defined directly as structs, not as single-element
 arrays.
 
+Rust has no type for the C `long double`, so [`mpfr::long_double`]
+and [`mpc::long_double_complex`] have the same layout as `long double`
+and `long double _Complex`. Functions such as `mpfr::set_ld` and
+`mpfr::get_ld` take and return them by value, but they are passed to
+the C libraries by pointer through small C wrappers compiled by the
+build script.
+
+The C `double _Complex` type has the same calling convention as a
+struct of two `double` values, so [`mpc::double_complex`] is a
+`#[repr(C)]` struct with the real and imaginary parts that is passed
+directly to functions such as `mpc::set_dc` and `mpc::get_dc`.
+
 ### Undocumented or obsolete functions
 
 The bindings do not cover undocumented or obsolete functions and
@@ -85,7 +101,7 @@ gmp-mpfr-sys in your crate, add it as a dependency inside
 
 ```toml
 [dependencies]
-gmp-mpfr-sys = "1.2"
+gmp-mpfr-sys = "2.0"
 ```
 
 This crate required rustc version 1.37.0 or later.
@@ -99,26 +115,73 @@ Rust bindings but do not use the C libraries directly.
 If on the other hand a dependent crate makes use of internal
 implementation details, or includes a C library that directly uses the
 header (*.h*) and library (*.a*) files built using C, it can be a good
-idea to depend on version `"~1.2"` instead of version `"1.2"` in order
+idea to depend on version `"~2.0"` instead of version `"2.0"` in order
 to ensure backwards compatibility at the C level as well.
 
 ## Optional features
 
-The gmp-mpfr-sys crate has two optional features:
+The gmp-mpfr-sys crate has nine optional features:
 
  1. `mpfr`, enabled by default. Required to include the [MPFR]
     library.
  2. `mpc`, enabled by default. Required to include the [MPC] library.
     This feature requires the `mpfr` feature.
-
-The [GMP] library is always included.
-
-The two optional features are enabled by default; to use features
-selectively, you can add the dependency like this to [*Cargo.toml*]:
+ 3. `mpfr-float128`, disabled by default. Builds [MPFR] with
+    `--enable-float128`, which adds conversions from and to the
+    `__float128` type. The functions `mpfr::set_float128` and
+    `mpfr::get_float128` are then available; they use the type
+    `mpfr::float128`, which holds the bits of an IEEE binary128
+    value.
+ 4. `mpfr-decimal-float`, disabled by default. Builds [MPFR] with
+    `--enable-decimal-float`, which adds conversions from and to
+    decimal floating-point types. The functions
+    `mpfr::set_decimal64` and `mpfr::get_decimal64` are then
+    available; they use the type `mpfr::decimal64`, which holds the
+    bits of a `_Decimal64` value in the encoding of the C compiler.
+ 5. `mpfr-shared-cache`, disabled by default. Builds [MPFR] with
+    `--enable-shared-cache`, so that the caches for constants such
+    as π are shared by all threads.
+ 6. `mpfr-gmp-internals`, disabled by default. Builds [MPFR] with
+    `--enable-gmp-internals`, so that [MPFR] can use undocumented
+    [GMP] functions. This feature only has an effect together with
+    `use-system-libs`, where it requires the system [MPFR] to have
+    been built with that option. The bundled [MPFR] is always built
+    against the [GMP] build directory, so it uses [GMP] internals
+    even without this feature.
+ 7. `disable-assembly`, disabled by default. Builds [GMP] with
+    `--disable-assembly` instead of `--enable-fat`, so that only
+    generic C code is used, and builds [GMP], [MPFR] and [MPC] with
+    the baseline compiler flags for the architecture. This is slower,
+    but can help with tools such as Valgrind and with unusual or
+    emulated processors. The `disable_assembly` configuration option
+    is then set for this crate, and [`gmp::BUILD_MODE`] is
+    `"generic"`.
+ 8. `std`, disabled by default. Makes the crate depend on the
+    standard library, and adds the [`io`] module, which wraps a
+    `std::io::Read` or `std::io::Write` in a temporary C `FILE` for
+    the stream input and output functions. The module is available
+    on Linux, macOS, iOS and the BSDs.
+ 9. `owned`, disabled by default. Adds owned types such as
+    `gmp::Mpz`, `gmp::RandState` and `mpfr::Mpfr`, which initialize
+    the raw value when created and clear it when dropped. They can be
+    passed to the functions in this crate using their `as_raw` and
+    `as_raw_mut` methods.
+
+The [GMP] library is always included. Features 3 to 6 require the
+`mpfr` feature; if one of them is not selected, [MPFR] is built with
+the corresponding `--disable-` option. When one of these features is
+selected, a configuration option with the same name but with
+underscores instead of hyphens, for example `mpfr_float128`, is set
+for this crate; `mpfr_gmp_internals` is always set for the bundled
+[MPFR].
+
+The first two optional features are enabled by default; to use
+features selectively, you can add the dependency like this to
+[*Cargo.toml*]:
 
 ```toml
 [dependencies.gmp-mpfr-sys]
-version = "1.2"
+version = "2.0"
 default-features = false
 features = ["mpfr"]
 ```
@@ -131,13 +194,38 @@ It is not considered a breaking change if experimental features are
 removed. The removal of experimental features would however require a
 minor version bump.
 
-There is one experimental feature:
+There are two experimental features:
 
  1. `use-system-libs`, disabled by default. This is *not* supported on
     Windows. Using this feature, the system libraries for [GMP], and
     [MPFR] and [MPC] if enabled, will be used instead of building them
     from source. The versions must be compatible with the versions
-    provided by the crate.
+    provided by the crate. The `mpfr_*` configuration options then
+    reflect how the system [MPFR] was built, and selecting a feature
+    such as `mpfr-float128` that the system [MPFR] was not built with
+    is an error. The `disable-assembly` feature has no effect on
+    system libraries.
+ 2. `linker-plugin-lto`, disabled by default. This builds the C parts
+    of [GMP], [MPFR] and [MPC] with `clang -flto=thin`, so that the
+    libraries contain LLVM bitcode and small C functions can be
+    inlined into Rust code and vice versa. It requires `clang`,
+    `lld` and `llvm-ar`, and the LLVM major version of `clang` must
+    match the one used by `rustc`. The build script cannot set the
+    flags for the final link, so the user must build with
+    cross-language link-time optimization themselves by setting
+    `RUSTFLAGS="-Clinker-plugin-lto -Clinker=clang
+    -Clink-arg=-fuse-ld=lld"`, where the `clang` and `lld` found in
+    the path have the same LLVM major version as `rustc`, which can
+    be checked with `rustc -vV`. This feature cannot be used together
+    with `use-system-libs`, and when it is selected, the
+    `linker_plugin_lto` configuration option is set for this crate.
+
+The build script compiles a small C program against the [GMP],
+[MPFR] and [MPC] headers, bundled or system, to check that the sizes,
+alignments and field offsets of types such as [`gmp::mpz_t`] and
+[`mpfr::mpfr_t`] match their Rust definitions. Sizes and alignments
+are checked at compile time, so a mismatching system library makes
+the build fail.
 
 ## Metadata
 
@@ -154,6 +242,17 @@ The gmp-mpfr-sys crate passes some metadata to its dependents:
     the `DEP_GMP_OUT_DIR` directory.
  4. `DEP_GMP_INCLUDE_DIR` contains the path of the *include*
     subdirectory of the `DEP_GMP_OUT_DIR` directory.
+ 5. `DEP_GMP_MPFR_FLOAT128`, `DEP_GMP_MPFR_DECIMAL_FLOAT`,
+    `DEP_GMP_MPFR_SHARED_CACHE` and `DEP_GMP_MPFR_GMP_INTERNALS`
+    are set to 1 if [MPFR] reports the corresponding option
+    described in [Optional features](#optional-features).
+ 6. `DEP_GMP_BUILD_MODE` contains `fat` if [GMP] was built with
+    `--enable-fat`, `generic` if it was built with
+    `--disable-assembly` using the `disable-assembly` feature, or
+    `system` if the system libraries are used.
+ 7. `DEP_GMP_LTO` and `DEP_GMP_LTO_LLVM_VERSION` are set if the
+    `linker-plugin-lto` feature is selected, and contain `thin` and
+    the LLVM major version.
 
 A dependent crate can use these environment variables in its build
 script.
@@ -224,6 +323,18 @@ To use a different directory, you can set the environment variable
 `GMP_MPFR_SYS_CACHE` to the desired cache directory. Setting the
 `GMP_MPFR_SYS_CACHE` variable to an empty string will disable caching.
 
+Libraries built with different [MPFR] options, with the
+`disable-assembly` feature, or with the `linker-plugin-lto` feature
+and a different LLVM version are cached separately.
+
+When the C libraries are built, the output of the configure, make and
+make check steps is written to log files inside the *log* subdirectory
+of the build script’s output directory, and the time taken by each
+step is printed in the build script output. Cargo only shows that
+output when building with `cargo build -vv`; otherwise it can be found
+in `target/<profile>/build/gmp-mpfr-sys-<hash>/output`. If a step
+fails, the path of its log file is included in the error message.
+
 [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 [GMP]: https://gmplib.org/
 [GNU GPL]: https://www.gnu.org/licenses/gpl-3.0.html
@@ -235,8 +346,10 @@ To use a different directory, you can set the environment variable
 [`Float`]: https://docs.rs/rug/&#42;/rug/struct.Float.html
 [`Integer`]: https://docs.rs/rug/&#42;/rug/struct.Integer.html
 [`MPFR_RNDN`]: https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#Rounding-Modes
+[`parse`]: parse/index.html
 [`Rational`]: https://docs.rs/rug/&#42;/rug/struct.Rational.html
 [`enum MPFR_RND_T`]: https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft
+[`gmp::BUILD_MODE`]: gmp/constant.BUILD_MODE.html
 [`gmp::mpf_t`]: gmp/struct.mpf_t.html
 [`gmp::mpq_t`]: gmp/struct.mpq_t.html
 [`gmp::mpz_init`]: gmp/fn.mpz_init.html
@@ -244,11 +357,15 @@ To use a different directory, you can set the environment variable
 [`gmp::randstate_t`]: gmp/struct.randstate_t.html
 [`gmp::set_memory_functions`]: gmp/fn.set_memory_functions.html
 [`gmp`]: gmp/index.html
+[`io`]: io/index.html
 [`mp_set_memory_functions`]: https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Custom-Allocation.html#index-mp_005fset_005fmemory_005ffunctions
+[`mpc::double_complex`]: mpc/struct.double_complex.html
+[`mpc::long_double_complex`]: mpc/struct.long_double_complex.html
 [`mpc::mpc_t`]: mpc/struct.mpc_t.html
 [`mpc`]: mpc/index.html
+[`mpfr::long_double`]: mpfr/struct.long_double.html
 [`mpfr::mpfr_t`]: mpfr/struct.mpfr_t.html
-[`mpfr::rnd_t::RNDN`]: mpfr/enum.rnd_t.html#variant.RNDN
+[`mpfr::rnd_t::RNDN`]: mpfr/struct.rnd_t.html#associatedconstant.RNDN
 [`mpfr`]: mpfr/index.html
 [`mpz_init`]: https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Integer-Functions.html#index-mpz_005finit
 [msys]:     https://msys2.github.io/
@@ -257,18 +374,42 @@ To use a different directory, you can set the environment variable
 */
 #![no_std]
 #![warn(missing_docs)]
-#![doc(html_root_url = "https://docs.rs/gmp-mpfr-sys/~1.2")]
+#![doc(html_root_url = "https://docs.rs/gmp-mpfr-sys/~2.0")]
 #![doc(html_logo_url = "https://tspiteri.gitlab.io/gmp-mpfr-sys/rug.svg")]
 #![doc(test(attr(deny(warnings))))]
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![cfg_attr(nightly_maybe_uninit, feature(maybe_uninit))]
 #![allow(clippy::identity_conversion, clippy::missing_safety_doc)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[macro_use]
+mod display;
+#[cfg(feature = "owned")]
+#[macro_use]
+mod owned;
 pub mod gmp;
+#[cfg(all(
+    feature = "std",
+    any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+))]
+pub mod io;
 #[cfg(feature = "mpc")]
 pub mod mpc;
 #[cfg(feature = "mpfr")]
 pub mod mpfr;
+#[cfg(feature = "owned")]
+mod ops;
+pub mod parse;
 
 // this is here for the mpfr_round_nearest_away macro only
 #[doc(hidden)]
@@ -276,11 +417,55 @@ pub use libc;
 
 #[cfg(test)]
 mod tests {
-    use core::{slice, str};
+    use core::{
+        fmt::{Arguments, Result as FmtResult, Write},
+        slice, str,
+    };
     use libc::c_char;
 
     pub unsafe fn str_from_cstr<'a>(cstr: *const c_char) -> &'a str {
         let s = slice::from_raw_parts(cstr as *const u8, libc::strlen(cstr));
         str::from_utf8(s).expect("version not utf8")
     }
+
+    // formats into a fixed buffer, as the tests can run without std
+    pub struct FmtBuf {
+        bytes: [u8; 1024],
+        len: usize,
+    }
+
+    impl FmtBuf {
+        pub fn as_str(&self) -> &str {
+            str::from_utf8(&self.bytes[..self.len]).expect("formatted not utf8")
+        }
+    }
+
+    impl Write for FmtBuf {
+        fn write_str(&mut self, s: &str) -> FmtResult {
+            let end = self.len + s.len();
+            self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    pub fn format(args: Arguments<'_>) -> FmtBuf {
+        let mut buf = FmtBuf {
+            bytes: [0; 1024],
+            len: 0,
+        };
+        buf.write_fmt(args).expect("format failed");
+        buf
+    }
+
+    // asserts that formatting $x with $fmt gives $expected
+    macro_rules! check_fmt {
+        ($fmt:tt, $x:expr, $expected:expr) => {
+            assert_eq!(
+                $crate::tests::format(format_args!($fmt, $x)).as_str(),
+                $expected
+            );
+        };
+    }
+    pub(crate) use check_fmt;
 }
@@ -44,10 +44,19 @@ unsafe {
 */
 #![allow(non_camel_case_types, non_snake_case)]
 
-use crate::{gmp, mpfr};
+use crate::{display, gmp, mpfr};
+use core::fmt::{Formatter, Result as FmtResult};
+#[cfg(feature = "owned")]
+use core::{
+    fmt::{Debug, Display},
+    mem::MaybeUninit,
+};
 use libc::{c_char, c_int, c_long, c_ulong, intmax_t, uintmax_t, FILE};
+#[cfg(feature = "std")]
+use std::string::String;
 
 include!(concat!(env!("OUT_DIR"), "/mpc_h.rs"));
+include!(concat!(env!("OUT_DIR"), "/mpc_layout.rs"));
 
 #[inline]
 extern "C" fn INEX_NEG(inex: c_int) -> c_int {
@@ -57,6 +66,20 @@ extern "C" fn INEX_NEG(inex: c_int) -> c_int {
         _ => 1,
     }
 }
+#[inline]
+extern "C" fn INEX_POS(inex: c_int) -> c_int {
+    if inex < 0 {
+        2
+    } else if inex == 0 {
+        0
+    } else {
+        1
+    }
+}
+#[inline]
+pub(crate) extern "C" fn INEX(inex_re: c_int, inex_im: c_int) -> c_int {
+    INEX_POS(inex_re) | (INEX_POS(inex_im) << 2)
+}
 /// See: [Return Value](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#return_002dvalue)
 #[inline]
 pub extern "C" fn INEX_RE(inex: c_int) -> c_int {
@@ -81,10 +104,10 @@ pub extern "C" fn INEX2(inex: c_int) -> c_int {
 /// See: [`mpc_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#index-mpc_005frnd_005ft)
 pub type rnd_t = c_int;
 
-const RNDN: c_int = mpfr::rnd_t::RNDN as c_int;
-const RNDZ: c_int = mpfr::rnd_t::RNDZ as c_int;
-const RNDU: c_int = mpfr::rnd_t::RNDU as c_int;
-const RNDD: c_int = mpfr::rnd_t::RNDD as c_int;
+const RNDN: c_int = mpfr::rnd_t::RNDN.0;
+const RNDZ: c_int = mpfr::rnd_t::RNDZ.0;
+const RNDU: c_int = mpfr::rnd_t::RNDU.0;
+const RNDD: c_int = mpfr::rnd_t::RNDD.0;
 
 /// See: [Rounding Modes](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#Rounding-Modes)
 pub const RNDNN: c_int = RNDN + (RNDN << 4);
@@ -119,6 +142,31 @@ pub const RNDDU: c_int = RNDD + (RNDU << 4);
 /// See: [Rounding Modes](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#Rounding-Modes)
 pub const RNDDD: c_int = RNDD + (RNDD << 4);
 
+/// See: [Rounding Modes](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#Rounding-Modes)
+///
+/// Combines the rounding modes of the real and imaginary parts like
+/// the `MPC_RND` macro.
+#[inline]
+pub extern "C" fn RND(re: mpfr::rnd_t, im: mpfr::rnd_t) -> rnd_t {
+    re.0 + (im.0 << 4)
+}
+/// See: [Rounding Modes](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#Rounding-Modes)
+///
+/// Extracts the rounding mode of the real part like the
+/// `MPC_RND_RE` macro.
+#[inline]
+pub extern "C" fn RND_RE(rnd: rnd_t) -> mpfr::rnd_t {
+    mpfr::rnd_t(rnd & 0x0f)
+}
+/// See: [Rounding Modes](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#Rounding-Modes)
+///
+/// Extracts the rounding mode of the imaginary part like the
+/// `MPC_RND_IM` macro.
+#[inline]
+pub extern "C" fn RND_IM(rnd: rnd_t) -> mpfr::rnd_t {
+    mpfr::rnd_t(rnd >> 4)
+}
+
 /// See: [`mpc_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/GNU-MPC-Basics.html#index-mpc_005ft)
 ///
 /// # Future compatibility
@@ -127,7 +175,7 @@ pub const RNDDD: c_int = RNDD + (RNDD << 4);
 /// internals may change in new minor releases of this crate, though
 /// they will be kept unchanged for patch releases. Any code that
 /// makes use of these internals should list the dependency as
-/// `version = "~1.2"` inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// `version = "~2.0"` inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 #[repr(C)]
@@ -139,6 +187,80 @@ pub struct mpc_t {
     pub im: mpfr::mpfr_t,
 }
 
+/// The C `double _Complex` type.
+///
+/// This type has the layout of `[f64; 2]`, with the real part
+/// followed by the imaginary part, which is the layout that C99
+/// specifies for `double _Complex`. It is passed to and returned from
+/// C functions such as [`set_dc`] and [`get_dc`] directly, as the C
+/// calling conventions of the supported targets treat `double
+/// _Complex` like a structure of two `double` values.
+///
+/// [`get_dc`]: fn.get_dc.html
+/// [`set_dc`]: fn.set_dc.html
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct double_complex {
+    /// The real part.
+    pub re: f64,
+    /// The imaginary part.
+    pub im: f64,
+}
+
+/// The C `long double _Complex` type of the target.
+///
+/// Like [`mpfr::long_double`], this type has the same layout as its C
+/// counterpart but it can only be passed to C by pointer. Functions
+/// such as [`set_ldc`] and [`get_ldc`] take or return it by value, and
+/// pass it by pointer to small C wrappers around the [MPC] functions.
+///
+/// [MPC]: http://www.multiprecision.org/mpc/
+/// [`get_ldc`]: fn.get_ldc.html
+/// [`mpfr::long_double`]: ../mpfr/struct.long_double.html
+/// [`set_ldc`]: fn.set_ldc.html
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct long_double_complex {
+    /// The real part.
+    pub re: mpfr::long_double,
+    /// The imaginary part.
+    pub im: mpfr::long_double,
+}
+
+/// A wrapper to format an [`mpc_t`] using `core::fmt`.
+///
+/// The number is written as `(re im)`, where each part is written like
+/// [`mpfr::DisplayMpfr`] writes it, using the same trait, precision
+/// and flags for both parts. The `+` flag and the prefix for the `#`
+/// flag apply to each part, while the width, fill and alignment apply
+/// to the whole number, which is never padded with zeros.
+///
+/// The number must be initialized. Formatting does not change the
+/// MPFR exception flags.
+///
+/// # Examples
+///
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use gmp_mpfr_sys::mpc::{self, DisplayMpc};
+/// unsafe {
+///     let mut c = MaybeUninit::uninit();
+///     mpc::init2(c.as_mut_ptr(), 53);
+///     let mut c = c.assume_init();
+///     mpc::set_si_si(&mut c, 5, -3, mpc::RNDNN);
+///     assert_eq!(format!("{}", DisplayMpc(&c)), "(5 -3)");
+///     assert_eq!(format!("{:+.1}", DisplayMpc(&c)), "(+5.0 -3.0)");
+///     assert_eq!(format!("{:#x}", DisplayMpc(&c)), "(0x5 -0x3)");
+///     assert_eq!(format!("{:^10}", DisplayMpc(&c)), "  (5 -3)  ");
+///     mpc::clear(&mut c);
+/// }
+/// ```
+///
+/// [`mpc_t`]: struct.mpc_t.html
+/// [`mpfr::DisplayMpfr`]: ../mpfr/struct.DisplayMpfr.html
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayMpc<'a>(pub &'a mpc_t);
+
 // Types for function declarations in this file.
 
 type mpz_srcptr = *const gmp::mpz_t;
@@ -192,6 +314,55 @@ extern "C" {
     /// See: [`mpc_set_d`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fd)
     #[link_name = "mpc_set_d"]
     pub fn set_d(rop: mpc_ptr, op: f64, rnd: rnd_t) -> c_int;
+}
+/// Sets the value of `rop` from `op`, like [`set_ui`] for `u128`.
+///
+/// [`set_ui`]: fn.set_ui.html
+#[inline]
+pub unsafe fn set_u128(rop: mpc_ptr, op: u128, rnd: rnd_t) -> c_int {
+    let inex_re = mpfr::set_u128(realref(rop), op, RND_RE(rnd));
+    let inex_im = mpfr::set_ui(imagref(rop), 0, RND_IM(rnd));
+    INEX(inex_re, inex_im)
+}
+/// Sets the value of `rop` from `op`, like [`set_si`] for `i128`.
+///
+/// [`set_si`]: fn.set_si.html
+#[inline]
+pub unsafe fn set_i128(rop: mpc_ptr, op: i128, rnd: rnd_t) -> c_int {
+    let inex_re = mpfr::set_i128(realref(rop), op, RND_RE(rnd));
+    let inex_im = mpfr::set_ui(imagref(rop), 0, RND_IM(rnd));
+    INEX(inex_re, inex_im)
+}
+/// Sets the value of `rop` from `op1` and `op2`, like [`set_ui_ui`]
+/// for `u128`.
+///
+/// [`set_ui_ui`]: fn.set_ui_ui.html
+#[inline]
+pub unsafe fn set_u128_u128(rop: mpc_ptr, op1: u128, op2: u128, rnd: rnd_t) -> c_int {
+    let inex_re = mpfr::set_u128(realref(rop), op1, RND_RE(rnd));
+    let inex_im = mpfr::set_u128(imagref(rop), op2, RND_IM(rnd));
+    INEX(inex_re, inex_im)
+}
+/// Sets the value of `rop` from `op1` and `op2`, like [`set_si_si`]
+/// for `i128`.
+///
+/// [`set_si_si`]: fn.set_si_si.html
+#[inline]
+pub unsafe fn set_i128_i128(rop: mpc_ptr, op1: i128, op2: i128, rnd: rnd_t) -> c_int {
+    let inex_re = mpfr::set_i128(realref(rop), op1, RND_RE(rnd));
+    let inex_im = mpfr::set_i128(imagref(rop), op2, RND_IM(rnd));
+    INEX(inex_re, inex_im)
+}
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpc_set_ld"]
+    fn glue_set_ld(rop: mpc_ptr, op: *const mpfr::long_double, rnd: rnd_t) -> c_int;
+}
+/// See: [`mpc_set_ld`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fld)
+#[inline]
+pub unsafe extern "C" fn set_ld(rop: mpc_ptr, op: mpfr::long_double, rnd: rnd_t) -> c_int {
+    glue_set_ld(rop, &op, rnd)
+}
+extern "C" {
     /// See: [`mpc_set_z`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fz)
     #[link_name = "mpc_set_z"]
     pub fn set_z(rop: mpc_ptr, op: mpz_srcptr, rnd: rnd_t) -> c_int;
@@ -219,6 +390,49 @@ extern "C" {
     /// See: [`mpc_set_d_d`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fd_005fd)
     #[link_name = "mpc_set_d_d"]
     pub fn set_d_d(rop: mpc_ptr, op1: f64, op2: f64, rnd: rnd_t) -> c_int;
+    /// See: [`mpc_set_dc`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fdc)
+    #[link_name = "mpc_set_dc"]
+    pub fn set_dc(rop: mpc_ptr, op: double_complex, rnd: rnd_t) -> c_int;
+    /// See: [`mpc_get_dc`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fget_005fdc)
+    #[link_name = "mpc_get_dc"]
+    pub fn get_dc(op: mpc_srcptr, rnd: rnd_t) -> double_complex;
+}
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpc_set_ld_ld"]
+    fn glue_set_ld_ld(
+        rop: mpc_ptr,
+        op1: *const mpfr::long_double,
+        op2: *const mpfr::long_double,
+        rnd: rnd_t,
+    ) -> c_int;
+    #[link_name = "gmp_mpfr_sys_mpc_set_ldc"]
+    fn glue_set_ldc(rop: mpc_ptr, op: *const long_double_complex, rnd: rnd_t) -> c_int;
+    #[link_name = "gmp_mpfr_sys_mpc_get_ldc"]
+    fn glue_get_ldc(rop: *mut long_double_complex, op: mpc_srcptr, rnd: rnd_t);
+}
+/// See: [`mpc_set_ld_ld`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fld_005fld)
+#[inline]
+pub unsafe extern "C" fn set_ld_ld(
+    rop: mpc_ptr,
+    op1: mpfr::long_double,
+    op2: mpfr::long_double,
+    rnd: rnd_t,
+) -> c_int {
+    glue_set_ld_ld(rop, &op1, &op2, rnd)
+}
+/// See: [`mpc_set_ldc`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fldc)
+#[inline]
+pub unsafe extern "C" fn set_ldc(rop: mpc_ptr, op: long_double_complex, rnd: rnd_t) -> c_int {
+    glue_set_ldc(rop, &op, rnd)
+}
+/// See: [`mpc_get_ldc`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fget_005fldc)
+#[inline]
+pub unsafe extern "C" fn get_ldc(op: mpc_srcptr, rnd: rnd_t) -> long_double_complex {
+    let mut ldc = long_double_complex::default();
+    glue_get_ldc(&mut ldc, op, rnd);
+    ldc
+}
+extern "C" {
     /// See: [`mpc_set_z_z`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fset_005fz_005fz)
     #[link_name = "mpc_set_z_z"]
     pub fn set_z_z(rop: mpc_ptr, op1: mpz_srcptr, op2: mpz_srcptr, rnd: rnd_t) -> c_int;
@@ -276,7 +490,157 @@ extern "C" {
         op: mpc_srcptr,
         rnd: rnd_t,
     ) -> usize;
-
+}
+/// Returns the number of bytes [`fpif_export_slice`] writes for `op`.
+///
+/// [`fpif_export_slice`]: fn.fpif_export_slice.html
+#[inline]
+pub unsafe fn fpif_len(op: mpc_srcptr) -> usize {
+    mpfr::fpif_len(realref_const(op)) + mpfr::fpif_len(imagref_const(op))
+}
+/// Writes `op` into the beginning of `buf` as two records in the
+/// format of [`mpfr::fpif_export`], the real part followed by the
+/// imaginary part, and returns the number of bytes written.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than [`fpif_len`]`(op)`.
+///
+/// [`fpif_len`]: fn.fpif_len.html
+/// [`mpfr::fpif_export`]: ../mpfr/fn.fpif_export.html
+pub unsafe fn fpif_export_slice(op: mpc_srcptr, buf: &mut [u8]) -> usize {
+    assert!(buf.len() >= fpif_len(op), "buffer too small for FPIF");
+    let re_len = mpfr::fpif_export_slice(realref_const(op), buf);
+    re_len + mpfr::fpif_export_slice(imagref_const(op), &mut buf[re_len..])
+}
+/// Reads two records in the format of [`mpfr::fpif_export`] from the
+/// beginning of `buf` into the real and imaginary parts of `op`, and
+/// returns the number of bytes read.
+///
+/// The precisions of the parts are set to the stored precisions. If
+/// the input is invalid or `buf` is too short, `None` is returned,
+/// and the parts may have been modified as in
+/// [`mpfr::fpif_import_slice`].
+///
+/// [`mpfr::fpif_export`]: ../mpfr/fn.fpif_export.html
+/// [`mpfr::fpif_import_slice`]: ../mpfr/fn.fpif_import_slice.html
+pub unsafe fn fpif_import_slice(op: mpc_ptr, buf: &[u8]) -> Option<usize> {
+    let re_len = mpfr::fpif_import_slice(realref(op), buf)?;
+    let im_len = mpfr::fpif_import_slice(imagref(op), &buf[re_len..])?;
+    Some(re_len + im_len)
+}
+// Writes x into the beginning of buf in the same format as each part
+// written by mpc_get_str, and returns the number of bytes written.
+// The significand is written one byte after the start so that the
+// point can be inserted after its first digit.
+unsafe fn get_part_into(
+    buf: &mut [u8],
+    b: c_int,
+    n: usize,
+    x: mpfr_srcptr,
+    rnd: mpfr::rnd_t,
+) -> usize {
+    if mpfr::zero_p(x) != 0 {
+        buf[0] = if mpfr::signbit(x) != 0 { b'-' } else { b'+' };
+        buf[1] = b'0';
+        return 2;
+    }
+    let mut expo = 0;
+    let len = mpfr::get_str_into(&mut buf[1..], &mut expo, b, n, x, rnd).len();
+    if mpfr::number_p(x) == 0 {
+        buf.copy_within(1..=len, 0);
+        return len;
+    }
+    let lead = if buf[1] == b'-' { 2 } else { 1 };
+    buf.copy_within(1..=lead, 0);
+    buf[lead] = b'.';
+    let mut pos = len + 1;
+    let mut x = i64::from(expo) - 1;
+    if b == 16 {
+        // like MPC, write a binary exponent
+        x = x.wrapping_mul(4);
+    }
+    if x != 0 {
+        buf[pos] = match b {
+            10 => b'e',
+            2 | 16 => b'p',
+            _ => b'@',
+        };
+        buf[pos + 1] = if x < 0 { b'-' } else { b'+' };
+        pos += 2;
+        let mut mag = x.wrapping_abs() as u64;
+        let mut digits = [0u8; 20];
+        let mut start = digits.len();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (mag % 10) as u8;
+            mag /= 10;
+            if mag == 0 {
+                break;
+            }
+        }
+        let digits = &digits[start..];
+        buf[pos..pos + digits.len()].copy_from_slice(digits);
+        pos += digits.len();
+    }
+    pos
+}
+/// Returns a buffer length that is large enough for [`get_str_into`]
+/// with the same `b` and `n`, including space for the NUL terminator.
+///
+/// # Panics
+///
+/// Panics if `b` is not in the range 2 to 36.
+///
+/// [`get_str_into`]: fn.get_str_into.html
+pub unsafe fn get_str_len(b: c_int, n: usize, op: mpc_srcptr) -> usize {
+    assert!((2..=36).contains(&b), "base out of range");
+    // each part also needs a point, an exponent letter, an exponent
+    // sign and up to 20 exponent digits
+    let re_len = mpfr::get_str_len(b, n, realref_const(op)) + 23;
+    let im_len = mpfr::get_str_len(b, n, imagref_const(op)) + 23;
+    re_len + im_len + 4
+}
+/// Writes `op` into `buf` like [`get_str`], and returns the written
+/// string without the NUL terminator.
+///
+/// # Panics
+///
+/// Panics if `b` is not in the range 2 to 36, or if `buf` is shorter
+/// than [`get_str_len`]`(b, n, op)`.
+///
+/// [`get_str_len`]: fn.get_str_len.html
+/// [`get_str`]: fn.get_str.html
+pub unsafe fn get_str_into(buf: &mut [u8], b: c_int, n: usize, op: mpc_srcptr, rnd: rnd_t) -> &str {
+    assert!(buf.len() >= get_str_len(b, n, op), "buffer too small");
+    buf[0] = b'(';
+    let mut pos = 1;
+    pos += get_part_into(&mut buf[pos..], b, n, realref_const(op), RND_RE(rnd));
+    buf[pos] = b' ';
+    pos += 1;
+    pos += get_part_into(&mut buf[pos..], b, n, imagref_const(op), RND_IM(rnd));
+    buf[pos] = b')';
+    buf[pos + 1] = 0;
+    gmp::str_before_nul(buf)
+}
+/// Returns `op` like [`get_str`], without using the MPC memory
+/// functions.
+///
+/// This function is only available if the `std` feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `b` is not in the range 2 to 36.
+///
+/// [`get_str`]: fn.get_str.html
+#[cfg(feature = "std")]
+pub unsafe fn get_string(b: c_int, n: usize, op: mpc_srcptr, rnd: rnd_t) -> String {
+    let mut v = std::vec![0; get_str_len(b, n, op)];
+    let len = get_str_into(&mut v, b, n, op, rnd).len();
+    v.truncate(len);
+    String::from_utf8_unchecked(v)
+}
+extern "C" {
     // Comparison Functions
 
     /// See: [`mpc_cmp`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fcmp)
@@ -340,6 +704,14 @@ extern "C" {
     /// See: [`mpc_add_ui`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fadd_005fui)
     #[link_name = "mpc_add_ui"]
     pub fn add_ui(rop: mpc_ptr, op1: mpc_srcptr, op2: c_ulong, rnd: rnd_t) -> c_int;
+    /// See: [`mpc_add`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fadd)
+    ///
+    /// `mpc_add_si` is declared in the [MPC] header but is not
+    /// documented.
+    ///
+    /// [MPC]: http://www.multiprecision.org/mpc/
+    #[link_name = "mpc_add_si"]
+    pub fn add_si(rop: mpc_ptr, op1: mpc_srcptr, op2: c_long, rnd: rnd_t) -> c_int;
     /// See: [`mpc_add_fr`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fadd_005ffr)
     #[link_name = "mpc_add_fr"]
     pub fn add_fr(rop: mpc_ptr, op1: mpc_srcptr, op2: mpfr_srcptr, rnd: rnd_t) -> c_int;
@@ -449,6 +821,27 @@ extern "C" {
     /// See: [`mpc_pow_d`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fpow_005fd)
     #[link_name = "mpc_pow_d"]
     pub fn pow_d(rop: mpc_ptr, op1: mpc_srcptr, op2: f64, rnd: rnd_t) -> c_int;
+}
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpc_pow_ld"]
+    fn glue_pow_ld(
+        rop: mpc_ptr,
+        op1: mpc_srcptr,
+        op2: *const mpfr::long_double,
+        rnd: rnd_t,
+    ) -> c_int;
+}
+/// See: [`mpc_pow_ld`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fpow_005fld)
+#[inline]
+pub unsafe extern "C" fn pow_ld(
+    rop: mpc_ptr,
+    op1: mpc_srcptr,
+    op2: mpfr::long_double,
+    rnd: rnd_t,
+) -> c_int {
+    glue_pow_ld(rop, op1, &op2, rnd)
+}
+extern "C" {
     /// See: [`mpc_pow_si`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/Complex-Functions.html#index-mpc_005fpow_005fsi)
     #[link_name = "mpc_pow_si"]
     pub fn pow_si(rop: mpc_ptr, op1: mpc_srcptr, op2: c_long, rnd: rnd_t) -> c_int;
@@ -547,9 +940,148 @@ pub extern "C" fn VERSION_NUM(major: c_int, minor: c_int, patchlevel: c_int) ->
     (major << 16) | (minor << 8) | patchlevel
 }
 
+// Formatting
+
+fn fmt_mpc(
+    op: &mpc_t,
+    f: &mut Formatter<'_>,
+    base: c_int,
+    exp_style: bool,
+    prefix: &str,
+) -> FmtResult {
+    display::fmt_complex(&op.re, &op.im, f, base, exp_style, prefix)
+}
+
+impl_fmt_traits! { DisplayMpc, fmt_mpc }
+
+// Owned types
+
+/// An owned, initialized [`mpc_t`] that is cleared when dropped.
+///
+/// The value can be passed to any function in this module using
+/// [`as_raw`] and [`as_raw_mut`]. Cloning preserves the precisions of
+/// both parts: it uses [`init3`] with the precisions of the source
+/// followed by [`set`]. `clone_from` sets the precision of each part
+/// to that of the source if they differ.
+///
+/// This type is only available if the `owned` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpc::{self, Mpc};
+/// let mut c = Mpc::new(53);
+/// unsafe {
+///     mpc::set_si_si(c.as_raw_mut(), 1, -2, mpc::RNDNN);
+///     mpc::sqr(c.as_raw_mut(), c.as_raw(), mpc::RNDNN);
+/// }
+/// assert_eq!(format!("{}", c.clone()), "(-3 -4)");
+/// ```
+///
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`as_raw`]: #method.as_raw
+/// [`init3`]: fn.init3.html
+/// [`mpc_t`]: struct.mpc_t.html
+/// [`set`]: fn.set.html
+#[cfg(feature = "owned")]
+pub struct Mpc {
+    inner: mpc_t,
+}
+
+#[cfg(feature = "owned")]
+impl_owned! { Mpc, mpc_t, clear }
+
+#[cfg(feature = "owned")]
+impl Mpc {
+    /// Creates a complex number with the value (NaN NaN) and a
+    /// precision of `prec` bits for both parts using [`init2`].
+    ///
+    /// [`init2`]: fn.init2.html
+    #[inline]
+    pub fn new(prec: mpfr::prec_t) -> Mpc {
+        Mpc::new3(prec, prec)
+    }
+
+    /// Creates a complex number with the value (NaN NaN) and the
+    /// precisions `prec_r` and `prec_i` for the real and imaginary
+    /// parts using [`init3`].
+    ///
+    /// [`init3`]: fn.init3.html
+    #[inline]
+    pub fn new3(prec_r: mpfr::prec_t, prec_i: mpfr::prec_t) -> Mpc {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            init3(inner.as_mut_ptr(), prec_r, prec_i);
+            Mpc {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Default for Mpc {
+    /// Creates a complex number with the value (NaN NaN) and the
+    /// default [MPFR] precision for both parts.
+    ///
+    /// [MPFR]: https://www.mpfr.org/
+    #[inline]
+    fn default() -> Mpc {
+        Mpc::new(unsafe { mpfr::get_default_prec() })
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Clone for Mpc {
+    #[inline]
+    fn clone(&self) -> Mpc {
+        let (mut prec_r, mut prec_i) = (0, 0);
+        unsafe {
+            get_prec2(&mut prec_r, &mut prec_i, &self.inner);
+        }
+        let mut ret = Mpc::new3(prec_r, prec_i);
+        ret.clone_from(self);
+        ret
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Mpc) {
+        unsafe {
+            let src_re = realref_const(&source.inner);
+            let src_im = imagref_const(&source.inner);
+            if mpfr::get_prec(&self.inner.re) != mpfr::get_prec(src_re) {
+                mpfr::set_prec(realref(&mut self.inner), mpfr::get_prec(src_re));
+            }
+            if mpfr::get_prec(&self.inner.im) != mpfr::get_prec(src_im) {
+                mpfr::set_prec(imagref(&mut self.inner), mpfr::get_prec(src_im));
+            }
+            // exact, as the precisions are the same; the flags are
+            // restored as setting a NaN raises the NaN flag
+            let flags = mpfr::flags_save();
+            set(&mut self.inner, &source.inner, RNDNN);
+            mpfr::flags_restore(flags, !0);
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Debug for Mpc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpc(&self.inner), f)
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Display for Mpc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpc(&self.inner), f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{gmp, mpc, mpfr};
+    use core::mem::MaybeUninit;
 
     #[test]
     fn check_real_imag_offsets() {
@@ -576,6 +1108,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn check_rnd() {
+        let (n, z, u, d) = (
+            mpfr::rnd_t::RNDN,
+            mpfr::rnd_t::RNDZ,
+            mpfr::rnd_t::RNDU,
+            mpfr::rnd_t::RNDD,
+        );
+        assert_eq!(mpc::RND(n, n), mpc::RNDNN);
+        assert_eq!(mpc::RND(z, u), mpc::RNDZU);
+        assert_eq!(mpc::RND(d, z), mpc::RNDDZ);
+        assert_eq!(mpc::RND_RE(mpc::RNDUD), u);
+        assert_eq!(mpc::RND_IM(mpc::RNDUD), d);
+        let rnd = mpc::RND(mpfr::rnd_t::RNDA, mpfr::rnd_t::RNDF);
+        assert_eq!(mpc::RND_RE(rnd), mpfr::rnd_t::RNDA);
+        assert_eq!(mpc::RND_IM(rnd), mpfr::rnd_t::RNDF);
+    }
+
     #[test]
     fn check_version() {
         use crate::tests;
@@ -594,4 +1144,258 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn check_long_double_complex() {
+        unsafe {
+            let mut c = MaybeUninit::uninit();
+            mpc::init2(c.as_mut_ptr(), 53);
+            let mut c = c.assume_init();
+            let re = mpfr::long_double::from_f64(1.5);
+            let im = mpfr::long_double::from_f64(-0.25);
+            assert_eq!(mpc::set_ld_ld(&mut c, re, im, mpc::RNDNN), 0);
+
+            let ldc = mpc::get_ldc(&c, mpc::RNDNN);
+            assert_eq!(ldc.re.to_f64(), 1.5);
+            assert_eq!(ldc.im.to_f64(), -0.25);
+
+            let mut d = MaybeUninit::uninit();
+            mpc::init2(d.as_mut_ptr(), 53);
+            let mut d = d.assume_init();
+            assert_eq!(mpc::set_ldc(&mut d, ldc, mpc::RNDNN), 0);
+            assert_eq!(mpc::cmp(&c, &d), 0);
+
+            mpc::set_ld(&mut c, mpfr::long_double::from_f64(2.0), mpc::RNDNN);
+            mpc::pow_ld(&mut d, &c, mpfr::long_double::from_f64(0.5), mpc::RNDNN);
+            let ldc = mpc::get_ldc(&d, mpc::RNDNN);
+            assert_eq!(ldc.re.to_f64(), core::f64::consts::SQRT_2);
+            assert_eq!(ldc.im.to_f64(), 0.0);
+
+            mpc::clear(&mut d);
+            mpc::clear(&mut c);
+        }
+    }
+
+    extern "C" {
+        // from the C library, to pass double _Complex values through C
+        fn conj(z: mpc::double_complex) -> mpc::double_complex;
+        fn cimag(z: mpc::double_complex) -> f64;
+    }
+
+    #[test]
+    fn check_double_complex() {
+        unsafe {
+            let z = mpc::double_complex { re: 1.5, im: -0.25 };
+            assert_eq!(cimag(z), -0.25);
+            let z = conj(z);
+            assert_eq!((z.re, z.im), (1.5, 0.25));
+
+            let mut c = MaybeUninit::uninit();
+            mpc::init2(c.as_mut_ptr(), 53);
+            let mut c = c.assume_init();
+            assert_eq!(mpc::set_dc(&mut c, z, mpc::RNDNN), 0);
+            assert_eq!(mpfr::get_d(mpc::realref_const(&c), mpfr::rnd_t::RNDN), 1.5);
+            assert_eq!(mpfr::get_d(mpc::imagref_const(&c), mpfr::rnd_t::RNDN), 0.25);
+
+            assert_eq!(mpc::add_si(&mut c, &c, -3, mpc::RNDNN), 0);
+            let z = mpc::get_dc(&c, mpc::RNDNN);
+            assert_eq!((z.re, z.im), (-1.5, 0.25));
+            assert_eq!(cimag(conj(z)), -0.25);
+
+            mpc::clear(&mut c);
+        }
+    }
+
+    #[test]
+    fn check_u128() {
+        unsafe {
+            let mut c = MaybeUninit::uninit();
+            mpc::init2(c.as_mut_ptr(), 53);
+            let mut c = c.assume_init();
+
+            let inex = mpc::set_u128_u128(&mut c, !0, 1 << 100, mpc::RNDZN);
+            assert!(mpc::INEX_RE(inex) < 0);
+            assert_eq!(mpc::INEX_IM(inex), 0);
+            let re = mpc::realref_const(&c);
+            let im = mpc::imagref_const(&c);
+            assert!(mpfr::cmp_ui_2exp(re, 1, 128) < 0);
+            assert_eq!(mpfr::cmp_ui_2exp(im, 1, 100), 0);
+
+            let inex = mpc::set_i128(&mut c, -((!0u128 >> 1) as i128), mpc::RNDND);
+            assert!(mpc::INEX_RE(inex) < 0);
+            assert_eq!(mpc::INEX_IM(inex), 0);
+            assert_eq!(mpfr::cmp_si_2exp(re, -1, 127), 0);
+            assert!(mpfr::zero_p(im) != 0 && mpfr::signbit(im) == 0);
+
+            let inex = mpc::set_i128_i128(&mut c, -3, 1 << 60, mpc::RNDNN);
+            assert_eq!(inex, 0);
+            assert_eq!(mpc::cmp_si_si(&c, -3, 1 << 60), 0);
+            assert_eq!(mpc::set_u128(&mut c, 7, mpc::RNDNN), 0);
+            assert_eq!(mpc::cmp_si_si(&c, 7, 0), 0);
+
+            mpc::clear(&mut c);
+        }
+    }
+
+    #[test]
+    fn check_fpif() {
+        unsafe {
+            let mut c = MaybeUninit::uninit();
+            mpc::init3(c.as_mut_ptr(), 53, 100);
+            let mut c = c.assume_init();
+            mpc::set_si_si(&mut c, 1, -1000, mpc::RNDNN);
+            mpc::div_ui(&mut c, &c, 3, mpc::RNDNN);
+
+            // the C library writes the real part followed by the imaginary part
+            let file = libc::tmpfile();
+            assert!(!file.is_null());
+            assert_eq!(mpfr::fpif_export(file, mpc::realref(&mut c)), 0);
+            assert_eq!(mpfr::fpif_export(file, mpc::imagref(&mut c)), 0);
+            let c_len = libc::ftell(file) as usize;
+            libc::rewind(file);
+            let mut from_c = [0u8; 64];
+            let read = libc::fread(from_c.as_mut_ptr() as *mut libc::c_void, 1, c_len, file);
+            assert_eq!(read, c_len);
+            libc::fclose(file);
+
+            let mut from_rust = [0u8; 64];
+            let len = mpc::fpif_export_slice(&c, &mut from_rust);
+            assert_eq!(len, mpc::fpif_len(&c));
+            assert_eq!(&from_rust[..len], &from_c[..c_len]);
+
+            let mut d = MaybeUninit::uninit();
+            mpc::init2(d.as_mut_ptr(), 1);
+            let mut d = d.assume_init();
+            assert_eq!(mpc::fpif_import_slice(&mut d, &from_rust[..len - 1]), None);
+            assert_eq!(mpc::fpif_import_slice(&mut d, &from_rust), Some(len));
+            assert_eq!(mpfr::get_prec(mpc::realref_const(&d)), 53);
+            assert_eq!(mpfr::get_prec(mpc::imagref_const(&d)), 100);
+            assert_eq!(mpc::cmp(&c, &d), 0);
+
+            mpc::clear(&mut d);
+            mpc::clear(&mut c);
+        }
+    }
+
+    #[test]
+    fn check_get_str_into() {
+        unsafe {
+            let mut c = MaybeUninit::uninit();
+            mpc::init3(c.as_mut_ptr(), 53, 100);
+            let mut c = c.assume_init();
+
+            let mut buf = [0u8; 256];
+            for i in 0..5 {
+                match i {
+                    0 => {
+                        mpc::set_si_si(&mut c, 0, 0, mpc::RNDNN);
+                        mpc::neg(&mut c, &c, mpc::RNDNN);
+                    }
+                    1 => {
+                        mpc::set_si_si(&mut c, 1, -1000, mpc::RNDNN);
+                        mpc::div_ui(&mut c, &c, 3, mpc::RNDNN);
+                    }
+                    2 => {
+                        mpc::set_nan(&mut c);
+                        mpfr::set_inf(mpc::imagref(&mut c), -1);
+                    }
+                    3 => {
+                        mpfr::set_si_2exp(mpc::realref(&mut c), -1, -300, mpfr::rnd_t::RNDN);
+                        mpfr::set_ui_2exp(mpc::imagref(&mut c), 3, 300, mpfr::rnd_t::RNDN);
+                    }
+                    _ => {
+                        mpc::set_si_si(&mut c, 7, 1, mpc::RNDNN);
+                    }
+                }
+                for &b in &[2, 3, 10, 16, 36] {
+                    for &n in &[0, 3] {
+                        let c_str = mpc::get_str(b, n, &c, mpc::RNDNN);
+                        let expected = crate::tests::str_from_cstr(c_str);
+                        let len = mpc::get_str_len(b, n, &c);
+                        assert!(expected.len() < len);
+                        let s = mpc::get_str_into(&mut buf[..len], b, n, &c, mpc::RNDNN);
+                        assert_eq!(s, expected);
+                        #[cfg(feature = "std")]
+                        {
+                            assert_eq!(mpc::get_string(b, n, &c, mpc::RNDNN), expected);
+                        }
+                        mpc::free_str(c_str);
+                    }
+                }
+            }
+
+            mpc::clear(&mut c);
+        }
+    }
+
+    #[test]
+    fn check_display() {
+        use crate::tests::check_fmt;
+        use mpc::DisplayMpc;
+
+        unsafe {
+            let mut c = MaybeUninit::uninit();
+            mpc::init2(c.as_mut_ptr(), 53);
+            let mut c = c.assume_init();
+            mpc::set_si_si(&mut c, 5, -3, mpc::RNDNN);
+            check_fmt!("{}", DisplayMpc(&c), "(5 -3)");
+            check_fmt!("{:+.1}", DisplayMpc(&c), "(+5.0 -3.0)");
+            check_fmt!("{:e}", DisplayMpc(&c), "(5e0 -3e0)");
+            check_fmt!("{:.1E}", DisplayMpc(&c), "(5.0E0 -3.0E0)");
+            check_fmt!("{:#b}", DisplayMpc(&c), "(0b101 -0b11)");
+            check_fmt!("{:#x}", DisplayMpc(&c), "(0x5 -0x3)");
+            check_fmt!("{:^10}", DisplayMpc(&c), "  (5 -3)  ");
+            check_fmt!("{:>010}", DisplayMpc(&c), "    (5 -3)");
+            check_fmt!("{:6}", DisplayMpc(&c), "(5 -3)");
+            mpc::set_d_d(&mut c, -0.0, f64::NAN, mpc::RNDNN);
+            check_fmt!("{}", DisplayMpc(&c), "(-0 NaN)");
+            check_fmt!("{:+.2}", DisplayMpc(&c), "(-0.00 NaN)");
+            mpfr::set_inf(&mut c.im, 1);
+            check_fmt!("{:+#o}", DisplayMpc(&c), "(-0o0 +inf)");
+            mpc::set_d_d(&mut c, 0.1, 2.5, mpc::RNDNN);
+            check_fmt!("{}", DisplayMpc(&c), "(0.10000000000000001 2.5)");
+            check_fmt!("{:.0}", DisplayMpc(&c), "(0 2)");
+            mpc::clear(&mut c);
+        }
+    }
+
+    #[cfg(feature = "owned")]
+    #[test]
+    fn check_owned() {
+        use crate::tests::format;
+        use mpc::Mpc;
+
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<Mpc>();
+
+        unsafe {
+            let mut a = Mpc::new3(20, 40);
+            mpc::set_si_si(a.as_raw_mut(), 3, -4, mpc::RNDNN);
+            let b = a.clone();
+            let (mut prec_r, mut prec_i) = (0, 0);
+            mpc::get_prec2(&mut prec_r, &mut prec_i, b.as_raw());
+            assert_eq!((prec_r, prec_i), (20, 40));
+            assert_eq!(mpc::cmp(a.as_raw(), b.as_raw()), 0);
+            let mut c = Mpc::new(8);
+            c.clone_from(&a);
+            mpc::get_prec2(&mut prec_r, &mut prec_i, c.as_raw());
+            assert_eq!((prec_r, prec_i), (20, 40));
+            mpc::conj(c.as_raw_mut(), c.as_raw(), mpc::RNDNN);
+            assert_eq!(
+                format(format_args!("{} {:?}", a, c)).as_str(),
+                "(3 -4) (3 4)"
+            );
+            let raw = c.into_raw();
+            let c = Mpc::from_raw(raw);
+            assert_eq!(mpc::cmp_si_si(c.as_raw(), 3, 4), 0);
+
+            // cloning a NaN does not raise the NaN flag
+            mpfr::clear_flags();
+            let nan = Mpc::new(8);
+            let mut d = nan.clone();
+            d.clone_from(&nan);
+            assert!(mpfr::nan_p(mpc::realref_const(d.as_raw())) != 0);
+            assert_eq!(mpfr::nanflag_p(), 0);
+        }
+    }
 }
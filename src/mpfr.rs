@@ -99,36 +99,135 @@ fn main() {
 #![allow(non_camel_case_types, non_snake_case)]
 #![allow(clippy::needless_doctest_main)]
 
-use crate::gmp;
-use core::mem;
+use crate::{
+    display::{self, Class, FloatDigits},
+    gmp,
+};
+#[cfg(const_generics)]
+use core::cell::Cell;
+#[cfg(any(feature = "owned", const_generics))]
+use core::fmt::{Debug, Display};
+use core::{
+    cmp,
+    convert::TryFrom,
+    fmt::{Formatter, Result as FmtResult},
+    marker::PhantomData,
+    mem,
+    mem::MaybeUninit,
+    ops::Deref,
+    slice,
+};
+#[cfg(feature = "owned")]
+use core::{
+    ops::Index,
+    ptr::{self, NonNull},
+};
 use libc::{c_char, c_int, c_long, c_uint, c_ulong, c_void, intmax_t, uintmax_t, FILE};
+#[cfg(feature = "std")]
+use std::string::String;
 
 include!(concat!(env!("OUT_DIR"), "/mpfr_h.rs"));
+include!(concat!(env!("OUT_DIR"), "/mpfr_layout.rs"));
 
 /// See: [`mpfr_prec_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005fprec_005ft)
 pub type prec_t = c_long;
 
-// TODO: breaking change: do not use enum
 /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
-#[repr(C)]
-#[derive(Clone, Copy, Debug)]
-#[allow(deprecated)]
-pub enum rnd_t {
+///
+/// This is a transparent wrapper around the C `int` that holds an
+/// [MPFR] rounding mode, so that any value received from C, for
+/// example from [`get_default_rounding_mode`], is a valid value of
+/// this type. The rounding modes are associated constants.
+/// Exhaustive matching is possible after conversion to [`rnd_enum`].
+///
+/// # Examples
+///
+/// ```rust
+/// use core::convert::TryFrom;
+/// use gmp_mpfr_sys::mpfr::{rnd_enum, rnd_t};
+/// let rnd = rnd_t::RNDZ;
+/// assert_eq!(rnd.0, 1);
+/// assert_eq!(rnd_enum::try_from(rnd), Ok(rnd_enum::RNDZ));
+/// assert_eq!(rnd_t::from(rnd_enum::RNDZ), rnd);
+/// assert_eq!(rnd_enum::try_from(rnd_t(42)), Err(rnd_t(42)));
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`get_default_rounding_mode`]: fn.get_default_rounding_mode.html
+/// [`rnd_enum`]: enum.rnd_enum.html
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct rnd_t(pub c_int);
+
+impl rnd_t {
+    /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
+    pub const RNDN: rnd_t = rnd_t(0);
+    /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
+    pub const RNDZ: rnd_t = rnd_t(1);
+    /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
+    pub const RNDU: rnd_t = rnd_t(2);
+    /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
+    pub const RNDD: rnd_t = rnd_t(3);
+    /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
+    pub const RNDA: rnd_t = rnd_t(4);
+    /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
+    pub const RNDF: rnd_t = rnd_t(5);
+    // internal to MPFR, used by mpfr_round
+    const RNDNA: rnd_t = rnd_t(-1);
+}
+
+/// The rounding modes of [`rnd_t`] as an enum.
+///
+/// This was the definition of `rnd_t` before version 2.0.0. It is
+/// kept for exhaustive matching, but it is not used in any function
+/// signature, as C can return values that are not valid variants.
+///
+/// [`rnd_t`]: struct.rnd_t.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum rnd_enum {
     /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
-    RNDN = 0,
+    RNDN,
     /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
-    RNDZ = 1,
+    RNDZ,
     /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
-    RNDU = 2,
+    RNDU,
     /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
-    RNDD = 3,
+    RNDD,
     /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
-    RNDA = 4,
+    RNDA,
     /// See: [`mpfr_rnd_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005frnd_005ft)
-    RNDF = 5,
-    #[doc(hidden)]
-    #[deprecated(since = "1.1.0", note = "do not use!")]
-    RNDNA = -1,
+    RNDF,
+}
+
+impl From<rnd_enum> for rnd_t {
+    #[inline]
+    fn from(src: rnd_enum) -> rnd_t {
+        match src {
+            rnd_enum::RNDN => rnd_t::RNDN,
+            rnd_enum::RNDZ => rnd_t::RNDZ,
+            rnd_enum::RNDU => rnd_t::RNDU,
+            rnd_enum::RNDD => rnd_t::RNDD,
+            rnd_enum::RNDA => rnd_t::RNDA,
+            rnd_enum::RNDF => rnd_t::RNDF,
+        }
+    }
+}
+
+impl TryFrom<rnd_t> for rnd_enum {
+    /// The unrecognized rounding mode.
+    type Error = rnd_t;
+    #[inline]
+    fn try_from(src: rnd_t) -> Result<rnd_enum, rnd_t> {
+        match src {
+            rnd_t::RNDN => Ok(rnd_enum::RNDN),
+            rnd_t::RNDZ => Ok(rnd_enum::RNDZ),
+            rnd_t::RNDU => Ok(rnd_enum::RNDU),
+            rnd_t::RNDD => Ok(rnd_enum::RNDD),
+            rnd_t::RNDA => Ok(rnd_enum::RNDA),
+            rnd_t::RNDF => Ok(rnd_enum::RNDF),
+            _ => Err(src),
+        }
+    }
 }
 
 /// See: [`mpfr_flags_t`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#index-mpfr_005fflags_005ft)
@@ -139,6 +238,230 @@ pub type exp_t = c_long;
 
 type uexp_t = c_ulong;
 
+/// The C `long double` type of the target.
+///
+/// Rust has no type with the ABI of `long double`, so this type has
+/// the same size and alignment as `long double` but it can only be
+/// passed to C by pointer. Functions such as [`set_ld`] and
+/// [`get_ld`] take or return it by value, and pass it by pointer to
+/// small C wrappers around the [MPFR] functions.
+///
+/// The format depends on the target, for example it is the x87
+/// 80-bit extended-precision format on x86 and x86_64, and IEEE
+/// binary128 on aarch64 GNU/Linux.
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`get_ld`]: fn.get_ld.html
+/// [`set_ld`]: fn.set_ld.html
+#[repr(C)]
+#[cfg_attr(long_double_align = "4", repr(align(4)))]
+#[cfg_attr(long_double_align = "8", repr(align(8)))]
+#[cfg_attr(long_double_align = "16", repr(align(16)))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct long_double {
+    bytes: [u8; LONG_DOUBLE_SIZE],
+}
+
+#[cfg(long_double_size = "8")]
+const LONG_DOUBLE_SIZE: usize = 8;
+#[cfg(long_double_size = "12")]
+const LONG_DOUBLE_SIZE: usize = 12;
+#[cfg(long_double_size = "16")]
+const LONG_DOUBLE_SIZE: usize = 16;
+
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_ld_from_d"]
+    fn ld_from_d(rop: *mut long_double, op: f64);
+    #[link_name = "gmp_mpfr_sys_ld_to_d"]
+    fn ld_to_d(op: *const long_double) -> f64;
+}
+
+impl long_double {
+    /// Converts an `f64` to a `long double`, which is exact.
+    #[inline]
+    pub fn from_f64(val: f64) -> long_double {
+        let mut ld = long_double::default();
+        unsafe {
+            ld_from_d(&mut ld, val);
+        }
+        ld
+    }
+
+    /// Converts a `long double` to an `f64`, rounding as the C
+    /// compiler would.
+    #[inline]
+    pub fn to_f64(self) -> f64 {
+        unsafe { ld_to_d(&self) }
+    }
+
+    /// Returns the memory representation of the `long double`,
+    /// including any padding bytes.
+    #[inline]
+    pub fn to_ne_bytes(self) -> [u8; LONG_DOUBLE_SIZE] {
+        self.bytes
+    }
+
+    /// Creates a `long double` from its memory representation.
+    #[inline]
+    pub fn from_ne_bytes(bytes: [u8; LONG_DOUBLE_SIZE]) -> long_double {
+        long_double { bytes }
+    }
+}
+
+/// An IEEE binary128 quadruple-precision value, with the layout of
+/// the C `__float128` type.
+///
+/// Like [`long_double`], this type can only be passed to C by
+/// pointer, so [`set_float128`] and [`get_float128`] pass it by
+/// pointer to small C wrappers around the [MPFR] functions. Those
+/// two functions are only available if the `mpfr-float128` feature
+/// is enabled, but the type itself and its conversions are always
+/// available.
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`get_float128`]: fn.get_float128.html
+/// [`long_double`]: struct.long_double.html
+/// [`set_float128`]: fn.set_float128.html
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct float128 {
+    bytes: [u8; 16],
+}
+
+const F128_EXP_MAX: u128 = 0x7fff;
+const F128_EXP_BIAS: i32 = 16383;
+const F128_MANT_BITS: u32 = 112;
+
+impl float128 {
+    /// Creates a `float128` from its bit pattern.
+    #[inline]
+    pub fn from_bits(bits: u128) -> float128 {
+        float128 {
+            bytes: bits.to_ne_bytes(),
+        }
+    }
+
+    /// Returns the bit pattern of the `float128`.
+    #[inline]
+    pub fn to_bits(self) -> u128 {
+        u128::from_ne_bytes(self.bytes)
+    }
+
+    /// Converts an `f64` to a `float128`, which is exact.
+    ///
+    /// The payload of a NaN is preserved.
+    pub fn from_f64(val: f64) -> float128 {
+        let bits = val.to_bits();
+        let sign = u128::from(bits >> 63) << 127;
+        let biased = (bits >> 52) & 0x7ff;
+        let mant = bits & ((1 << 52) - 1);
+        let (exp, frac) = if biased == 0x7ff {
+            (F128_EXP_MAX, mant)
+        } else if biased == 0 && mant == 0 {
+            (0, 0)
+        } else if biased == 0 {
+            // subnormal f64 values are normal binary128 values
+            let top = 63 - mant.leading_zeros();
+            let exp = top as i32 - 1074 + F128_EXP_BIAS;
+            (exp as u128, (mant << (52 - top)) & ((1 << 52) - 1))
+        } else {
+            let exp = biased as i32 - 1023 + F128_EXP_BIAS;
+            (exp as u128, mant)
+        };
+        float128::from_bits(sign | exp << F128_MANT_BITS | u128::from(frac) << 60)
+    }
+
+    /// Converts a `float128` to an `f64`, rounding to the nearest
+    /// with ties to even.
+    ///
+    /// Values too large for `f64` become infinite, and a NaN stays a
+    /// NaN, with the most significant bits of its payload preserved.
+    pub fn to_f64(self) -> f64 {
+        let bits = self.to_bits();
+        let sign = ((bits >> 127) as u64) << 63;
+        let biased = (bits >> F128_MANT_BITS) & F128_EXP_MAX;
+        let mant = bits & ((1 << F128_MANT_BITS) - 1);
+        let inf = 0x7ff << 52;
+        if biased == F128_EXP_MAX {
+            if mant == 0 {
+                return f64::from_bits(sign | inf);
+            }
+            let payload = (mant >> 60) as u64 | 1 << 51;
+            return f64::from_bits(sign | inf | payload);
+        }
+        // binary128 subnormals are much too small for f64
+        if biased == 0 {
+            return f64::from_bits(sign);
+        }
+        let exp = biased as i32 - F128_EXP_BIAS;
+        let sig = 1 << F128_MANT_BITS | mant;
+        let magnitude = if exp > 1023 {
+            inf
+        } else if exp >= -1022 {
+            // rounded includes the implicit bit, and rounding up to
+            // 2^53 carries into the exponent, possibly up to infinity
+            let rounded = round_shift(sig, F128_MANT_BITS - 52) as u64;
+            (((exp + 1022) as u64) << 52) + rounded
+        } else {
+            let shift = (F128_MANT_BITS as i32 - 1074 - exp) as u32;
+            if shift > F128_MANT_BITS + 1 {
+                0
+            } else {
+                round_shift(sig, shift) as u64
+            }
+        };
+        f64::from_bits(sign | magnitude)
+    }
+}
+
+/// An IEEE 754 decimal64 value, with the layout of the C `_Decimal64`
+/// type.
+///
+/// The bits are in the encoding used by the C compiler, which is the
+/// binary integer decimal (BID) encoding on x86 and x86_64, and the
+/// densely packed decimal (DPD) encoding on PowerPC. Like
+/// [`long_double`], this type can only be passed to C by pointer, so
+/// [`set_decimal64`] and [`get_decimal64`] pass it by pointer to
+/// small C wrappers around the [MPFR] functions. Those two functions
+/// are only available if the `mpfr-decimal-float` feature is
+/// enabled.
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`get_decimal64`]: fn.get_decimal64.html
+/// [`long_double`]: struct.long_double.html
+/// [`set_decimal64`]: fn.set_decimal64.html
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct decimal64 {
+    bits: u64,
+}
+
+impl decimal64 {
+    /// Creates a `decimal64` from its bit pattern.
+    #[inline]
+    pub const fn from_bits(bits: u64) -> decimal64 {
+        decimal64 { bits }
+    }
+
+    /// Returns the bit pattern of the `decimal64`.
+    #[inline]
+    pub const fn to_bits(self) -> u64 {
+        self.bits
+    }
+}
+
+// shifts right by shift > 0 rounding to the nearest with ties to even
+fn round_shift(val: u128, shift: u32) -> u128 {
+    let quot = val >> shift;
+    let rem = val & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    if rem > half || (rem == half && quot & 1 != 0) {
+        quot + 1
+    } else {
+        quot
+    }
+}
+
 /// See: [Nomenclature and Types](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#Nomenclature-and-Types)
 pub const PREC_MIN: prec_t = 1;
 /// See: [Nomenclature and Types](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Basics.html#Nomenclature-and-Types)
@@ -153,7 +476,7 @@ pub const PREC_MAX: prec_t = ((!(0 as uprec_t) >> 1) - 256) as prec_t;
 /// internals may change in new minor releases of this crate, though
 /// they will be kept unchanged for patch releases. Any code that
 /// makes use of these internals should list the dependency as
-/// `version = "~1.2"` inside [*Cargo.toml*], *not* `version = "1.2"`.
+/// `version = "~2.0"` inside [*Cargo.toml*], *not* `version = "2.0"`.
 ///
 /// [*Cargo.toml*]: https://doc.rust-lang.org/cargo/guide/dependencies.html
 #[repr(C)]
@@ -183,6 +506,54 @@ pub const FREE_LOCAL_CACHE: c_int = 1;
 /// See: [`mpfr_free_cache2`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffree_005fcache2)
 pub const FREE_GLOBAL_CACHE: c_int = 2;
 
+/// A wrapper to format an [`mpfr_t`] using `core::fmt`.
+///
+/// `Display` writes the number in decimal, `Binary`, `Octal`,
+/// `LowerHex` and `UpperHex` write it in base 2, 8 and 16, and
+/// `LowerExp` and `UpperExp` write it in decimal exponent
+/// notation. Without a precision, the number is written with enough
+/// digits to read it back exactly and without trailing zeros, and
+/// except for exponent notation, in positional notation unless the
+/// exponent is less than −4 or not less than the number of digits,
+/// like the `%g` conversion in C. With a precision, it is the number
+/// of digits after the point, and the number is rounded to nearest
+/// with ties to even. Exponents are written in decimal after `e` or
+/// `E` for base 10, and after `@` for the other bases, where they are
+/// powers of the base. NaN is written as `NaN` and infinity as `inf`
+/// with its sign. The width, fill, alignment and the `+`, `#` and `0`
+/// flags are supported like for primitive floating-point numbers.
+///
+/// The number must be initialized. Formatting does not change the
+/// MPFR exception flags. Numbers with up to about 250 digits are
+/// formatted using the stack only; longer numbers use a temporary
+/// buffer allocated using the GMP memory functions.
+///
+/// # Examples
+///
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use gmp_mpfr_sys::mpfr::{self, DisplayMpfr};
+/// unsafe {
+///     let mut f = MaybeUninit::uninit();
+///     mpfr::init2(f.as_mut_ptr(), 53);
+///     let mut f = f.assume_init();
+///     mpfr::set_d(&mut f, 0.1, mpfr::rnd_t::RNDN);
+///     assert_eq!(format!("{}", DisplayMpfr(&f)), "0.10000000000000001");
+///     assert_eq!(format!("{:.3}", DisplayMpfr(&f)), "0.100");
+///     assert_eq!(format!("{:+.2e}", DisplayMpfr(&f)), "+1.00e-1");
+///     mpfr::set_d(&mut f, 2.5, mpfr::rnd_t::RNDN);
+///     assert_eq!(format!("{:.0}", DisplayMpfr(&f)), "2");
+///     assert_eq!(format!("{:#b}", DisplayMpfr(&f)), "0b10.1");
+///     mpfr::set_inf(&mut f, -1);
+///     assert_eq!(format!("{:>6}", DisplayMpfr(&f)), "  -inf");
+///     mpfr::clear(&mut f);
+/// }
+/// ```
+///
+/// [`mpfr_t`]: struct.mpfr_t.html
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayMpfr<'a>(pub &'a mpfr_t);
+
 // Types for function declarations in this file.
 
 type uprec_t = c_ulong;
@@ -270,6 +641,45 @@ extern "C" {
     /// See: [`mpfr_set_d`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fset_005fd)
     #[link_name = "mpfr_set_d"]
     pub fn set_d(rop: mpfr_ptr, op: f64, rnd: rnd_t) -> c_int;
+}
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpfr_set_ld"]
+    fn glue_set_ld(rop: mpfr_ptr, op: *const long_double, rnd: rnd_t) -> c_int;
+}
+/// See: [`mpfr_set_ld`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fset_005fld)
+#[inline]
+pub unsafe extern "C" fn set_ld(rop: mpfr_ptr, op: long_double, rnd: rnd_t) -> c_int {
+    glue_set_ld(rop, &op, rnd)
+}
+#[cfg(mpfr_float128)]
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpfr_set_float128"]
+    fn glue_set_float128(rop: mpfr_ptr, op: *const float128, rnd: rnd_t) -> c_int;
+}
+/// See: [`mpfr_set_float128`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fset_005ffloat128)
+///
+/// This function is only available if the `mpfr-float128` feature
+/// is enabled.
+#[cfg(mpfr_float128)]
+#[inline]
+pub unsafe extern "C" fn set_float128(rop: mpfr_ptr, op: float128, rnd: rnd_t) -> c_int {
+    glue_set_float128(rop, &op, rnd)
+}
+#[cfg(mpfr_decimal_float)]
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpfr_set_decimal64"]
+    fn glue_set_decimal64(rop: mpfr_ptr, op: *const decimal64, rnd: rnd_t) -> c_int;
+}
+/// See: [`mpfr_set_decimal64`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fset_005fdecimal64)
+///
+/// This function is only available if the `mpfr-decimal-float`
+/// feature is enabled.
+#[cfg(mpfr_decimal_float)]
+#[inline]
+pub unsafe extern "C" fn set_decimal64(rop: mpfr_ptr, op: decimal64, rnd: rnd_t) -> c_int {
+    glue_set_decimal64(rop, &op, rnd)
+}
+extern "C" {
     /// See: [`mpfr_set_z`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fset_005fz)
     #[link_name = "mpfr_set_z"]
     pub fn set_z(rop: mpfr_ptr, op: mpz_srcptr, rnd: rnd_t) -> c_int;
@@ -346,6 +756,12 @@ pub unsafe extern "C" fn init_set_d(rop: mpfr_ptr, op: f64, rnd: rnd_t) -> c_int
     init(rop);
     set_d(rop, op, rnd)
 }
+/// See: [`mpfr_init_set_ld`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005finit_005fset_005fld)
+#[inline]
+pub unsafe extern "C" fn init_set_ld(rop: mpfr_ptr, op: long_double, rnd: rnd_t) -> c_int {
+    init(rop);
+    set_ld(rop, op, rnd)
+}
 /// See: [`mpfr_init_set_z`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005finit_005fset_005fz)
 #[inline]
 pub unsafe extern "C" fn init_set_z(rop: mpfr_ptr, op: mpz_srcptr, rnd: rnd_t) -> c_int {
@@ -392,6 +808,60 @@ extern "C" {
     /// See: [`mpfr_get_d_2exp`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fget_005fd_005f2exp)
     #[link_name = "mpfr_get_d_2exp"]
     pub fn get_d_2exp(exp: *mut c_long, op: mpfr_srcptr, rnd: rnd_t) -> f64;
+}
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpfr_get_ld"]
+    fn glue_get_ld(rop: *mut long_double, op: mpfr_srcptr, rnd: rnd_t);
+    #[link_name = "gmp_mpfr_sys_mpfr_get_ld_2exp"]
+    fn glue_get_ld_2exp(rop: *mut long_double, exp: *mut c_long, op: mpfr_srcptr, rnd: rnd_t);
+}
+/// See: [`mpfr_get_ld`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fget_005fld)
+#[inline]
+pub unsafe extern "C" fn get_ld(op: mpfr_srcptr, rnd: rnd_t) -> long_double {
+    let mut ld = long_double::default();
+    glue_get_ld(&mut ld, op, rnd);
+    ld
+}
+/// See: [`mpfr_get_ld_2exp`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fget_005fld_005f2exp)
+#[inline]
+pub unsafe extern "C" fn get_ld_2exp(exp: *mut c_long, op: mpfr_srcptr, rnd: rnd_t) -> long_double {
+    let mut ld = long_double::default();
+    glue_get_ld_2exp(&mut ld, exp, op, rnd);
+    ld
+}
+#[cfg(mpfr_float128)]
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpfr_get_float128"]
+    fn glue_get_float128(rop: *mut float128, op: mpfr_srcptr, rnd: rnd_t);
+}
+/// See: [`mpfr_get_float128`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fget_005ffloat128)
+///
+/// This function is only available if the `mpfr-float128` feature
+/// is enabled.
+#[cfg(mpfr_float128)]
+#[inline]
+pub unsafe extern "C" fn get_float128(op: mpfr_srcptr, rnd: rnd_t) -> float128 {
+    let mut f = float128::default();
+    glue_get_float128(&mut f, op, rnd);
+    f
+}
+#[cfg(mpfr_decimal_float)]
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpfr_get_decimal64"]
+    fn glue_get_decimal64(rop: *mut decimal64, op: mpfr_srcptr, rnd: rnd_t);
+}
+/// See: [`mpfr_get_decimal64`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fget_005fdecimal64)
+///
+/// This function is only available if the `mpfr-decimal-float`
+/// feature is enabled.
+#[cfg(mpfr_decimal_float)]
+#[inline]
+pub unsafe extern "C" fn get_decimal64(op: mpfr_srcptr, rnd: rnd_t) -> decimal64 {
+    let mut d = decimal64::default();
+    glue_get_decimal64(&mut d, op, rnd);
+    d
+}
+extern "C" {
     /// See: [`mpfr_frexp`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffrexp)
     #[link_name = "mpfr_frexp"]
     pub fn frexp(exp: *mut exp_t, y: mpfr_ptr, x: mpfr_srcptr, rnd: rnd_t) -> c_int;
@@ -420,6 +890,84 @@ extern "C" {
     /// See: [`mpfr_free_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffree_005fstr)
     #[link_name = "mpfr_free_str"]
     pub fn free_str(str: *mut c_char);
+}
+// Returns an upper bound for the number of digits in base b needed
+// for a number with the given number of bits.
+fn digits_for_bits(bits: prec_t, b: c_int) -> usize {
+    // floor(16 log2(b)) is a lower bound for 16 log2(b)
+    let mut pow = 1u128;
+    for _ in 0..16 {
+        pow *= b as u128;
+    }
+    let log2_16 = u128::from(127 - pow.leading_zeros());
+    ((bits as u128 * 16 - 1) / log2_16 + 1) as usize
+}
+/// Returns a buffer length that is large enough for [`get_str_into`]
+/// with the same `b` and `n`, including space for the sign and the
+/// NUL terminator.
+///
+/// # Panics
+///
+/// Panics if `b` is not in the range 2 to 62.
+///
+/// [`get_str_into`]: fn.get_str_into.html
+pub unsafe fn get_str_len(b: c_int, n: usize, op: mpfr_srcptr) -> usize {
+    assert!((2..=62).contains(&b), "base out of range");
+    // when n is zero, get_str can use one digit more than the bound
+    // in its documentation
+    let digits = if n == 0 {
+        digits_for_bits(get_prec(op), b) + 2
+    } else {
+        n
+    };
+    cmp::max(digits + 2, 7)
+}
+/// Writes the significand of `op` into `buf` like [`get_str`], and
+/// returns the written string without the NUL terminator.
+///
+/// # Panics
+///
+/// Panics if `b` is not in the range 2 to 62, or if `buf` is shorter
+/// than [`get_str_len`]`(b, n, op)`.
+///
+/// [`get_str_len`]: fn.get_str_len.html
+/// [`get_str`]: fn.get_str.html
+pub unsafe fn get_str_into(
+    buf: &mut [u8],
+    expptr: *mut exp_t,
+    b: c_int,
+    n: usize,
+    op: mpfr_srcptr,
+    rnd: rnd_t,
+) -> &str {
+    assert!(buf.len() >= get_str_len(b, n, op), "buffer too small");
+    get_str(buf.as_mut_ptr() as *mut c_char, expptr, b, n, op, rnd);
+    gmp::str_before_nul(buf)
+}
+/// Returns the significand of `op` like [`get_str`], without using
+/// the MPFR memory functions.
+///
+/// This function is only available if the `std` feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `b` is not in the range 2 to 62.
+///
+/// [`get_str`]: fn.get_str.html
+#[cfg(feature = "std")]
+pub unsafe fn get_string(
+    expptr: *mut exp_t,
+    b: c_int,
+    n: usize,
+    op: mpfr_srcptr,
+    rnd: rnd_t,
+) -> String {
+    let mut v = std::vec![0; get_str_len(b, n, op)];
+    let len = get_str_into(&mut v, expptr, b, n, op, rnd).len();
+    v.truncate(len);
+    String::from_utf8_unchecked(v)
+}
+extern "C" {
     /// See: [`mpfr_fits_ulong_p`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffits_005fulong_005fp)
     #[link_name = "mpfr_fits_ulong_p"]
     pub fn fits_ulong_p(op: mpfr_srcptr, rnd: rnd_t) -> c_int;
@@ -444,7 +992,158 @@ extern "C" {
     /// See: [`mpfr_fits_intmax_p`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffits_005fintmax_005fp)
     #[link_name = "mpfr_fits_intmax_p"]
     pub fn fits_intmax_p(op: mpfr_srcptr, rnd: rnd_t) -> c_int;
+}
 
+const U128_LIMBS: usize = 128 / gmp::LIMB_BITS as usize;
+const I128_MAX: u128 = !0 >> 1;
+
+unsafe fn set_u128_abs(rop: mpfr_ptr, abs: u128, neg: bool, rnd: rnd_t) -> c_int {
+    let mut limbs = [0; U128_LIMBS];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        *limb = (abs >> (i * gmp::LIMB_BITS as usize)) as gmp::limb_t;
+    }
+    let size = U128_LIMBS as gmp::size_t;
+    let mut z = MaybeUninit::uninit();
+    let z = gmp::mpz_roinit_n(
+        z.as_mut_ptr(),
+        limbs.as_ptr(),
+        if neg { -size } else { size },
+    );
+    set_z(rop, z, rnd)
+}
+
+// Rounds op to an integer with 128 bits of precision, and returns
+// whether the result is negative and its absolute value, or None if
+// it does not fit in 128 bits or if op is NaN or infinite.
+unsafe fn rint_u128(op: mpfr_srcptr, rnd: rnd_t) -> (bool, Option<u128>) {
+    let mut limbs = [0; U128_LIMBS];
+    let mut rounded = mpfr_t {
+        prec: 128,
+        sign: 1,
+        exp: EXP_NAN,
+        d: limbs.as_mut_ptr(),
+    };
+    rint(&mut rounded, op, rnd);
+    let neg = rounded.sign < 0;
+    if rounded.exp == EXP_ZERO {
+        return (neg, Some(0));
+    }
+    if rounded.exp <= EXP_INF || rounded.exp > 128 {
+        return (neg, None);
+    }
+    let mut significand = 0u128;
+    for (i, &limb) in limbs.iter().enumerate() {
+        significand |= u128::from(limb) << (i * gmp::LIMB_BITS as usize);
+    }
+    (neg, Some(significand >> (128 - rounded.exp)))
+}
+
+/// Sets the value of `rop` from `op` rounded in the direction `rnd`,
+/// like [`set_ui`] for `u128`.
+///
+/// The return value is the ternary value.
+///
+/// [`set_ui`]: fn.set_ui.html
+#[inline]
+pub unsafe fn set_u128(rop: mpfr_ptr, op: u128, rnd: rnd_t) -> c_int {
+    set_u128_abs(rop, op, false, rnd)
+}
+/// Sets the value of `rop` from `op` rounded in the direction `rnd`,
+/// like [`set_si`] for `i128`.
+///
+/// The return value is the ternary value.
+///
+/// [`set_si`]: fn.set_si.html
+#[inline]
+pub unsafe fn set_i128(rop: mpfr_ptr, op: i128, rnd: rnd_t) -> c_int {
+    set_u128_abs(rop, op.wrapping_abs() as u128, op < 0, rnd)
+}
+/// Converts `op` to a `u128` after rounding it to an integer in the
+/// direction `rnd`, like [`get_ui`].
+///
+/// If `op` is NaN, zero is returned. If `op` is too large or negative
+/// after rounding, the closest representable value is returned. In
+/// both cases the erange flag is set.
+///
+/// [`get_ui`]: fn.get_ui.html
+pub unsafe fn get_u128(op: mpfr_srcptr, rnd: rnd_t) -> u128 {
+    if nan_p(op) != 0 {
+        set_erangeflag();
+        return 0;
+    }
+    match rint_u128(op, rnd) {
+        (_, Some(0)) => 0,
+        (false, Some(abs)) => abs,
+        (true, _) => {
+            set_erangeflag();
+            0
+        }
+        (false, None) => {
+            set_erangeflag();
+            !0
+        }
+    }
+}
+/// Converts `op` to an `i128` after rounding it to an integer in the
+/// direction `rnd`, like [`get_si`].
+///
+/// If `op` is NaN, zero is returned. If `op` is too large after
+/// rounding, the closest representable value is returned. In both
+/// cases the erange flag is set.
+///
+/// [`get_si`]: fn.get_si.html
+pub unsafe fn get_i128(op: mpfr_srcptr, rnd: rnd_t) -> i128 {
+    if nan_p(op) != 0 {
+        set_erangeflag();
+        return 0;
+    }
+    match rint_u128(op, rnd) {
+        (false, Some(abs)) if abs <= I128_MAX => abs as i128,
+        (true, Some(abs)) if abs <= I128_MAX + 1 => (abs as i128).wrapping_neg(),
+        (neg, _) => {
+            set_erangeflag();
+            if neg {
+                (I128_MAX + 1) as i128
+            } else {
+                I128_MAX as i128
+            }
+        }
+    }
+}
+/// Returns non-zero if `op` would fit in a `u128` when rounded to an
+/// integer in the direction `rnd`, like [`fits_ulong_p`].
+///
+/// [`fits_ulong_p`]: fn.fits_ulong_p.html
+pub unsafe fn fits_u128_p(op: mpfr_srcptr, rnd: rnd_t) -> c_int {
+    if nan_p(op) != 0 {
+        return 0;
+    }
+    let flags = flags_save();
+    let rounded = rint_u128(op, rnd);
+    flags_restore(flags, !0);
+    match rounded {
+        (_, Some(0)) | (false, Some(_)) => 1,
+        _ => 0,
+    }
+}
+/// Returns non-zero if `op` would fit in an `i128` when rounded to
+/// an integer in the direction `rnd`, like [`fits_slong_p`].
+///
+/// [`fits_slong_p`]: fn.fits_slong_p.html
+pub unsafe fn fits_i128_p(op: mpfr_srcptr, rnd: rnd_t) -> c_int {
+    if nan_p(op) != 0 {
+        return 0;
+    }
+    let flags = flags_save();
+    let rounded = rint_u128(op, rnd);
+    flags_restore(flags, !0);
+    match rounded {
+        (false, Some(abs)) if abs <= I128_MAX => 1,
+        (true, Some(abs)) if abs <= I128_MAX + 1 => 1,
+        _ => 0,
+    }
+}
+extern "C" {
     // Basic Arithmetic Functions
 
     /// See: [`mpfr_add`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fadd)
@@ -635,6 +1334,17 @@ extern "C" {
     /// See: [`mpfr_cmp_d`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fcmp_005fd)
     #[link_name = "mpfr_cmp_d"]
     pub fn cmp_d(op1: mpfr_srcptr, op2: f64) -> c_int;
+}
+extern "C" {
+    #[link_name = "gmp_mpfr_sys_mpfr_cmp_ld"]
+    fn glue_cmp_ld(op1: mpfr_srcptr, op2: *const long_double) -> c_int;
+}
+/// See: [`mpfr_cmp_ld`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fcmp_005fld)
+#[inline]
+pub unsafe extern "C" fn cmp_ld(op1: mpfr_srcptr, op2: long_double) -> c_int {
+    glue_cmp_ld(op1, &op2)
+}
+extern "C" {
     /// See: [`mpfr_cmp_z`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fcmp_005fz)
     #[link_name = "mpfr_cmp_z"]
     pub fn cmp_z(op1: mpfr_srcptr, op2: mpz_srcptr) -> c_int;
@@ -965,49 +1675,286 @@ extern "C" {
     /// See: [`mpfr_dump`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fdump)
     #[link_name = "mpfr_dump"]
     pub fn dump(op: mpfr_srcptr);
+}
 
-    // Formatted Output Functions
+// Portable interchange format (FPIF) without streams
 
-    /// See: [`mpfr_fprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffprintf)
-    #[link_name = "__gmpfr_fprintf"]
-    pub fn fprintf(stream: *mut FILE, template: *const c_char, ...) -> c_int;
-    /// See: [`mpfr_printf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fprintf)
-    #[link_name = "mpfr_printf"]
-    pub fn printf(template: *const c_char, ...) -> c_int;
-    /// See: [`mpfr_sprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fsprintf)
-    #[link_name = "mpfr_sprintf"]
-    pub fn sprintf(buf: *mut c_char, template: *const c_char, ...) -> c_int;
-    /// See: [`mpfr_snprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fsnprintf)
-    #[link_name = "mpfr_snprintf"]
-    pub fn snprintf(buf: *mut c_char, n: usize, template: *const c_char, ...) -> c_int;
-    /// See: [`mpfr_asprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fasprintf)
-    #[link_name = "mpfr_asprintf"]
-    pub fn asprintf(str: *mut *mut c_char, template: *const c_char, ...) -> c_int;
+const FPIF_MAX_PRECSIZE: u8 = 7;
+const FPIF_MAX_EMBEDDED_PRECISION: prec_t = 255 - FPIF_MAX_PRECSIZE as prec_t;
+const FPIF_MAX_EMBEDDED_EXPONENT: i64 = 47;
+const FPIF_EXTERNAL_EXPONENT: u8 = 94;
+const FPIF_KIND_ZERO: u8 = 119;
+const FPIF_KIND_INF: u8 = 120;
+const FPIF_KIND_NAN: u8 = 121;
+const LIMB_BYTES: usize = mem::size_of::<gmp::limb_t>();
 
-    // Integer and Remainder Related Functions
+// Number of bytes required to store val, at least one.
+fn fpif_byte_len(val: u64) -> usize {
+    cmp::max(1, 8 - val.leading_zeros() as usize / 8)
+}
 
-    /// See: [`mpfr_rint`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005frint)
-    #[link_name = "mpfr_rint"]
-    pub fn rint(rop: mpfr_ptr, op: mpfr_srcptr, rnd: rnd_t) -> c_int;
+fn fpif_extra_precision(prec: prec_t) -> Option<u64> {
+    if prec > FPIF_MAX_EMBEDDED_PRECISION {
+        Some((prec - FPIF_MAX_EMBEDDED_PRECISION - 1) as u64)
+    } else {
+        None
+    }
 }
-/// See: [`mpfr_ceil`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fceil)
-#[inline]
-pub unsafe extern "C" fn ceil(rop: mpfr_ptr, op: mpfr_srcptr) -> c_int {
-    rint(rop, op, rnd_t::RNDU)
+
+// Returns the stored exponent without the embedded offset, if it does
+// not fit in the first byte.
+unsafe fn fpif_extra_exponent(op: mpfr_srcptr) -> Option<u64> {
+    if (*op).exp <= EXP_INF {
+        return None;
+    }
+    let exp = i64::from((*op).exp);
+    if !(-FPIF_MAX_EMBEDDED_EXPONENT..=FPIF_MAX_EMBEDDED_EXPONENT).contains(&exp) {
+        let abs = if exp < 0 { exp.wrapping_neg() } else { exp };
+        Some(abs as u64 - FPIF_MAX_EMBEDDED_EXPONENT as u64)
+    } else {
+        None
+    }
 }
-/// See: [`mpfr_floor`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffloor)
-#[inline]
-pub unsafe extern "C" fn floor(rop: mpfr_ptr, op: mpfr_srcptr) -> c_int {
-    rint(rop, op, rnd_t::RNDD)
+
+/// Returns the number of bytes [`fpif_export_slice`] and
+/// [`fpif_export`] write for `op`.
+///
+/// [`fpif_export_slice`]: fn.fpif_export_slice.html
+/// [`fpif_export`]: fn.fpif_export.html
+pub unsafe fn fpif_len(op: mpfr_srcptr) -> usize {
+    let prec_len = 1 + fpif_extra_precision((*op).prec).map_or(0, fpif_byte_len);
+    let exp_len = 1 + fpif_extra_exponent(op).map_or(0, |uexp| fpif_byte_len(uexp << 1));
+    let limbs_len = if (*op).exp > EXP_INF {
+        ((*op).prec as usize + 7) >> 3
+    } else {
+        0
+    };
+    prec_len + exp_len + limbs_len
 }
-/// See: [`mpfr_round`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fround)
-#[inline]
-pub unsafe extern "C" fn round(rop: mpfr_ptr, op: mpfr_srcptr) -> c_int {
-    #[allow(deprecated)]
-    rint(rop, op, rnd_t::RNDNA)
+
+/// Writes `op` into the beginning of `buf` in the format of
+/// [`fpif_export`], and returns the number of bytes written.
+///
+/// The output is byte-for-byte identical to what [`fpif_export`]
+/// writes to a stream: the precision, then the sign and the exponent
+/// or the kind of special value, and then the significand for regular
+/// numbers.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than [`fpif_len`]`(op)`.
+///
+/// [`fpif_export`]: fn.fpif_export.html
+/// [`fpif_len`]: fn.fpif_len.html
+pub unsafe fn fpif_export_slice(op: mpfr_srcptr, buf: &mut [u8]) -> usize {
+    let len = fpif_len(op);
+    assert!(buf.len() >= len, "buffer too small for FPIF");
+    let prec = (*op).prec;
+    let mut pos = 1;
+    if let Some(extra) = fpif_extra_precision(prec) {
+        let n = fpif_byte_len(extra);
+        buf[0] = (n - 1) as u8;
+        buf[pos..pos + n].copy_from_slice(&extra.to_le_bytes()[..n]);
+        pos += n;
+    } else {
+        buf[0] = prec as u8 + FPIF_MAX_PRECSIZE;
+    }
+
+    let sign_bit = if (*op).sign < 0 { 0x80 } else { 0 };
+    let exp = (*op).exp;
+    if exp == EXP_ZERO {
+        buf[pos] = FPIF_KIND_ZERO | sign_bit;
+        return pos + 1;
+    } else if exp == EXP_INF {
+        buf[pos] = FPIF_KIND_INF | sign_bit;
+        return pos + 1;
+    } else if exp == EXP_NAN {
+        buf[pos] = FPIF_KIND_NAN | sign_bit;
+        return pos + 1;
+    }
+    if let Some(mut uexp) = fpif_extra_exponent(op) {
+        let n = fpif_byte_len(uexp << 1);
+        if exp < 0 {
+            uexp |= 1u64 << (8 * n - 1);
+        }
+        buf[pos] = (FPIF_EXTERNAL_EXPONENT + n as u8) | sign_bit;
+        buf[pos + 1..pos + 1 + n].copy_from_slice(&uexp.to_le_bytes()[..n]);
+        pos += 1 + n;
+    } else {
+        buf[pos] = (i64::from(exp) + FPIF_MAX_EMBEDDED_EXPONENT) as u8 | sign_bit;
+        pos += 1;
+    }
+
+    // The least significant limb only has its most significant bytes
+    // stored, in big-endian order; the other limbs are stored whole in
+    // little-endian order.
+    let nb_byte = (prec as usize + 7) >> 3;
+    let partial = nb_byte % LIMB_BYTES;
+    let nb_limb = nb_byte / LIMB_BYTES + usize::from(partial != 0);
+    let mut limbs = slice::from_raw_parts((*op).d, nb_limb).iter();
+    if partial != 0 {
+        let first = limbs.next().unwrap();
+        buf[pos..pos + partial].copy_from_slice(&first.to_be_bytes()[..partial]);
+        pos += partial;
+    }
+    for limb in limbs {
+        buf[pos..pos + LIMB_BYTES].copy_from_slice(&limb.to_le_bytes());
+        pos += LIMB_BYTES;
+    }
+    debug_assert_eq!(pos, len);
+    len
 }
-extern "C" {
-    /// See: [`mpfr_roundeven`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005froundeven)
+
+/// Reads a number in the format of [`fpif_export`] from the beginning
+/// of `buf` into `op`, and returns the number of bytes read.
+///
+/// This accepts the same input as [`fpif_import`], and like
+/// [`fpif_import`] it sets the precision of `op` to the stored
+/// precision. If the input is invalid or `buf` is too short, `None`
+/// is returned; if this happens after the precision is read, `op` is
+/// set to NaN.
+///
+/// [`fpif_export`]: fn.fpif_export.html
+/// [`fpif_import`]: fn.fpif_import.html
+pub unsafe fn fpif_import_slice(op: mpfr_ptr, buf: &[u8]) -> Option<usize> {
+    let first = *buf.first()?;
+    let mut pos = 1;
+    let prec = if first > FPIF_MAX_PRECSIZE {
+        prec_t::from(first - FPIF_MAX_PRECSIZE)
+    } else {
+        let n = usize::from(first) + 1;
+        let mut extra = 0u64;
+        for (i, &b) in buf.get(pos..pos + n)?.iter().enumerate() {
+            extra |= u64::from(b) << (8 * i);
+        }
+        pos += n;
+        let prec = extra.checked_add(FPIF_MAX_EMBEDDED_PRECISION as u64 + 1)?;
+        if prec > PREC_MAX as u64 {
+            return None;
+        }
+        prec as prec_t
+    };
+    set_prec(op, prec);
+    match fpif_import_value(op, &buf[pos..]) {
+        Some(value_len) => Some(pos + value_len),
+        None => {
+            set_nan(op);
+            None
+        }
+    }
+}
+
+unsafe fn fpif_import_value(op: mpfr_ptr, buf: &[u8]) -> Option<usize> {
+    let first = *buf.first()?;
+    let mut pos = 1;
+    (*op).sign = if first & 0x80 != 0 { -1 } else { 1 };
+    let kind = first & 0x7f;
+    let exp = match kind {
+        FPIF_KIND_ZERO => EXP_ZERO,
+        FPIF_KIND_INF => EXP_INF,
+        FPIF_KIND_NAN => EXP_NAN,
+        _ if kind <= FPIF_EXTERNAL_EXPONENT => {
+            fpif_exp_in_range(i64::from(kind) - FPIF_MAX_EMBEDDED_EXPONENT)?
+        }
+        _ if kind < FPIF_KIND_ZERO => {
+            let n = usize::from(kind - FPIF_EXTERNAL_EXPONENT);
+            if n > mem::size_of::<exp_t>() {
+                return None;
+            }
+            let mut uexp = 0u64;
+            for (i, &b) in buf.get(pos..pos + n)?.iter().enumerate() {
+                uexp |= u64::from(b) << (8 * i);
+            }
+            pos += n;
+            let exp_sign_bit = uexp & (1u64 << (8 * n - 1));
+            uexp = (uexp & !exp_sign_bit).checked_add(FPIF_MAX_EMBEDDED_EXPONENT as u64)?;
+            if uexp >> 63 != 0 {
+                return None;
+            }
+            let exp = if exp_sign_bit != 0 {
+                -(uexp as i64)
+            } else {
+                uexp as i64
+            };
+            fpif_exp_in_range(exp)?
+        }
+        _ => return None,
+    };
+    (*op).exp = exp;
+    if exp <= EXP_INF {
+        return Some(pos);
+    }
+
+    let nb_byte = ((*op).prec as usize + 7) >> 3;
+    let partial = nb_byte % LIMB_BYTES;
+    let nb_limb = nb_byte / LIMB_BYTES + usize::from(partial != 0);
+    let mut bytes = buf.get(pos..pos + nb_byte)?;
+    let mut limbs = slice::from_raw_parts_mut((*op).d, nb_limb).iter_mut();
+    if partial != 0 {
+        let mut be = gmp::limb_t::to_be_bytes(0);
+        be[..partial].copy_from_slice(&bytes[..partial]);
+        *limbs.next().unwrap() = gmp::limb_t::from_be_bytes(be);
+        bytes = &bytes[partial..];
+    }
+    for (limb, le) in limbs.zip(bytes.chunks(LIMB_BYTES)) {
+        let mut array = gmp::limb_t::to_le_bytes(0);
+        array.copy_from_slice(le);
+        *limb = gmp::limb_t::from_le_bytes(array);
+    }
+    Some(pos + nb_byte)
+}
+
+unsafe fn fpif_exp_in_range(exp: i64) -> Option<exp_t> {
+    if exp < i64::from(get_emin()) || exp > i64::from(get_emax()) {
+        None
+    } else {
+        Some(exp as exp_t)
+    }
+}
+
+extern "C" {
+
+    // Formatted Output Functions
+
+    /// See: [`mpfr_fprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffprintf)
+    #[link_name = "__gmpfr_fprintf"]
+    pub fn fprintf(stream: *mut FILE, template: *const c_char, ...) -> c_int;
+    /// See: [`mpfr_printf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fprintf)
+    #[link_name = "mpfr_printf"]
+    pub fn printf(template: *const c_char, ...) -> c_int;
+    /// See: [`mpfr_sprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fsprintf)
+    #[link_name = "mpfr_sprintf"]
+    pub fn sprintf(buf: *mut c_char, template: *const c_char, ...) -> c_int;
+    /// See: [`mpfr_snprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fsnprintf)
+    #[link_name = "mpfr_snprintf"]
+    pub fn snprintf(buf: *mut c_char, n: usize, template: *const c_char, ...) -> c_int;
+    /// See: [`mpfr_asprintf`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fasprintf)
+    #[link_name = "mpfr_asprintf"]
+    pub fn asprintf(str: *mut *mut c_char, template: *const c_char, ...) -> c_int;
+
+    // Integer and Remainder Related Functions
+
+    /// See: [`mpfr_rint`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005frint)
+    #[link_name = "mpfr_rint"]
+    pub fn rint(rop: mpfr_ptr, op: mpfr_srcptr, rnd: rnd_t) -> c_int;
+}
+/// See: [`mpfr_ceil`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fceil)
+#[inline]
+pub unsafe extern "C" fn ceil(rop: mpfr_ptr, op: mpfr_srcptr) -> c_int {
+    rint(rop, op, rnd_t::RNDU)
+}
+/// See: [`mpfr_floor`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005ffloor)
+#[inline]
+pub unsafe extern "C" fn floor(rop: mpfr_ptr, op: mpfr_srcptr) -> c_int {
+    rint(rop, op, rnd_t::RNDD)
+}
+/// See: [`mpfr_round`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005fround)
+#[inline]
+pub unsafe extern "C" fn round(rop: mpfr_ptr, op: mpfr_srcptr) -> c_int {
+    rint(rop, op, rnd_t::RNDNA)
+}
+extern "C" {
+    /// See: [`mpfr_roundeven`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/MPFR-Interface.html#index-mpfr_005froundeven)
     #[link_name = "mpfr_roundeven"]
     pub fn roundeven(rop: mpfr_ptr, op: mpfr_srcptr) -> c_int;
 }
@@ -1412,64 +2359,2039 @@ pub unsafe extern "C" fn custom_move(x: mpfr_ptr, new_position: *mut c_void) {
     (*x).d = new_position as *mut gmp::limb_t
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::mpfr;
-    use core::mem::MaybeUninit;
-
-    #[test]
-    fn check_version() {
-        use crate::tests;
+// Read-only Views
 
-        let (major, minor, patchlevel) = (4, 0, 2);
-        let version = "4.0.2-p1";
+/// A read-only view of a floating-point number as an [`mpfr_t`],
+/// which borrows its significand from a slice of limbs and does not
+/// allocate.
+///
+/// The view is created using [`custom_init_set`]. It dereferences to
+/// an [`mpfr_t`], so that a reference to it can be passed as a source
+/// operand to any function in this module. It must never be passed as
+/// a destination operand.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::{gmp, mpfr::{self, MpfrRef}};
+/// // 0.11 in binary × 2^2 = 3
+/// let limbs = [0b11 << (gmp::LIMB_BITS - 2)];
+/// let view = MpfrRef::from_limbs(&limbs, 2, 2, false);
+/// unsafe {
+///     assert_eq!(mpfr::get_si(&*view, mpfr::rnd_t::RNDN), 3);
+///     assert_eq!(mpfr::get_prec(view.as_raw()), 2);
+/// }
+/// ```
+///
+/// [`custom_init_set`]: fn.custom_init_set.html
+/// [`mpfr_t`]: struct.mpfr_t.html
+#[derive(Clone, Copy, Debug)]
+pub struct MpfrRef<'a> {
+    raw: mpfr_t,
+    phantom: PhantomData<&'a [gmp::limb_t]>,
+}
 
-        assert_eq!(mpfr::VERSION_MAJOR, major);
-        assert!(mpfr::VERSION_MINOR >= minor);
-        assert!(mpfr::VERSION_MINOR > minor || mpfr::VERSION_PATCHLEVEL >= patchlevel);
-        if mpfr::VERSION_MINOR == minor && mpfr::VERSION_PATCHLEVEL == patchlevel {
-            assert_eq!(
-                unsafe { tests::str_from_cstr(mpfr::get_version()) },
-                version
+impl<'a> MpfrRef<'a> {
+    /// Creates a view of the regular number with the significand
+    /// stored in `limbs`, least significant limb first, with a
+    /// precision of `prec` bits and an exponent `exp`, and which is
+    /// negative if `negative` is true.
+    ///
+    /// Like for the significands of [MPFR] numbers, the value is
+    /// 0.<i>significand</i> × 2<sup><i>exp</i></sup>, and the
+    /// significand must be normalized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prec` is not in the range [`PREC_MIN`] to
+    /// [`PREC_MAX`], if the length of `limbs` is not the number of
+    /// limbs needed for `prec` bits, if the most significant bit of
+    /// the significand is not set, if any bit after the first `prec`
+    /// bits is set, or if `exp` is not in the current exponent range
+    /// from [`get_emin`] to [`get_emax`].
+    ///
+    /// [MPFR]: https://www.mpfr.org/
+    /// [`PREC_MAX`]: constant.PREC_MAX.html
+    /// [`PREC_MIN`]: constant.PREC_MIN.html
+    /// [`get_emax`]: fn.get_emax.html
+    /// [`get_emin`]: fn.get_emin.html
+    pub fn from_limbs(
+        limbs: &'a [gmp::limb_t],
+        prec: prec_t,
+        exp: exp_t,
+        negative: bool,
+    ) -> MpfrRef<'a> {
+        assert!(
+            (PREC_MIN..=PREC_MAX).contains(&prec),
+            "precision out of range"
+        );
+        let bits = gmp::LIMB_BITS as usize;
+        let len = (prec as usize - 1) / bits + 1;
+        assert_eq!(limbs.len(), len, "wrong number of limbs for precision");
+        assert!(
+            limbs[len - 1] >> (bits - 1) != 0,
+            "significand not normalized"
+        );
+        let unused = len * bits - prec as usize;
+        assert!(
+            unused == 0 || limbs[0] & ((1 << unused) - 1) == 0,
+            "significand has bits after precision"
+        );
+        unsafe {
+            assert!(
+                (get_emin()..=get_emax()).contains(&exp),
+                "exponent out of range"
             );
-            assert_eq!(
-                unsafe { tests::str_from_cstr(mpfr::VERSION_STRING) },
-                version
+            let mut raw = MaybeUninit::uninit();
+            let kind = if negative {
+                -REGULAR_KIND
+            } else {
+                REGULAR_KIND
+            };
+            custom_init_set(
+                raw.as_mut_ptr(),
+                kind,
+                exp,
+                prec,
+                limbs.as_ptr() as *mut c_void,
             );
+            MpfrRef {
+                raw: raw.assume_init(),
+                phantom: PhantomData,
+            }
         }
     }
 
-    #[test]
-    fn check_round_nearest_away() {
+    /// Returns a const pointer to the inner [`mpfr_t`].
+    ///
+    /// [`mpfr_t`]: struct.mpfr_t.html
+    #[inline]
+    pub fn as_raw(&self) -> *const mpfr_t {
+        &self.raw
+    }
+}
+
+impl Deref for MpfrRef<'_> {
+    type Target = mpfr_t;
+    #[inline]
+    fn deref(&self) -> &mpfr_t {
+        &self.raw
+    }
+}
+
+// The view does not modify the limbs.
+unsafe impl Send for MpfrRef<'_> {}
+unsafe impl Sync for MpfrRef<'_> {}
+
+// Stack-allocated Numbers
+
+/// Returns the number of limbs needed for the significand of a
+/// floating-point number with a precision of `prec` bits.
+///
+/// This is [`custom_get_size`] in limbs rather than bytes, and can be
+/// used in constant expressions, for example as the number of limbs
+/// of a [`FixedMpfr`].
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::{gmp, mpfr};
+/// assert_eq!(mpfr::fixed_limbs(1), 1);
+/// assert_eq!(mpfr::fixed_limbs(gmp::NUMB_BITS as mpfr::prec_t), 1);
+/// assert_eq!(mpfr::fixed_limbs(gmp::NUMB_BITS as mpfr::prec_t + 1), 2);
+/// ```
+///
+/// [`FixedMpfr`]: struct.FixedMpfr.html
+/// [`custom_get_size`]: fn.custom_get_size.html
+#[inline]
+pub const fn fixed_limbs(prec: prec_t) -> usize {
+    (prec as usize - 1) / gmp::NUMB_BITS as usize + 1
+}
+
+/// A floating-point number with a fixed precision that stores its
+/// significand inline in `LIMBS` limbs, so that it needs no heap
+/// allocation.
+///
+/// The number is created using [`custom_init_set`] and can be moved
+/// freely; the significand pointer of the inner [`mpfr_t`] is fixed
+/// on access if the number has moved. It can be passed to any
+/// function in this module using [`as_raw`] and [`as_raw_mut`], or by
+/// reference as a source operand as it dereferences to an
+/// [`mpfr_t`]. The pointers are only valid until the number is moved.
+///
+/// The precision is set on creation and cannot change. Since the
+/// limbs are not allocated by [MPFR], the number is never cleared and
+/// it must not be passed to [`set_prec`], [`set_prec_raw`],
+/// [`clear`] or [`swap`], or be initialized again.
+///
+/// This type is only available if the compiler supports const
+/// generics, that is with Rust 1.51 or later.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, FixedMpfr};
+/// type F128 = FixedMpfr<{ mpfr::fixed_limbs(128) }>;
+/// fn pi() -> F128 {
+///     let mut f = F128::new(128);
+///     unsafe {
+///         mpfr::const_pi(f.as_raw_mut(), mpfr::rnd_t::RNDN);
+///     }
+///     f
+/// }
+/// let f = pi();
+/// unsafe {
+///     assert_eq!(mpfr::get_prec(&*f), 128);
+///     assert_eq!(mpfr::get_d(&*f, mpfr::rnd_t::RNDN), core::f64::consts::PI);
+/// }
+/// assert_eq!(format!("{:.5}", f), "3.14159");
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`as_raw`]: #method.as_raw
+/// [`clear`]: fn.clear.html
+/// [`custom_init_set`]: fn.custom_init_set.html
+/// [`mpfr_t`]: struct.mpfr_t.html
+/// [`set_prec_raw`]: fn.set_prec_raw.html
+/// [`set_prec`]: fn.set_prec.html
+/// [`swap`]: fn.swap.html
+#[cfg(const_generics)]
+#[derive(Clone)]
+pub struct FixedMpfr<const LIMBS: usize> {
+    // d points to limbs, and is fixed on access if the number has
+    // moved
+    raw: Cell<mpfr_t>,
+    limbs: [gmp::limb_t; LIMBS],
+}
+
+#[cfg(const_generics)]
+impl<const LIMBS: usize> FixedMpfr<LIMBS> {
+    /// Creates a floating-point number with the value NaN and a
+    /// precision of `prec` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prec` is not in the range [`PREC_MIN`] to
+    /// [`PREC_MAX`], or if `LIMBS` is less than the number of limbs
+    /// needed for `prec` bits, which is given by [`fixed_limbs`].
+    ///
+    /// [`PREC_MAX`]: constant.PREC_MAX.html
+    /// [`PREC_MIN`]: constant.PREC_MIN.html
+    /// [`fixed_limbs`]: fn.fixed_limbs.html
+    pub fn new(prec: prec_t) -> FixedMpfr<LIMBS> {
+        assert!(
+            (PREC_MIN..=PREC_MAX).contains(&prec),
+            "precision out of range"
+        );
+        assert!(fixed_limbs(prec) <= LIMBS, "too few limbs for precision");
+        let mut limbs = [0; LIMBS];
         unsafe {
-            let mut f = MaybeUninit::uninit();
-            mpfr::init2(f.as_mut_ptr(), 4);
-            let mut f = f.assume_init();
+            let mut raw = MaybeUninit::uninit();
+            custom_init_set(
+                raw.as_mut_ptr(),
+                NAN_KIND,
+                0,
+                prec,
+                limbs.as_mut_ptr() as *mut c_void,
+            );
+            FixedMpfr {
+                raw: Cell::new(raw.assume_init()),
+                limbs,
+            }
+        }
+    }
 
-            // mpfr_round_nearest_away needs emin > emin_min
-            if mpfr::get_emin() == mpfr::get_emin_min() {
-                mpfr::set_emin(mpfr::get_emin_min() + 1);
+    /// Returns a const pointer to the inner [`mpfr_t`].
+    ///
+    /// [`mpfr_t`]: struct.mpfr_t.html
+    #[inline]
+    pub fn as_raw(&self) -> *const mpfr_t {
+        &**self
+    }
+
+    /// Returns a mutable pointer to the inner [`mpfr_t`].
+    ///
+    /// The significand is the fixed array of `LIMBS` limbs inside the
+    /// number, so the pointer must not be passed to [`set_prec`] or
+    /// [`clear`], which would reallocate or free it.
+    ///
+    /// [`clear`]: fn.clear.html
+    /// [`mpfr_t`]: struct.mpfr_t.html
+    /// [`set_prec`]: fn.set_prec.html
+    #[inline]
+    pub fn as_raw_mut(&mut self) -> *mut mpfr_t {
+        let d = self.limbs.as_mut_ptr();
+        let raw = self.raw.get_mut();
+        raw.d = d;
+        raw
+    }
+}
+
+#[cfg(const_generics)]
+impl<const LIMBS: usize> Deref for FixedMpfr<LIMBS> {
+    type Target = mpfr_t;
+    #[inline]
+    fn deref(&self) -> &mpfr_t {
+        let raw = self.raw.as_ptr();
+        unsafe {
+            // The significand is the limbs field, which moves with
+            // the number while d keeps the old address. Moving needs
+            // exclusive ownership, so when d is stale there is no
+            // earlier mpfr_t reference that the write could affect.
+            let d = self.limbs.as_ptr() as *mut gmp::limb_t;
+            if (*raw).d != d {
+                (*raw).d = d;
             }
+            &*raw
+        }
+    }
+}
 
-            // tie to even: 10101 becomes 10100
-            let dir_tie_even = mpfr::set_ui(&mut f, 21, mpfr::rnd_t::RNDN);
-            assert!(dir_tie_even < 0);
-            let tie_even = mpfr::get_ui(&f, mpfr::rnd_t::RNDN);
-            assert_eq!(tie_even, 20);
+#[cfg(const_generics)]
+impl<const LIMBS: usize> Debug for FixedMpfr<LIMBS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpfr(self), f)
+    }
+}
 
-            // tie away from zero, 10101 becomes 10110
-            let dir_tie_away = mpfr_round_nearest_away!(mpfr::set_ui, &mut f, 21);
-            assert!(dir_tie_away > 0);
-            let tie_away = mpfr::get_ui(&f, mpfr::rnd_t::RNDN);
-            assert_eq!(tie_away, 22);
+#[cfg(const_generics)]
+impl<const LIMBS: usize> Display for FixedMpfr<LIMBS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpfr(self), f)
+    }
+}
 
-            // tie away from zero, 101001 becomes 101000
-            let dir_tie_away2 = mpfr_round_nearest_away!(mpfr::set_ui, &mut f, 41);
-            assert!(dir_tie_away2 < 0);
-            let tie_away2 = mpfr::get_ui(&f, mpfr::rnd_t::RNDN);
-            assert_eq!(tie_away2, 40);
+// The number owns its limbs, and the pointer into them is fixed on
+// access after moving to another thread.
+#[cfg(const_generics)]
+unsafe impl<const LIMBS: usize> Send for FixedMpfr<LIMBS> {}
 
-            mpfr::clear(&mut f);
+// Scoped Guards
+
+/// A guard that sets the exponent range and restores the previous
+/// range when dropped, including on unwinding.
+///
+/// The exponent range is thread local if [MPFR] is thread safe, so
+/// the guard cannot be sent to another thread. Numbers created inside
+/// the scope may be outside the restored range, in which case they
+/// should be passed to [`check_range`] before further use.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, ExpRangeGuard};
+/// let (emin, emax) = unsafe { (mpfr::get_emin(), mpfr::get_emax()) };
+/// {
+///     let _guard = ExpRangeGuard::new(-10, 10);
+///     unsafe {
+///         assert_eq!(mpfr::get_emin(), -10);
+///         assert_eq!(mpfr::get_emax(), 10);
+///     }
+/// }
+/// unsafe {
+///     assert_eq!(mpfr::get_emin(), emin);
+///     assert_eq!(mpfr::get_emax(), emax);
+/// }
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`check_range`]: fn.check_range.html
+#[must_use = "the previous exponent range is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct ExpRangeGuard {
+    emin: exp_t,
+    emax: exp_t,
+    phantom: PhantomData<*const ()>,
+}
+
+impl ExpRangeGuard {
+    /// Saves the current exponent range and sets it to `emin` to
+    /// `emax` using [`set_emin`] and [`set_emax`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `emin` is not in the range [`get_emin_min`] to
+    /// [`get_emin_max`], or if `emax` is not in the range
+    /// [`get_emax_min`] to [`get_emax_max`].
+    ///
+    /// [`get_emax_max`]: fn.get_emax_max.html
+    /// [`get_emax_min`]: fn.get_emax_min.html
+    /// [`get_emin_max`]: fn.get_emin_max.html
+    /// [`get_emin_min`]: fn.get_emin_min.html
+    /// [`set_emax`]: fn.set_emax.html
+    /// [`set_emin`]: fn.set_emin.html
+    pub fn new(emin: exp_t, emax: exp_t) -> ExpRangeGuard {
+        unsafe {
+            let guard = ExpRangeGuard {
+                emin: get_emin(),
+                emax: get_emax(),
+                phantom: PhantomData,
+            };
+            assert!(set_emin(emin) == 0, "minimum exponent out of range");
+            assert!(set_emax(emax) == 0, "maximum exponent out of range");
+            guard
+        }
+    }
+}
+
+impl Drop for ExpRangeGuard {
+    fn drop(&mut self) {
+        unsafe {
+            set_emin(self.emin);
+            set_emax(self.emax);
+        }
+    }
+}
+
+/// A guard that sets the default precision and restores the previous
+/// default precision when dropped, including on unwinding.
+///
+/// The default precision is thread local if [MPFR] is thread safe, so
+/// the guard cannot be sent to another thread.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, DefaultPrecGuard};
+/// let prec = unsafe { mpfr::get_default_prec() };
+/// {
+///     let _guard = DefaultPrecGuard::new(100);
+///     assert_eq!(unsafe { mpfr::get_default_prec() }, 100);
+/// }
+/// assert_eq!(unsafe { mpfr::get_default_prec() }, prec);
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+#[must_use = "the previous default precision is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct DefaultPrecGuard {
+    prec: prec_t,
+    phantom: PhantomData<*const ()>,
+}
+
+impl DefaultPrecGuard {
+    /// Saves the current default precision and sets it to `prec` bits
+    /// using [`set_default_prec`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prec` is not in the range [`PREC_MIN`] to
+    /// [`PREC_MAX`].
+    ///
+    /// [`PREC_MAX`]: constant.PREC_MAX.html
+    /// [`PREC_MIN`]: constant.PREC_MIN.html
+    /// [`set_default_prec`]: fn.set_default_prec.html
+    pub fn new(prec: prec_t) -> DefaultPrecGuard {
+        assert!(
+            (PREC_MIN..=PREC_MAX).contains(&prec),
+            "precision out of range"
+        );
+        unsafe {
+            let guard = DefaultPrecGuard {
+                prec: get_default_prec(),
+                phantom: PhantomData,
+            };
+            set_default_prec(prec);
+            guard
+        }
+    }
+}
+
+impl Drop for DefaultPrecGuard {
+    fn drop(&mut self) {
+        unsafe {
+            set_default_prec(self.prec);
+        }
+    }
+}
+
+/// A guard that sets the default rounding mode and restores the
+/// previous default rounding mode when dropped, including on
+/// unwinding.
+///
+/// The default rounding mode is thread local if [MPFR] is thread
+/// safe, so the guard cannot be sent to another thread.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, rnd_t, DefaultRoundingGuard};
+/// {
+///     let _guard = DefaultRoundingGuard::new(rnd_t::RNDZ);
+///     assert_eq!(unsafe { mpfr::get_default_rounding_mode() }, rnd_t::RNDZ);
+/// }
+/// assert_eq!(unsafe { mpfr::get_default_rounding_mode() }, rnd_t::RNDN);
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+#[must_use = "the previous default rounding mode is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct DefaultRoundingGuard {
+    rnd: rnd_t,
+    phantom: PhantomData<*const ()>,
+}
+
+impl DefaultRoundingGuard {
+    /// Saves the current default rounding mode and sets it to `rnd`
+    /// using [`set_default_rounding_mode`].
+    ///
+    /// [`set_default_rounding_mode`]: fn.set_default_rounding_mode.html
+    pub fn new(rnd: rnd_t) -> DefaultRoundingGuard {
+        unsafe {
+            let guard = DefaultRoundingGuard {
+                rnd: get_default_rounding_mode(),
+                phantom: PhantomData,
+            };
+            set_default_rounding_mode(rnd);
+            guard
+        }
+    }
+}
+
+impl Drop for DefaultRoundingGuard {
+    fn drop(&mut self) {
+        unsafe {
+            set_default_rounding_mode(self.rnd);
+        }
+    }
+}
+
+/// A guard that saves the exception flags using [`flags_save`] and
+/// restores all of them using [`flags_restore`] when dropped,
+/// including on unwinding.
+///
+/// Flags raised inside the scope are discarded, and flags cleared
+/// inside the scope are raised again. The flags are thread local if
+/// [MPFR] is thread safe, so the guard cannot be sent to another
+/// thread.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, FlagsGuard};
+/// unsafe {
+///     mpfr::clear_flags();
+///     {
+///         let _guard = FlagsGuard::new();
+///         mpfr::set_inexflag();
+///         assert!(mpfr::inexflag_p() != 0);
+///     }
+///     assert!(mpfr::inexflag_p() == 0);
+/// }
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`flags_restore`]: fn.flags_restore.html
+/// [`flags_save`]: fn.flags_save.html
+#[must_use = "the saved flags are restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct FlagsGuard {
+    flags: flags_t,
+    phantom: PhantomData<*const ()>,
+}
+
+impl FlagsGuard {
+    /// Saves the current exception flags.
+    #[inline]
+    pub fn new() -> FlagsGuard {
+        FlagsGuard {
+            flags: unsafe { flags_save() },
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl Default for FlagsGuard {
+    #[inline]
+    fn default() -> FlagsGuard {
+        FlagsGuard::new()
+    }
+}
+
+impl Drop for FlagsGuard {
+    fn drop(&mut self) {
+        unsafe {
+            flags_restore(self.flags, !0);
+        }
+    }
+}
+
+/// A guard that sets the exponent range and default precision to
+/// emulate an IEEE 754 binary interchange format, and restores them
+/// when dropped, including on unwinding.
+///
+/// The exponent range allows subnormal numbers, which must be
+/// obtained by passing the result of each operation to
+/// [`subnormalize`], as in the [MPFR] documentation for [`subnormalize`].
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, IeeeContextGuard};
+/// let _guard = IeeeContextGuard::new(64);
+/// unsafe {
+///     assert_eq!(mpfr::get_default_prec(), 53);
+///     assert_eq!(mpfr::get_emin(), -1073);
+///     assert_eq!(mpfr::get_emax(), 1024);
+/// }
+/// ```
+///
+/// [MPFR]: https://www.mpfr.org/
+/// [`subnormalize`]: fn.subnormalize.html
+#[must_use = "the previous context is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct IeeeContextGuard {
+    // only used to restore the context when dropped
+    _range: ExpRangeGuard,
+    _prec: DefaultPrecGuard,
+}
+
+impl IeeeContextGuard {
+    /// Saves the current exponent range and default precision and sets
+    /// them for the IEEE 754 binary interchange format with a width of
+    /// `bits` bits.
+    ///
+    /// The supported widths are 16, 32, 64, and multiples of 32 from
+    /// 128 to 65,504. For example with 64 bits, the precision is 53
+    /// bits and the exponent range is −1073 to 1024, as [MPFR]
+    /// exponents are one more than IEEE 754 exponents and include the
+    /// subnormal numbers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width is not supported, or if the exponent range
+    /// does not fit in the current [MPFR] build.
+    ///
+    /// [MPFR]: https://www.mpfr.org/
+    pub fn new(bits: u32) -> IeeeContextGuard {
+        let (prec, emax) = ieee_format(bits).expect("unsupported IEEE 754 format");
+        let emin = 3 - emax - prec;
+        let (prec, emin, emax) = match (
+            prec_t::try_from(prec),
+            exp_t::try_from(emin),
+            exp_t::try_from(emax + 1),
+        ) {
+            (Ok(prec), Ok(emin), Ok(emax)) => (prec, emin, emax),
+            _ => panic!("exponent out of range"),
+        };
+        IeeeContextGuard {
+            _range: ExpRangeGuard::new(emin, emax),
+            _prec: DefaultPrecGuard::new(prec),
+        }
+    }
+}
+
+// Returns the precision and the maximum exponent of the IEEE 754
+// binary interchange format with a width of bits bits.
+fn ieee_format(bits: u32) -> Option<(i64, i64)> {
+    let prec = match bits {
+        16 => 11,
+        32 => 24,
+        64 => 53,
+        128..=65504 if bits & 31 == 0 => {
+            // prec = bits − round(4 × log2(bits)) + 13, where
+            // n = floor(log2(bits⁴)) is rounded up if bits⁸ ≥ 2^(2n + 1)
+            let pow4 = u128::from(bits).pow(4);
+            let n = 127 - pow4.leading_zeros();
+            let round = if (pow4 * pow4) >> (2 * n + 1) != 0 {
+                n + 1
+            } else {
+                n
+            };
+            bits - round + 13
+        }
+        _ => return None,
+    };
+    let exp_bits = bits - prec;
+    Some((i64::from(prec), (1i64 << (exp_bits - 1)) - 1))
+}
+
+// Formatting
+
+// Calls get_str and restores the exception flags, as formatting
+// should not have side effects.
+unsafe fn get_str_keep_flags(
+    buf: &mut [u8],
+    b: c_int,
+    n: usize,
+    op: &mpfr_t,
+    rnd: rnd_t,
+) -> (usize, usize, i64) {
+    let flags = flags_save();
+    let mut exp = 0;
+    let len = get_str_into(buf, &mut exp, b, n, op, rnd).len();
+    flags_restore(flags, !0);
+    let start = if buf[0] == b'-' { 1 } else { 0 };
+    (start, len, i64::from(exp))
+}
+
+impl FloatDigits for mpfr_t {
+    fn class(&self) -> Class {
+        unsafe {
+            if nan_p(self) != 0 {
+                Class::Nan
+            } else if inf_p(self) != 0 {
+                Class::Inf
+            } else if zero_p(self) != 0 {
+                Class::Zero
+            } else {
+                Class::Normal
+            }
         }
     }
+
+    fn negative(&self) -> bool {
+        unsafe { signbit(self) != 0 }
+    }
+
+    fn buf_len(&self, base: c_int, n: usize) -> usize {
+        unsafe { get_str_len(base, n, self) }
+    }
+
+    fn get(&self, buf: &mut [u8], base: c_int, n: usize) -> (usize, usize, i64) {
+        unsafe { get_str_keep_flags(buf, base, n, self, rnd_t::RNDN) }
+    }
+
+    fn exp(&self, base: c_int) -> i64 {
+        let mut buf = [0u8; 8];
+        unsafe { get_str_keep_flags(&mut buf, base, 2, self, rnd_t::RNDZ).2 }
+    }
+
+    fn above_half(&self, base: c_int) -> bool {
+        let len = self.buf_len(base, 0);
+        display::with_buf(len, |buf| unsafe {
+            let (start, end, _) = get_str_keep_flags(buf, base, 0, self, rnd_t::RNDZ);
+            match display::digits_above_half(&buf[start..end], base) {
+                Some(above) => above,
+                None => {
+                    // exactly one half unless rounding away from zero
+                    // gives different digits
+                    display::with_buf(len, |away| {
+                        let (a_start, a_end, _) =
+                            get_str_keep_flags(away, base, 0, self, rnd_t::RNDA);
+                        away[a_start..a_end] != buf[start..end]
+                    })
+                }
+            }
+        })
+    }
+}
+
+fn fmt_mpfr(
+    op: &mpfr_t,
+    f: &mut Formatter<'_>,
+    base: c_int,
+    exp_style: bool,
+    prefix: &str,
+) -> FmtResult {
+    display::fmt_float(op, f, base, exp_style, prefix)
+}
+
+impl_fmt_traits! { DisplayMpfr, fmt_mpfr }
+
+// Owned types
+
+/// An owned, initialized [`mpfr_t`] that is cleared when dropped.
+///
+/// The value can be passed to any function in this module using
+/// [`as_raw`] and [`as_raw_mut`]. Cloning preserves the precision:
+/// unlike [`init_set`], which uses the default precision, it uses
+/// [`init2`] with the precision of the source followed by [`set`].
+/// `clone_from` sets the precision to that of the source if they
+/// differ.
+///
+/// This type is only available if the `owned` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, Mpfr};
+/// let mut f = Mpfr::new(100);
+/// unsafe {
+///     mpfr::const_pi(f.as_raw_mut(), mpfr::rnd_t::RNDN);
+/// }
+/// let copy = f.clone();
+/// unsafe {
+///     assert_eq!(mpfr::get_prec(copy.as_raw()), 100);
+///     assert!(mpfr::equal_p(copy.as_raw(), f.as_raw()) != 0);
+/// }
+/// assert_eq!(format!("{:.5}", copy), "3.14159");
+/// ```
+///
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`as_raw`]: #method.as_raw
+/// [`init2`]: fn.init2.html
+/// [`init_set`]: fn.init_set.html
+/// [`mpfr_t`]: struct.mpfr_t.html
+/// [`set`]: fn.set.html
+#[cfg(feature = "owned")]
+pub struct Mpfr {
+    inner: mpfr_t,
+}
+
+#[cfg(feature = "owned")]
+impl_owned! { Mpfr, mpfr_t, clear }
+
+#[cfg(feature = "owned")]
+impl Mpfr {
+    /// Creates a floating-point number with the value NaN and a
+    /// precision of `prec` bits using [`init2`].
+    ///
+    /// [`init2`]: fn.init2.html
+    #[inline]
+    pub fn new(prec: prec_t) -> Mpfr {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            init2(inner.as_mut_ptr(), prec);
+            Mpfr {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Default for Mpfr {
+    /// Creates a floating-point number with the value NaN and the
+    /// default precision using [`init`].
+    ///
+    /// [`init`]: fn.init.html
+    #[inline]
+    fn default() -> Mpfr {
+        unsafe {
+            let mut inner = MaybeUninit::uninit();
+            init(inner.as_mut_ptr());
+            Mpfr {
+                inner: inner.assume_init(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Clone for Mpfr {
+    #[inline]
+    fn clone(&self) -> Mpfr {
+        let mut ret = Mpfr::new(unsafe { get_prec(&self.inner) });
+        ret.clone_from(self);
+        ret
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Mpfr) {
+        unsafe {
+            let prec = get_prec(&source.inner);
+            if get_prec(&self.inner) != prec {
+                set_prec(&mut self.inner, prec);
+            }
+            // exact, as the precision is the same; the flags are
+            // restored as setting a NaN raises the NaN flag
+            let flags = flags_save();
+            set(&mut self.inner, &source.inner, rnd_t::RNDN);
+            flags_restore(flags, !0);
+        }
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Debug for Mpfr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpfr(&self.inner), f)
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Display for Mpfr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&DisplayMpfr(&self.inner), f)
+    }
+}
+
+/// An owned array of floating-point numbers with the same precision,
+/// whose significands are stored contiguously in a single allocation.
+///
+/// Each element is an [`mpfr_t`] created using [`custom_init_set`]
+/// with its significand pointing into the shared limbs, so that it can
+/// be passed to any function in this module: by reference as a source
+/// operand using indexing or [`iter`], and as any operand using
+/// [`as_raw_mut`]. Resizing moves the significands and re-points the
+/// elements using [`custom_move`]. Cloning copies the limbs and the
+/// kind and exponent of each element, obtained using
+/// [`custom_get_kind`] and [`custom_get_exp`].
+///
+/// Since the limbs are not allocated by [MPFR], the elements are
+/// never cleared, and they must not be passed to [`set_prec`],
+/// [`set_prec_raw`], [`clear`] or [`swap`], or be initialized again.
+/// The memory is allocated using the [GMP] memory functions.
+///
+/// This type is only available if the `owned` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use gmp_mpfr_sys::mpfr::{self, MpfrArray};
+/// let mut a = MpfrArray::new(100, 3);
+/// for i in 0..a.len() {
+///     unsafe {
+///         mpfr::set_ui(a.as_raw_mut(i), i as _, mpfr::rnd_t::RNDN);
+///     }
+/// }
+/// a.resize(4);
+/// unsafe {
+///     mpfr::add(a.as_raw_mut(3), &a[1], &a[2], mpfr::rnd_t::RNDN);
+/// }
+/// let sum = a
+///     .iter()
+///     .map(|x| unsafe { mpfr::get_si(x, mpfr::rnd_t::RNDN) })
+///     .sum::<i64>();
+/// assert_eq!(sum, 6);
+/// assert_eq!(format!("{:?}", a), "[0, 1, 2, 3]");
+/// ```
+///
+/// [GMP]: https://gmplib.org/
+/// [MPFR]: https://www.mpfr.org/
+/// [`as_raw_mut`]: #method.as_raw_mut
+/// [`clear`]: fn.clear.html
+/// [`custom_get_exp`]: fn.custom_get_exp.html
+/// [`custom_get_kind`]: fn.custom_get_kind.html
+/// [`custom_init_set`]: fn.custom_init_set.html
+/// [`custom_move`]: fn.custom_move.html
+/// [`iter`]: #method.iter
+/// [`mpfr_t`]: struct.mpfr_t.html
+/// [`set_prec_raw`]: fn.set_prec_raw.html
+/// [`set_prec`]: fn.set_prec.html
+/// [`swap`]: fn.swap.html
+#[cfg(feature = "owned")]
+pub struct MpfrArray {
+    // the d pointer of element i points to limbs + i * nlimbs
+    raws: NonNull<mpfr_t>,
+    limbs: NonNull<gmp::limb_t>,
+    len: usize,
+    nlimbs: usize,
+    prec: prec_t,
+}
+
+#[cfg(feature = "owned")]
+impl MpfrArray {
+    /// Creates an array of `len` floating-point numbers with the value
+    /// NaN and a precision of `prec` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prec` is not in the range [`PREC_MIN`] to
+    /// [`PREC_MAX`], or if the size of the array overflows `usize`.
+    ///
+    /// [`PREC_MAX`]: constant.PREC_MAX.html
+    /// [`PREC_MIN`]: constant.PREC_MIN.html
+    pub fn new(prec: prec_t, len: usize) -> MpfrArray {
+        assert!(
+            (PREC_MIN..=PREC_MAX).contains(&prec),
+            "precision out of range"
+        );
+        let mut array = MpfrArray {
+            raws: NonNull::dangling(),
+            limbs: NonNull::dangling(),
+            len: 0,
+            nlimbs: fixed_limbs(prec),
+            prec,
+        };
+        array.resize(len);
+        array
+    }
+
+    /// Returns the precision of the elements.
+    #[inline]
+    pub fn prec(&self) -> prec_t {
+        self.prec
+    }
+
+    /// Returns the number of elements.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the array has no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the elements as a slice, for use as source operands.
+    #[inline]
+    pub fn as_slice(&self) -> &[mpfr_t] {
+        unsafe { slice::from_raw_parts(self.raws.as_ptr(), self.len) }
+    }
+
+    /// Returns an iterator over the elements.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, mpfr_t> {
+        self.as_slice().iter()
+    }
+
+    /// Returns a const pointer to the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn as_raw(&self, index: usize) -> *const mpfr_t {
+        &self.as_slice()[index]
+    }
+
+    /// Returns a mutable pointer to the element at `index`, for use as
+    /// any operand.
+    ///
+    /// The significands of all the elements are slices of one
+    /// allocation owned by the array, each sized for the precision of
+    /// the array, so the pointer must not be passed to [`set_prec`] or
+    /// [`clear`]. The pointer is invalidated by [`resize`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// [`clear`]: fn.clear.html
+    /// [`resize`]: #method.resize
+    /// [`set_prec`]: fn.set_prec.html
+    #[inline]
+    pub fn as_raw_mut(&mut self, index: usize) -> *mut mpfr_t {
+        assert!(index < self.len, "index out of bounds");
+        unsafe { self.raws.as_ptr().add(index) }
+    }
+
+    /// Resizes the array to `new_len` elements, moving the significands
+    /// if they are reallocated.
+    ///
+    /// New elements have the value NaN.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the array overflows `usize`.
+    pub fn resize(&mut self, new_len: usize) {
+        let old_len = self.len;
+        let total = |len: usize| len.checked_mul(self.nlimbs).expect("array too large");
+        let (old_total, new_total) = (total(old_len), total(new_len));
+        unsafe {
+            self.raws = realloc_array(self.raws, old_len, new_len);
+            self.limbs = realloc_array(self.limbs, old_total, new_total);
+            self.len = new_len;
+            for i in 0..new_len {
+                let raw = self.raws.as_ptr().add(i);
+                let d = self.limbs.as_ptr().add(i * self.nlimbs) as *mut c_void;
+                if i < old_len {
+                    custom_move(raw, d);
+                } else {
+                    custom_init_set(raw, NAN_KIND, 0, self.prec, d);
+                }
+            }
+        }
+    }
+}
+
+// Reallocates an array of old_len elements to new_len elements using
+// the GMP memory functions. An empty array is not allocated and uses a
+// dangling pointer.
+#[cfg(feature = "owned")]
+unsafe fn realloc_array<T>(ptr: NonNull<T>, old_len: usize, new_len: usize) -> NonNull<T> {
+    let size = mem::size_of::<T>();
+    let old_size = old_len * size;
+    let new_size = new_len.checked_mul(size).expect("array too large");
+    if old_size == new_size {
+        return ptr;
+    }
+    let (mut alloc_func, mut realloc_func, mut free_func) = (None, None, None);
+    gmp::get_memory_functions(&mut alloc_func, &mut realloc_func, &mut free_func);
+    if new_size == 0 {
+        let free_func = free_func.expect("no GMP free function");
+        free_func(ptr.as_ptr() as *mut c_void, old_size);
+        return NonNull::dangling();
+    }
+    let new_ptr = if old_size == 0 {
+        let alloc_func = alloc_func.expect("no GMP allocation function");
+        alloc_func(new_size)
+    } else {
+        let realloc_func = realloc_func.expect("no GMP reallocation function");
+        realloc_func(ptr.as_ptr() as *mut c_void, old_size, new_size)
+    };
+    NonNull::new(new_ptr as *mut T).expect("allocation failed")
+}
+
+#[cfg(feature = "owned")]
+impl Clone for MpfrArray {
+    fn clone(&self) -> MpfrArray {
+        let ret = MpfrArray::new(self.prec, self.len);
+        unsafe {
+            ptr::copy_nonoverlapping(
+                self.limbs.as_ptr(),
+                ret.limbs.as_ptr(),
+                self.len * self.nlimbs,
+            );
+            for (i, src) in self.iter().enumerate() {
+                custom_init_set(
+                    ret.raws.as_ptr().add(i),
+                    custom_get_kind(src),
+                    custom_get_exp(src),
+                    self.prec,
+                    ret.limbs.as_ptr().add(i * self.nlimbs) as *mut c_void,
+                );
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Drop for MpfrArray {
+    fn drop(&mut self) {
+        self.resize(0);
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Index<usize> for MpfrArray {
+    type Output = mpfr_t;
+    #[inline]
+    fn index(&self, index: usize) -> &mpfr_t {
+        &self.as_slice()[index]
+    }
+}
+
+#[cfg(feature = "owned")]
+impl<'a> IntoIterator for &'a MpfrArray {
+    type Item = &'a mpfr_t;
+    type IntoIter = slice::Iter<'a, mpfr_t>;
+    #[inline]
+    fn into_iter(self) -> slice::Iter<'a, mpfr_t> {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "owned")]
+impl Debug for MpfrArray {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        struct Element<'a>(&'a mpfr_t);
+        impl Debug for Element<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+                Display::fmt(&DisplayMpfr(self.0), f)
+            }
+        }
+        f.debug_list().entries(self.iter().map(Element)).finish()
+    }
+}
+
+// The array owns its memory, and shared references only read it.
+#[cfg(feature = "owned")]
+unsafe impl Send for MpfrArray {}
+#[cfg(feature = "owned")]
+unsafe impl Sync for MpfrArray {}
+
+#[cfg(test)]
+mod tests {
+    use crate::mpfr;
+    use core::mem::MaybeUninit;
+    #[cfg(all(mpfr_decimal_float, any(target_arch = "x86", target_arch = "x86_64")))]
+    use libc::c_char;
+    use libc::c_void;
+
+    #[test]
+    fn check_version() {
+        use crate::tests;
+
+        let (major, minor, patchlevel) = (4, 0, 2);
+        let version = "4.0.2-p1";
+
+        assert_eq!(mpfr::VERSION_MAJOR, major);
+        assert!(mpfr::VERSION_MINOR >= minor);
+        assert!(mpfr::VERSION_MINOR > minor || mpfr::VERSION_PATCHLEVEL >= patchlevel);
+        if mpfr::VERSION_MINOR == minor && mpfr::VERSION_PATCHLEVEL == patchlevel {
+            assert_eq!(
+                unsafe { tests::str_from_cstr(mpfr::get_version()) },
+                version
+            );
+            assert_eq!(
+                unsafe { tests::str_from_cstr(mpfr::VERSION_STRING) },
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn check_buildopts() {
+        unsafe {
+            assert_eq!(mpfr::buildopt_float128_p() != 0, cfg!(mpfr_float128));
+            assert_eq!(mpfr::buildopt_decimal_p() != 0, cfg!(mpfr_decimal_float));
+            assert_eq!(mpfr::buildopt_sharedcache_p() != 0, cfg!(mpfr_shared_cache));
+            assert_eq!(
+                mpfr::buildopt_gmpinternals_p() != 0,
+                cfg!(mpfr_gmp_internals)
+            );
+        }
+    }
+
+    #[test]
+    fn check_rnd_t() {
+        use core::convert::TryFrom;
+
+        for &(rnd, e) in &[
+            (mpfr::rnd_t::RNDN, mpfr::rnd_enum::RNDN),
+            (mpfr::rnd_t::RNDZ, mpfr::rnd_enum::RNDZ),
+            (mpfr::rnd_t::RNDU, mpfr::rnd_enum::RNDU),
+            (mpfr::rnd_t::RNDD, mpfr::rnd_enum::RNDD),
+            (mpfr::rnd_t::RNDA, mpfr::rnd_enum::RNDA),
+            (mpfr::rnd_t::RNDF, mpfr::rnd_enum::RNDF),
+        ] {
+            assert_eq!(mpfr::rnd_t::from(e), rnd);
+            assert_eq!(mpfr::rnd_enum::try_from(rnd), Ok(e));
+        }
+        let bad = mpfr::rnd_t(-1);
+        assert_eq!(mpfr::rnd_enum::try_from(bad), Err(bad));
+
+        unsafe {
+            let rnd = mpfr::get_default_rounding_mode();
+            assert_eq!(rnd, mpfr::rnd_t::RNDN);
+            mpfr::set_default_rounding_mode(mpfr::rnd_t::RNDA);
+            assert_eq!(mpfr::get_default_rounding_mode(), mpfr::rnd_t::RNDA);
+            mpfr::set_default_rounding_mode(rnd);
+        }
+    }
+
+    #[test]
+    fn check_round_nearest_away() {
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 4);
+            let mut f = f.assume_init();
+
+            // mpfr_round_nearest_away needs emin > emin_min
+            let emin = core::cmp::max(mpfr::get_emin(), mpfr::get_emin_min() + 1);
+            let _range = mpfr::ExpRangeGuard::new(emin, mpfr::get_emax());
+
+            // tie to even: 10101 becomes 10100
+            let dir_tie_even = mpfr::set_ui(&mut f, 21, mpfr::rnd_t::RNDN);
+            assert!(dir_tie_even < 0);
+            let tie_even = mpfr::get_ui(&f, mpfr::rnd_t::RNDN);
+            assert_eq!(tie_even, 20);
+
+            // tie away from zero, 10101 becomes 10110
+            let dir_tie_away = mpfr_round_nearest_away!(mpfr::set_ui, &mut f, 21);
+            assert!(dir_tie_away > 0);
+            let tie_away = mpfr::get_ui(&f, mpfr::rnd_t::RNDN);
+            assert_eq!(tie_away, 22);
+
+            // tie away from zero, 101001 becomes 101000
+            let dir_tie_away2 = mpfr_round_nearest_away!(mpfr::set_ui, &mut f, 41);
+            assert!(dir_tie_away2 < 0);
+            let tie_away2 = mpfr::get_ui(&f, mpfr::rnd_t::RNDN);
+            assert_eq!(tie_away2, 40);
+
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[test]
+    fn check_guards() {
+        use mpfr::{
+            rnd_t, DefaultPrecGuard, DefaultRoundingGuard, ExpRangeGuard, FlagsGuard,
+            IeeeContextGuard,
+        };
+
+        unsafe {
+            let (emin, emax) = (mpfr::get_emin(), mpfr::get_emax());
+            let prec = mpfr::get_default_prec();
+            let rnd = mpfr::get_default_rounding_mode();
+            mpfr::clear_flags();
+            mpfr::set_divby0();
+            {
+                let _flags = FlagsGuard::new();
+                let _range = ExpRangeGuard::new(-100, 100);
+                let _prec = DefaultPrecGuard::new(1000);
+                let _rnd = DefaultRoundingGuard::new(rnd_t::RNDU);
+                mpfr::clear_divby0();
+                mpfr::set_overflow();
+                assert_eq!((mpfr::get_emin(), mpfr::get_emax()), (-100, 100));
+                assert_eq!(mpfr::get_default_prec(), 1000);
+                assert_eq!(mpfr::get_default_rounding_mode(), rnd_t::RNDU);
+            }
+            assert_eq!((mpfr::get_emin(), mpfr::get_emax()), (emin, emax));
+            assert_eq!(mpfr::get_default_prec(), prec);
+            assert_eq!(mpfr::get_default_rounding_mode(), rnd);
+            assert!(mpfr::divby0_p() != 0);
+            assert!(mpfr::overflow_p() == 0);
+            mpfr::clear_flags();
+
+            for &(bits, prec, emin, emax) in &[
+                (16, 11, -23, 16),
+                (32, 24, -148, 128),
+                (64, 53, -1073, 1024),
+                (128, 113, -16493, 16384),
+                (256, 237, -262377, 262144),
+            ] {
+                let _ieee = IeeeContextGuard::new(bits);
+                assert_eq!(mpfr::get_default_prec(), prec);
+                assert_eq!((mpfr::get_emin(), mpfr::get_emax()), (emin, emax));
+            }
+            assert_eq!((mpfr::get_emin(), mpfr::get_emax()), (emin, emax));
+            assert_eq!(mpfr::get_default_prec(), prec);
+        }
+    }
+
+    #[test]
+    fn check_ieee_context_f32() {
+        // 2^-149 is the smallest positive subnormal f32
+        let _ieee = mpfr::IeeeContextGuard::new(32);
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init(f.as_mut_ptr());
+            let mut f = f.assume_init();
+            mpfr::set_ui_2exp(&mut f, 3, -151, mpfr::rnd_t::RNDN);
+            let dir = mpfr::check_range(&mut f, 0, mpfr::rnd_t::RNDN);
+            mpfr::subnormalize(&mut f, dir, mpfr::rnd_t::RNDN);
+            assert_eq!(mpfr::get_flt(&f, mpfr::rnd_t::RNDN), f32::from_bits(1));
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported IEEE 754 format")]
+    fn check_ieee_context_unsupported() {
+        let _ieee = mpfr::IeeeContextGuard::new(96);
+    }
+
+    #[test]
+    fn check_long_double() {
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 53);
+            let mut f = f.assume_init();
+            // one third rounded to 53 bits fits in any long double
+            mpfr::set_ui(&mut f, 1, mpfr::rnd_t::RNDN);
+            mpfr::div_ui(&mut f, &f, 3, mpfr::rnd_t::RNDN);
+
+            let ld = mpfr::get_ld(&f, mpfr::rnd_t::RNDN);
+            assert_eq!(ld.to_f64(), mpfr::get_d(&f, mpfr::rnd_t::RNDN));
+            assert_eq!(mpfr::cmp_ld(&f, ld), 0);
+
+            let mut g = MaybeUninit::uninit();
+            let dir = mpfr::init_set_ld(g.as_mut_ptr(), ld, mpfr::rnd_t::RNDN);
+            let mut g = g.assume_init();
+            assert_eq!(dir, 0);
+            assert_ne!(mpfr::equal_p(&f, &g), 0);
+
+            let mut exp = 0;
+            let mant = mpfr::get_ld_2exp(&mut exp, &f, mpfr::rnd_t::RNDN);
+            assert_eq!(exp, -1);
+            assert_eq!(mant.to_f64(), 2.0 * ld.to_f64());
+
+            mpfr::clear(&mut g);
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[test]
+    fn check_long_double_x87() {
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 64);
+            let mut f = f.assume_init();
+            // 1 + 2^-63 needs all 64 bits of the x87 significand
+            mpfr::set_ui_2exp(&mut f, 1, -63, mpfr::rnd_t::RNDN);
+            mpfr::add_ui(&mut f, &f, 1, mpfr::rnd_t::RNDN);
+
+            let ld = mpfr::get_ld(&f, mpfr::rnd_t::RNDN);
+            let bytes = ld.to_ne_bytes();
+            assert_eq!(bytes[..10], [1, 0, 0, 0, 0, 0, 0, 0x80, 0xff, 0x3f]);
+            assert_eq!(ld.to_f64(), 1.0);
+            assert_eq!(mpfr::cmp_ld(&f, ld), 0);
+
+            let mut g = MaybeUninit::uninit();
+            mpfr::init2(g.as_mut_ptr(), 64);
+            let mut g = g.assume_init();
+            let ld = mpfr::long_double::from_ne_bytes(bytes);
+            assert_eq!(mpfr::set_ld(&mut g, ld, mpfr::rnd_t::RNDN), 0);
+            assert_ne!(mpfr::equal_p(&f, &g), 0);
+
+            mpfr::clear(&mut g);
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[test]
+    fn check_float128_f64() {
+        let one = mpfr::float128::from_f64(1.0);
+        assert_eq!(one.to_bits(), 0x3fff << 112);
+        let neg = mpfr::float128::from_f64(-2.5);
+        assert_eq!(neg.to_bits(), 1 << 127 | 0x4000 << 112 | 1 << 110);
+        let tiny = mpfr::float128::from_f64(f64::from_bits(1));
+        assert_eq!(tiny.to_bits(), (16383 - 1074) << 112);
+
+        // zeros, normals, subnormals and infinities
+        for &bits in &[
+            0,
+            1 << 63,
+            0x3ff0_0000_0000_0000,
+            0x3fb9_9999_9999_999a,
+            0xfe37_e43c_8800_759c,
+            0x7fef_ffff_ffff_ffff,
+            0x0010_0000_0000_0000,
+            0x0005_5555_5555_5555,
+            1,
+            0x7ff0_0000_0000_0000,
+            0xfff0_0000_0000_0000,
+        ] {
+            let f = mpfr::float128::from_f64(f64::from_bits(bits));
+            assert_eq!(f.to_f64().to_bits(), bits);
+            assert_eq!(
+                mpfr::float128::from_bits(f.to_bits()).to_bits(),
+                f.to_bits()
+            );
+        }
+        let nan = f64::from_bits(0x7ff8_0000_0000_0001);
+        assert_eq!(
+            mpfr::float128::from_f64(nan).to_f64().to_bits(),
+            nan.to_bits()
+        );
+
+        // 1 + 2^-53 is a tie rounded down to even, 1 + 3 * 2^-53 a tie
+        // rounded up to even, and 1 + 2^-53 + 2^-112 is above the tie
+        let f = |bits: u128| mpfr::float128::from_bits(bits).to_f64().to_bits();
+        assert_eq!(f(0x3fff << 112 | 1 << 59), 0x3ff0_0000_0000_0000);
+        assert_eq!(f(0x3fff << 112 | 3 << 59), 0x3ff0_0000_0000_0002);
+        assert_eq!(f(0x3fff << 112 | 1 << 59 | 1), 0x3ff0_0000_0000_0001);
+        // f64::MAX rounded up overflows, and 1.5 * 2^1024 is infinite
+        assert_eq!(f(0x43fe_ffff_ffff_ffff_f800 << 48), 0x7ff0_0000_0000_0000);
+        assert_eq!(f(0x43ff_8000 << 96), 0x7ff0_0000_0000_0000);
+        // 2^-1075 is a tie rounded down to zero, 1.5 * 2^-1075 is not
+        assert_eq!(f((16383 - 1075) << 112), 0);
+        assert_eq!(f((16383 - 1075) << 112 | 1 << 111), 1);
+        // 1.5 * 2^-1024 is subnormal in f64
+        assert_eq!(f((16383 - 1024) << 112 | 1 << 111), 3 << 49);
+        // the smallest binary128 subnormal becomes negative zero
+        assert_eq!(f(1 << 127 | 1), 1 << 63);
+    }
+
+    #[cfg(mpfr_float128)]
+    #[test]
+    fn check_float128() {
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 113);
+            let mut f = f.assume_init();
+            mpfr::set_ui(&mut f, 1, mpfr::rnd_t::RNDN);
+            mpfr::div_ui(&mut f, &f, 3, mpfr::rnd_t::RNDN);
+
+            let q = mpfr::get_float128(&f, mpfr::rnd_t::RNDN);
+            assert_eq!(q.to_bits(), 0x3ffd_5555_5555_5555_5555_5555_5555_5555);
+            assert_eq!(q.to_f64(), mpfr::get_d(&f, mpfr::rnd_t::RNDN));
+
+            let mut g = MaybeUninit::uninit();
+            mpfr::init2(g.as_mut_ptr(), 113);
+            let mut g = g.assume_init();
+            assert_eq!(mpfr::set_float128(&mut g, q, mpfr::rnd_t::RNDN), 0);
+            assert_ne!(mpfr::equal_p(&f, &g), 0);
+
+            mpfr::clear(&mut g);
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[cfg(all(mpfr_decimal_float, any(target_arch = "x86", target_arch = "x86_64")))]
+    #[test]
+    fn check_decimal64_bid() {
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 64);
+            let mut f = f.assume_init();
+            let mut g = MaybeUninit::uninit();
+            mpfr::init2(g.as_mut_ptr(), 64);
+            let mut g = g.assume_init();
+
+            // BID encodings of the values, and the encodings with
+            // sixteen significant digits that get_decimal64 returns
+            let values: [(u64, &[u8], u64); 5] = [
+                (0x31c0_0000_0000_0001, b"1\0", 0x2fe3_8d7e_a4c6_8000),
+                (0x31a0_0000_0000_0001, b"0.1\0", 0x2fc3_8d7e_a4c6_8000),
+                (0xb180_0000_0000_3039, b"-123.45\0", 0xb024_62c5_6df9_a800),
+                (0x3200_0000_0000_0005, b"5e2\0", 0x3031_c379_37e0_8000),
+                (
+                    0x6c73_86f2_6fc0_ffff,
+                    b"9999999999999999\0",
+                    0x6c73_86f2_6fc0_ffff,
+                ),
+            ];
+            for &(bits, s, normalized) in &values {
+                let d = mpfr::decimal64::from_bits(bits);
+                mpfr::set_decimal64(&mut f, d, mpfr::rnd_t::RNDN);
+                let s = s.as_ptr() as *const c_char;
+                assert_eq!(mpfr::set_str(&mut g, s, 10, mpfr::rnd_t::RNDN), 0);
+                assert_eq!(mpfr::cmp(&f, &g), 0);
+                let d = mpfr::get_decimal64(&f, mpfr::rnd_t::RNDN);
+                assert_eq!(d.to_bits(), normalized);
+            }
+
+            // 0.1 is inexact in binary, infinities and NaNs are kept
+            let tenth = mpfr::decimal64::from_bits(0x31a0_0000_0000_0001);
+            assert_ne!(mpfr::set_decimal64(&mut f, tenth, mpfr::rnd_t::RNDN), 0);
+            let inf = mpfr::decimal64::from_bits(0xf800_0000_0000_0000);
+            mpfr::set_decimal64(&mut f, inf, mpfr::rnd_t::RNDN);
+            assert!(mpfr::inf_p(&f) != 0 && mpfr::signbit(&f) != 0);
+            let d = mpfr::get_decimal64(&f, mpfr::rnd_t::RNDN);
+            assert_eq!(d.to_bits(), 0xf800_0000_0000_0000);
+            let nan = mpfr::decimal64::from_bits(0x7c00_0000_0000_0000);
+            mpfr::set_decimal64(&mut f, nan, mpfr::rnd_t::RNDN);
+            assert_ne!(mpfr::nan_p(&f), 0);
+
+            mpfr::clear(&mut g);
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[test]
+    fn check_u128() {
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 53);
+            let mut f = f.assume_init();
+            let mut g = MaybeUninit::uninit();
+            mpfr::init2(g.as_mut_ptr(), 200);
+            let mut g = g.assume_init();
+
+            let umax = !0u128;
+            let imax = (umax >> 1) as i128;
+            let imin = -imax - 1;
+
+            // 2^128 - 1 is rounded correctly to 53 bits
+            assert!(mpfr::set_u128(&mut f, umax, mpfr::rnd_t::RNDN) > 0);
+            assert_eq!(mpfr::cmp_ui_2exp(&f, 1, 128), 0);
+            assert!(mpfr::set_u128(&mut f, umax, mpfr::rnd_t::RNDZ) < 0);
+            mpfr::set_ui_2exp(&mut g, 1, 128, mpfr::rnd_t::RNDN);
+            mpfr::sub(&mut g, &g, &f, mpfr::rnd_t::RNDN);
+            assert_eq!(mpfr::cmp_ui_2exp(&g, 1, 128 - 53), 0);
+            assert_eq!(mpfr::set_i128(&mut f, imin, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::cmp_si_2exp(&f, -1, 127), 0);
+            assert_eq!(mpfr::set_i128(&mut f, -5, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::get_si(&f, mpfr::rnd_t::RNDN), -5);
+
+            mpfr::set_prec(&mut f, 200);
+            assert_eq!(mpfr::set_u128(&mut f, umax, mpfr::rnd_t::RNDN), 0);
+            mpfr::clear_flags();
+            assert_eq!(mpfr::get_u128(&f, mpfr::rnd_t::RNDN), umax);
+            assert_ne!(mpfr::fits_u128_p(&f, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::fits_i128_p(&f, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::get_i128(&f, mpfr::rnd_t::RNDN), imax);
+            assert_ne!(mpfr::erangeflag_p(), 0);
+
+            // 2^127 - 0.5 is a tie between 2^127 - 1 and 2^127
+            mpfr::set_ui_2exp(&mut f, 1, 127, mpfr::rnd_t::RNDN);
+            mpfr::sub_d(&mut f, &f, 0.5, mpfr::rnd_t::RNDN);
+            for &(rnd, fits) in &[
+                (mpfr::rnd_t::RNDN, false),
+                (mpfr::rnd_t::RNDZ, true),
+                (mpfr::rnd_t::RNDD, true),
+                (mpfr::rnd_t::RNDU, false),
+            ] {
+                assert_eq!(mpfr::fits_i128_p(&f, rnd) != 0, fits);
+                assert_ne!(mpfr::fits_u128_p(&f, rnd), 0);
+                let expected = if fits { imax as u128 } else { imax as u128 + 1 };
+                assert_eq!(mpfr::get_u128(&f, rnd), expected);
+            }
+            mpfr::neg(&mut f, &f, mpfr::rnd_t::RNDN);
+            assert_ne!(mpfr::fits_i128_p(&f, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::get_i128(&f, mpfr::rnd_t::RNDN), imin);
+            assert_eq!(mpfr::get_i128(&f, mpfr::rnd_t::RNDU), imin + 1);
+
+            // fits functions do not change the flags, like fits_ulong_p
+            mpfr::clear_flags();
+            mpfr::set_d(&mut f, -0.25, mpfr::rnd_t::RNDN);
+            assert_ne!(mpfr::fits_u128_p(&f, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::fits_u128_p(&f, mpfr::rnd_t::RNDD), 0);
+            assert_eq!(mpfr::inexflag_p(), 0);
+            assert_eq!(mpfr::get_u128(&f, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::erangeflag_p(), 0);
+            assert_eq!(mpfr::get_u128(&f, mpfr::rnd_t::RNDD), 0);
+            assert_ne!(mpfr::erangeflag_p(), 0);
+
+            mpfr::clear_flags();
+            mpfr::set_nan(&mut f);
+            assert_eq!(mpfr::fits_i128_p(&f, mpfr::rnd_t::RNDN), 0);
+            assert_eq!(mpfr::get_i128(&f, mpfr::rnd_t::RNDN), 0);
+            assert_ne!(mpfr::erangeflag_p(), 0);
+            mpfr::set_inf(&mut f, -1);
+            assert_eq!(mpfr::get_i128(&f, mpfr::rnd_t::RNDN), imin);
+            assert_eq!(mpfr::get_u128(&f, mpfr::rnd_t::RNDN), 0);
+
+            mpfr::clear(&mut g);
+            mpfr::clear(&mut f);
+        }
+    }
+
+    unsafe fn same_fpif_value(a: *const mpfr::mpfr_t, b: *const mpfr::mpfr_t) -> bool {
+        mpfr::get_prec(a) == mpfr::get_prec(b)
+            && mpfr::signbit(a) == mpfr::signbit(b)
+            && (mpfr::nan_p(a) != 0 && mpfr::nan_p(b) != 0 || mpfr::equal_p(a, b) != 0)
+    }
+
+    #[test]
+    fn check_fpif() {
+        unsafe {
+            let (emin, emax) = (mpfr::get_emin(), mpfr::get_emax());
+            mpfr::set_emin(mpfr::get_emin_min());
+            mpfr::set_emax(mpfr::get_emax_max());
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 1);
+            let mut f = f.assume_init();
+            let mut g = MaybeUninit::uninit();
+            mpfr::init2(g.as_mut_ptr(), 1);
+            let mut g = g.assume_init();
+            let file = libc::tmpfile();
+            assert!(!file.is_null());
+            let mut from_c = [0u8; 9000];
+            let mut from_rust = [0u8; 9000];
+
+            let precs = [1, 7, 8, 9, 53, 64, 65, 100, 248, 249, 504, 505, 66_000];
+            let exps = [
+                mpfr::get_emin_min(),
+                -100_000,
+                -48,
+                -47,
+                -46,
+                0,
+                1,
+                47,
+                48,
+                100_000,
+                mpfr::get_emax_max(),
+            ];
+            for &prec in &precs {
+                mpfr::set_prec(&mut f, prec);
+                for case in 0..6 + 2 * exps.len() {
+                    match case {
+                        0 => mpfr::set_zero(&mut f, 1),
+                        1 => mpfr::set_zero(&mut f, -1),
+                        2 => mpfr::set_inf(&mut f, 1),
+                        3 => mpfr::set_inf(&mut f, -1),
+                        4 => mpfr::set_nan(&mut f),
+                        5 => {
+                            mpfr::setsign(&mut f, &f, 1, mpfr::rnd_t::RNDN);
+                        }
+                        _ => {
+                            mpfr::set_si(&mut f, 1, mpfr::rnd_t::RNDN);
+                            mpfr::div_ui(&mut f, &f, 3, mpfr::rnd_t::RNDN);
+                            assert_eq!(mpfr::set_exp(&mut f, exps[(case - 6) / 2]), 0);
+                            if case % 2 != 0 {
+                                mpfr::neg(&mut f, &f, mpfr::rnd_t::RNDN);
+                            }
+                        }
+                    }
+
+                    libc::rewind(file);
+                    assert_eq!(mpfr::fpif_export(file, &mut f), 0);
+                    let c_len = libc::ftell(file) as usize;
+                    libc::rewind(file);
+                    let read = libc::fread(from_c.as_mut_ptr() as *mut c_void, 1, c_len, file);
+                    assert_eq!(read, c_len);
+
+                    let len = mpfr::fpif_export_slice(&f, &mut from_rust);
+                    assert_eq!(len, mpfr::fpif_len(&f));
+                    assert_eq!(&from_rust[..len], &from_c[..c_len]);
+
+                    assert_eq!(mpfr::fpif_import_slice(&mut g, &from_c[..len - 1]), None);
+                    assert_eq!(mpfr::fpif_import_slice(&mut g, &from_c), Some(len));
+                    assert!(same_fpif_value(&f, &g));
+
+                    mpfr::set_prec(&mut g, 1);
+                    libc::rewind(file);
+                    let written = libc::fwrite(from_rust.as_ptr() as *const c_void, 1, len, file);
+                    assert_eq!(written, len);
+                    libc::rewind(file);
+                    assert_eq!(mpfr::fpif_import(&mut g, file), 0);
+                    assert_eq!(libc::ftell(file) as usize, len);
+                    assert!(same_fpif_value(&f, &g));
+                }
+            }
+
+            // 122 is not a valid exponent byte
+            assert_eq!(mpfr::fpif_import_slice(&mut g, &[8, 122]), None);
+            assert_ne!(mpfr::nan_p(&g), 0);
+
+            libc::fclose(file);
+            mpfr::clear(&mut g);
+            mpfr::clear(&mut f);
+            mpfr::set_emin(emin);
+            mpfr::set_emax(emax);
+        }
+    }
+
+    #[test]
+    fn check_get_str_into() {
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 53);
+            let mut f = f.assume_init();
+
+            let mut buf = [0u8; 2048];
+            for i in 0..7 {
+                match i {
+                    0 => {
+                        mpfr::set_zero(&mut f, -1);
+                    }
+                    1 => {
+                        mpfr::set_prec(&mut f, 53);
+                        mpfr::set_si(&mut f, 1, mpfr::rnd_t::RNDN);
+                        mpfr::div_ui(&mut f, &f, 3, mpfr::rnd_t::RNDN);
+                    }
+                    2 => mpfr::set_inf(&mut f, -1),
+                    3 => mpfr::set_nan(&mut f),
+                    4 => {
+                        mpfr::set_prec(&mut f, 200);
+                        mpfr::set_si_2exp(&mut f, -1, 100, mpfr::rnd_t::RNDN);
+                    }
+                    5 => {
+                        mpfr::set_prec(&mut f, 7);
+                        mpfr::set_ui_2exp(&mut f, 11, -200, mpfr::rnd_t::RNDN);
+                    }
+                    _ => {
+                        mpfr::set_prec(&mut f, 1000);
+                        mpfr::const_pi(&mut f, mpfr::rnd_t::RNDN);
+                    }
+                }
+                for &b in &[2, 3, 10, 16, 36, 62] {
+                    for &n in &[0, 2, 5] {
+                        let mut exp = 0;
+                        let mut c_exp = 0;
+                        let c_str = mpfr::get_str(
+                            core::ptr::null_mut(),
+                            &mut c_exp,
+                            b,
+                            n,
+                            &f,
+                            mpfr::rnd_t::RNDN,
+                        );
+                        let expected = crate::tests::str_from_cstr(c_str);
+                        let len = mpfr::get_str_len(b, n, &f);
+                        assert!(expected.len() < len);
+                        let s = mpfr::get_str_into(
+                            &mut buf[..len],
+                            &mut exp,
+                            b,
+                            n,
+                            &f,
+                            mpfr::rnd_t::RNDN,
+                        );
+                        assert_eq!(s, expected);
+                        assert_eq!(exp, c_exp);
+                        #[cfg(feature = "std")]
+                        {
+                            let s = mpfr::get_string(&mut exp, b, n, &f, mpfr::rnd_t::RNDN);
+                            assert_eq!(s, expected);
+                        }
+                        mpfr::free_str(c_str);
+                    }
+                }
+            }
+
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[test]
+    fn check_display() {
+        use crate::tests::{check_fmt, format};
+        use mpfr::DisplayMpfr;
+
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 53);
+            let mut f = f.assume_init();
+            check_fmt!("{}", DisplayMpfr(&f), "NaN");
+            check_fmt!("{:+e}", DisplayMpfr(&f), "NaN");
+            check_fmt!("{:06}", DisplayMpfr(&f), "   NaN");
+            mpfr::set_inf(&mut f, -1);
+            check_fmt!("{}", DisplayMpfr(&f), "-inf");
+            check_fmt!("{:#x}", DisplayMpfr(&f), "-inf");
+            check_fmt!("{:<6}|", DisplayMpfr(&f), "-inf  |");
+            mpfr::set_zero(&mut f, -1);
+            check_fmt!("{}", DisplayMpfr(&f), "-0");
+            check_fmt!("{:.2}", DisplayMpfr(&f), "-0.00");
+            check_fmt!("{:e}", DisplayMpfr(&f), "-0e0");
+            mpfr::set_d(&mut f, 0.1, mpfr::rnd_t::RNDN);
+            check_fmt!("{}", DisplayMpfr(&f), "0.10000000000000001");
+            check_fmt!("{:.2}", DisplayMpfr(&f), "0.10");
+            check_fmt!("{:.0}", DisplayMpfr(&f), "0");
+            check_fmt!("{:e}", DisplayMpfr(&f), "1.0000000000000001e-1");
+            check_fmt!("{:+.3E}", DisplayMpfr(&f), "+1.000E-1");
+            check_fmt!("{:X}", DisplayMpfr(&f), "0.1999999999999A");
+            check_fmt!("{:09.3}", DisplayMpfr(&f), "00000.100");
+            mpfr::set_d(&mut f, 123.456, mpfr::rnd_t::RNDN);
+            check_fmt!("{}", DisplayMpfr(&f), "123.456");
+            check_fmt!("{:.1}", DisplayMpfr(&f), "123.5");
+            check_fmt!("{:.3e}", DisplayMpfr(&f), "1.235e2");
+            // ties to even
+            mpfr::set_d(&mut f, 0.5, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.0}", DisplayMpfr(&f), "0");
+            mpfr::set_d(&mut f, 1.5, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.0}", DisplayMpfr(&f), "2");
+            mpfr::set_d(&mut f, 2.5, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.0}", DisplayMpfr(&f), "2");
+            check_fmt!("{:#b}", DisplayMpfr(&f), "0b10.1");
+            check_fmt!("{:.0b}", DisplayMpfr(&f), "10");
+            mpfr::set_d(&mut f, 0.125, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.2}", DisplayMpfr(&f), "0.12");
+            check_fmt!("{:.1}", DisplayMpfr(&f), "0.1");
+            // rounding to zero or one in the last place
+            mpfr::set_d(&mut f, 0.05, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.1}", DisplayMpfr(&f), "0.1");
+            mpfr::set_d(&mut f, 0.04, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.1}", DisplayMpfr(&f), "0.0");
+            mpfr::set_d(&mut f, 0.004, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.1}", DisplayMpfr(&f), "0.0");
+            mpfr::set_d(&mut f, -0.75, mpfr::rnd_t::RNDN);
+            check_fmt!("{:.0}", DisplayMpfr(&f), "-1");
+            mpfr::set_d(&mut f, 0.0009765625, mpfr::rnd_t::RNDN);
+            check_fmt!("{}", DisplayMpfr(&f), "0.0009765625");
+            check_fmt!("{:.3}", DisplayMpfr(&f), "0.001");
+            check_fmt!("{:x}", DisplayMpfr(&f), "0.004");
+            mpfr::set_d(&mut f, 9.5367431640625e-7, mpfr::rnd_t::RNDN);
+            check_fmt!("{}", DisplayMpfr(&f), "9.5367431640625e-7");
+            check_fmt!("{:x}", DisplayMpfr(&f), "1@-5");
+            mpfr::set_d(&mut f, 1024.0, mpfr::rnd_t::RNDN);
+            check_fmt!("{}", DisplayMpfr(&f), "1024");
+            check_fmt!("{:b}", DisplayMpfr(&f), "10000000000");
+            check_fmt!("{:.1}", DisplayMpfr(&f), "1024.0");
+            mpfr::set_d(&mut f, 1e300, mpfr::rnd_t::RNDN);
+            check_fmt!("{}", DisplayMpfr(&f), "1.0000000000000001e300");
+            let s = format(format_args!("{:.1}", DisplayMpfr(&f)));
+            assert_eq!(s.as_str().len(), 303);
+            assert!(s.as_str().starts_with("10000000000000000525047602552044"));
+            assert!(s.as_str().ends_with("400540160.0"));
+
+            // formatting does not change the flags
+            mpfr::set_d(&mut f, 0.1, mpfr::rnd_t::RNDN);
+            mpfr::clear_flags();
+            check_fmt!("{:.3}", DisplayMpfr(&f), "0.100");
+            check_fmt!("{}", DisplayMpfr(&f), "0.10000000000000001");
+            assert_eq!(mpfr::flags_save(), 0);
+
+            // longer than the stack buffer
+            mpfr::set_prec(&mut f, 1000);
+            mpfr::set_ui(&mut f, 1, mpfr::rnd_t::RNDN);
+            mpfr::div_ui(&mut f, &f, 3, mpfr::rnd_t::RNDN);
+            let s = format(format_args!("{:b}", DisplayMpfr(&f)));
+            assert_eq!(s.as_str().len(), 1003);
+            assert!(s.as_str().starts_with("0.0101"));
+            check_fmt!("{:.5}", DisplayMpfr(&f), "0.33333");
+            check_fmt!("{:.2e}", DisplayMpfr(&f), "3.33e-1");
+
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[cfg(feature = "owned")]
+    #[test]
+    fn check_owned() {
+        use crate::tests::format;
+        use mpfr::Mpfr;
+
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<Mpfr>();
+
+        unsafe {
+            let mut a = Mpfr::new(200);
+            assert!(mpfr::nan_p(a.as_raw()) != 0);
+            mpfr::set_ui(a.as_raw_mut(), 1, mpfr::rnd_t::RNDN);
+            mpfr::div_ui(a.as_raw_mut(), a.as_raw(), 3, mpfr::rnd_t::RNDN);
+            let b = a.clone();
+            assert_eq!(mpfr::get_prec(b.as_raw()), 200);
+            assert!(mpfr::equal_p(a.as_raw(), b.as_raw()) != 0);
+            let mut c = Mpfr::new(10);
+            c.clone_from(&a);
+            assert_eq!(mpfr::get_prec(c.as_raw()), 200);
+            assert!(mpfr::equal_p(a.as_raw(), c.as_raw()) != 0);
+            mpfr::neg(c.as_raw_mut(), c.as_raw(), mpfr::rnd_t::RNDN);
+            assert!(mpfr::sgn(a.as_raw()) > 0);
+            assert_eq!(
+                format(format_args!("{:.3} {:?}", c, Mpfr::new(8))).as_str(),
+                "-0.333 NaN"
+            );
+            let d = Mpfr::default();
+            assert_eq!(mpfr::get_prec(d.as_raw()), mpfr::get_default_prec());
+            let raw = c.into_raw();
+            let c = Mpfr::from_raw(raw);
+            assert_eq!(mpfr::get_prec(c.as_raw()), 200);
+
+            // cloning a NaN does not raise the NaN flag
+            mpfr::clear_flags();
+            let nan = Mpfr::new(8);
+            let mut e = nan.clone();
+            e.clone_from(&Mpfr::default());
+            assert!(mpfr::nan_p(e.as_raw()) != 0);
+            assert_eq!(mpfr::nanflag_p(), 0);
+        }
+    }
+
+    #[cfg(feature = "owned")]
+    #[test]
+    fn check_mpfr_array() {
+        use crate::{gmp, tests::format};
+        use mpfr::MpfrArray;
+
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<MpfrArray>();
+
+        unsafe {
+            // more than one limb per element
+            let prec = mpfr::prec_t::from(gmp::NUMB_BITS) + 1;
+            let mut a = MpfrArray::new(prec, 2);
+            assert_eq!(a.prec(), prec);
+            assert!(a.iter().all(|x| mpfr::nan_p(x) != 0));
+            mpfr::set_ui(a.as_raw_mut(0), 1, mpfr::rnd_t::RNDN);
+            mpfr::div_2ui(a.as_raw_mut(1), &a[0], 1, mpfr::rnd_t::RNDN);
+            let half = a.clone();
+
+            // growing moves the significands and keeps the values
+            a.resize(1000);
+            assert_eq!(a.len(), 1000);
+            assert!(mpfr::equal_p(&a[1], &half[1]) != 0);
+            assert!(mpfr::nan_p(&a[999]) != 0);
+            for i in 2..a.len() {
+                let (prev, next) = (a.as_raw(i - 1), a.as_raw_mut(i));
+                mpfr::add(next, prev, &half[0], mpfr::rnd_t::RNDN);
+            }
+            let first = mpfr::custom_get_significand(&a[0]) as *const gmp::limb_t;
+            for (i, x) in a.iter().enumerate() {
+                assert_eq!(mpfr::get_prec(x), prec);
+                let limbs = mpfr::custom_get_significand(x) as *const gmp::limb_t;
+                assert_eq!(limbs, first.add(2 * i));
+            }
+            assert_eq!(mpfr::cmp_d(&a[4], 3.5), 0);
+
+            // shrinking keeps the leading values
+            a.resize(5);
+            let b = a.clone();
+            assert_eq!(mpfr::cmp_d(&b[4], 3.5), 0);
+            assert_eq!(
+                format(format_args!("{:?}", b)).as_str(),
+                "[1, 0.5, 1.5, 2.5, 3.5]"
+            );
+            assert_eq!(format(format_args!("{:.1?}", half)).as_str(), "[1.0, 0.5]");
+
+            let mut empty = MpfrArray::new(53, 0);
+            assert!(empty.is_empty());
+            assert_eq!(format(format_args!("{:?}", empty)).as_str(), "[]");
+            empty.resize(1);
+            assert!(mpfr::nan_p(&empty[0]) != 0);
+        }
+    }
+
+    #[cfg(feature = "owned")]
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn check_mpfr_array_index() {
+        let mut a = mpfr::MpfrArray::new(53, 1);
+        a.as_raw_mut(1);
+    }
+
+    #[test]
+    fn check_mpfr_ref() {
+        use crate::gmp;
+        use mpfr::MpfrRef;
+
+        unsafe {
+            let mut f = MaybeUninit::uninit();
+            mpfr::init2(f.as_mut_ptr(), 100);
+            let mut f = f.assume_init();
+            mpfr::const_pi(&mut f, mpfr::rnd_t::RNDN);
+            mpfr::neg(&mut f, &f, mpfr::rnd_t::RNDN);
+            let mut limbs = [0; 4];
+            let len = mpfr::custom_get_size(100) / core::mem::size_of::<gmp::limb_t>();
+            let significand = mpfr::custom_get_significand(&f) as *const gmp::limb_t;
+            for (i, limb) in limbs[..len].iter_mut().enumerate() {
+                *limb = *significand.add(i);
+            }
+            let view = MpfrRef::from_limbs(&limbs[..len], 100, f.exp, true);
+            assert_eq!(mpfr::get_prec(&*view), 100);
+            assert!(mpfr::equal_p(&*view, &f) != 0);
+            let copy = view;
+            assert!(mpfr::equal_p(copy.as_raw(), &f) != 0);
+
+            let one = [1 << (gmp::LIMB_BITS - 1)];
+            let view = MpfrRef::from_limbs(&one, 1, 1, false);
+            assert_eq!(mpfr::cmp_ui(&*view, 1), 0);
+
+            mpfr::clear(&mut f);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "significand not normalized")]
+    fn check_mpfr_ref_not_normalized() {
+        mpfr::MpfrRef::from_limbs(&[1], 1, 1, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "significand has bits after precision")]
+    fn check_mpfr_ref_extra_bits() {
+        mpfr::MpfrRef::from_limbs(&[!0], 10, 1, false);
+    }
+
+    #[test]
+    #[cfg(const_generics)]
+    fn check_fixed_mpfr() {
+        use mpfr::FixedMpfr;
+
+        type F100 = FixedMpfr<{ mpfr::fixed_limbs(100) }>;
+        fn third() -> F100 {
+            let mut f = F100::new(100);
+            unsafe {
+                assert!(mpfr::nan_p(&*f) != 0);
+                mpfr::set_ui(f.as_raw_mut(), 1, mpfr::rnd_t::RNDN);
+                mpfr::div_ui(f.as_raw_mut(), f.as_raw(), 3, mpfr::rnd_t::RNDN);
+            }
+            f
+        }
+
+        unsafe {
+            let mut h = MaybeUninit::uninit();
+            mpfr::init2(h.as_mut_ptr(), 100);
+            let mut h = h.assume_init();
+            mpfr::set_ui(&mut h, 1, mpfr::rnd_t::RNDN);
+            mpfr::div_ui(&mut h, &h, 3, mpfr::rnd_t::RNDN);
+
+            // moved out of third, into the array and into the clone
+            let mut array = [third(), third()];
+            let f = array[1].clone();
+            assert_eq!(mpfr::get_prec(&*f), 100);
+            assert!(mpfr::equal_p(&*f, &h) != 0);
+            mpfr::mul_ui(array[0].as_raw_mut(), &*f, 3, mpfr::rnd_t::RNDN);
+            assert_eq!(mpfr::cmp_ui(&*array[0], 1), 0);
+            let [a, b] = array;
+            assert_eq!(mpfr::cmp_ui(a.as_raw(), 1), 0);
+            assert!(mpfr::equal_p(b.as_raw(), &h) != 0);
+            assert_eq!(
+                mpfr::custom_get_significand(&*b),
+                b.limbs.as_ptr() as *mut c_void
+            );
+
+            // extra limbs are allowed
+            let mut wide = FixedMpfr::<4>::new(1);
+            mpfr::set_si(wide.as_raw_mut(), -2, mpfr::rnd_t::RNDN);
+            assert_eq!(mpfr::get_si(&*wide, mpfr::rnd_t::RNDN), -2);
+            assert_eq!(
+                crate::tests::format(format_args!("{}", wide)).as_str(),
+                "-2"
+            );
+
+            mpfr::clear(&mut h);
+        }
+    }
+
+    #[test]
+    #[cfg(const_generics)]
+    #[should_panic(expected = "too few limbs for precision")]
+    fn check_fixed_mpfr_too_few_limbs() {
+        let bits = crate::gmp::NUMB_BITS as mpfr::prec_t;
+        mpfr::FixedMpfr::<1>::new(bits + 1);
+    }
 }
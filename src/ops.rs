@@ -0,0 +1,750 @@
+// Copyright © 2017–2020 University of Malta
+
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License
+// as published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License and a copy of the GNU General Public License along with
+// this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Operator traits for gmp::Mpz, which are only available with the
+// owned feature.
+//
+// Each operation is a function writing into a destination that may
+// alias the operands, which GMP allows. Owned operands are reused as
+// the destination so that their allocation is reused, and only
+// operations on two references allocate a new integer.
+//
+// Primitive operands are split into a sign and a magnitude so that
+// only the _ui functions are needed. A 64-bit magnitude that does not
+// fit in c_ulong, which has 32 bits on some targets, is converted to a
+// temporary integer instead.
+
+use crate::gmp::{self, Mpz};
+use core::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::{
+        Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div,
+        DivAssign, Mul, MulAssign, Neg, Not, Rem, RemAssign, Shl, ShlAssign, Shr, ShrAssign, Sub,
+        SubAssign,
+    },
+    slice,
+};
+use libc::c_ulong;
+
+type MpzPtr = *mut gmp::mpz_t;
+type MpzSrcPtr = *const gmp::mpz_t;
+
+unsafe fn check_divisor(d: MpzSrcPtr) {
+    assert!(gmp::mpz_sgn(d) != 0, "division by zero");
+}
+
+unsafe fn tdiv_q(q: MpzPtr, n: MpzSrcPtr, d: MpzSrcPtr) {
+    check_divisor(d);
+    gmp::mpz_tdiv_q(q, n, d);
+}
+
+unsafe fn tdiv_r(r: MpzPtr, n: MpzSrcPtr, d: MpzSrcPtr) {
+    check_divisor(d);
+    gmp::mpz_tdiv_r(r, n, d);
+}
+
+// Implements $Op and $OpAssign for all combinations of owned and
+// borrowed operands using $func(rop, op1, op2).
+macro_rules! mpz_op {
+    ($Op:ident $op:ident, $OpAssign:ident $op_assign:ident, $func:path) => {
+        impl $OpAssign<&Mpz> for Mpz {
+            #[inline]
+            fn $op_assign(&mut self, rhs: &Mpz) {
+                unsafe {
+                    $func(self.as_raw_mut(), self.as_raw(), rhs.as_raw());
+                }
+            }
+        }
+
+        impl $OpAssign<Mpz> for Mpz {
+            #[inline]
+            fn $op_assign(&mut self, rhs: Mpz) {
+                self.$op_assign(&rhs);
+            }
+        }
+
+        impl $Op<&Mpz> for Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(mut self, rhs: &Mpz) -> Mpz {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+
+        impl $Op<Mpz> for Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(mut self, rhs: Mpz) -> Mpz {
+                self.$op_assign(&rhs);
+                self
+            }
+        }
+
+        impl $Op<Mpz> for &Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(self, mut rhs: Mpz) -> Mpz {
+                unsafe {
+                    $func(rhs.as_raw_mut(), self.as_raw(), rhs.as_raw());
+                }
+                rhs
+            }
+        }
+
+        impl $Op<&Mpz> for &Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(self, rhs: &Mpz) -> Mpz {
+                let mut ret = Mpz::new();
+                unsafe {
+                    $func(ret.as_raw_mut(), self.as_raw(), rhs.as_raw());
+                }
+                ret
+            }
+        }
+    };
+}
+
+mpz_op! { Add add, AddAssign add_assign, gmp::mpz_add }
+mpz_op! { Sub sub, SubAssign sub_assign, gmp::mpz_sub }
+mpz_op! { Mul mul, MulAssign mul_assign, gmp::mpz_mul }
+mpz_op! { Div div, DivAssign div_assign, tdiv_q }
+mpz_op! { Rem rem, RemAssign rem_assign, tdiv_r }
+mpz_op! { BitAnd bitand, BitAndAssign bitand_assign, gmp::mpz_and }
+mpz_op! { BitOr bitor, BitOrAssign bitor_assign, gmp::mpz_ior }
+mpz_op! { BitXor bitxor, BitXorAssign bitxor_assign, gmp::mpz_xor }
+
+impl Neg for Mpz {
+    type Output = Mpz;
+    #[inline]
+    fn neg(mut self) -> Mpz {
+        unsafe {
+            gmp::mpz_neg(self.as_raw_mut(), self.as_raw());
+        }
+        self
+    }
+}
+
+impl Neg for &Mpz {
+    type Output = Mpz;
+    #[inline]
+    fn neg(self) -> Mpz {
+        let mut ret = Mpz::new();
+        unsafe {
+            gmp::mpz_neg(ret.as_raw_mut(), self.as_raw());
+        }
+        ret
+    }
+}
+
+impl Not for Mpz {
+    type Output = Mpz;
+    #[inline]
+    fn not(mut self) -> Mpz {
+        unsafe {
+            gmp::mpz_com(self.as_raw_mut(), self.as_raw());
+        }
+        self
+    }
+}
+
+impl Not for &Mpz {
+    type Output = Mpz;
+    #[inline]
+    fn not(self) -> Mpz {
+        let mut ret = Mpz::new();
+        unsafe {
+            gmp::mpz_com(ret.as_raw_mut(), self.as_raw());
+        }
+        ret
+    }
+}
+
+// Implements a shift operator and its assignment variant for owned and
+// borrowed integers using $func(rop, op, bits).
+macro_rules! mpz_shift {
+    ($Op:ident $op:ident, $OpAssign:ident $op_assign:ident, $func:path) => {
+        impl $OpAssign<u32> for Mpz {
+            #[inline]
+            fn $op_assign(&mut self, rhs: u32) {
+                unsafe {
+                    $func(self.as_raw_mut(), self.as_raw(), gmp::bitcnt_t::from(rhs));
+                }
+            }
+        }
+
+        impl $Op<u32> for Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(mut self, rhs: u32) -> Mpz {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+
+        impl $Op<u32> for &Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(self, rhs: u32) -> Mpz {
+                let mut ret = Mpz::new();
+                unsafe {
+                    $func(ret.as_raw_mut(), self.as_raw(), gmp::bitcnt_t::from(rhs));
+                }
+                ret
+            }
+        }
+    };
+}
+
+mpz_shift! { Shl shl, ShlAssign shl_assign, gmp::mpz_mul_2exp }
+mpz_shift! { Shr shr, ShrAssign shr_assign, gmp::mpz_fdiv_q_2exp }
+
+// A primitive operand split into whether it is negative and its
+// magnitude.
+#[derive(Clone, Copy)]
+struct Small {
+    neg: bool,
+    mag: c_ulong,
+}
+
+// A primitive operand, which is Small if its magnitude fits in c_ulong
+// and a temporary integer otherwise.
+enum Prim {
+    Small(Small),
+    Big(Mpz),
+}
+
+trait ToPrim: Copy {
+    fn to_prim(self) -> Prim;
+}
+
+macro_rules! to_prim {
+    (unsigned $($T:ty)*) => { $(
+        impl ToPrim for $T {
+            #[inline]
+            fn to_prim(self) -> Prim {
+                Prim::Small(Small {
+                    neg: false,
+                    mag: c_ulong::from(self),
+                })
+            }
+        }
+    )* };
+    (signed $($T:ty)*) => { $(
+        impl ToPrim for $T {
+            #[inline]
+            fn to_prim(self) -> Prim {
+                // widened first, as MIN.wrapping_abs() is negative
+                Prim::Small(Small {
+                    neg: self < 0,
+                    mag: c_ulong::from(i32::from(self).wrapping_abs() as u32),
+                })
+            }
+        }
+    )* };
+    (wide unsigned $($T:ty)*) => { $(
+        impl ToPrim for $T {
+            #[inline]
+            fn to_prim(self) -> Prim {
+                wide_prim(false, self as u64)
+            }
+        }
+    )* };
+    (wide signed $($T:ty)*) => { $(
+        impl ToPrim for $T {
+            #[inline]
+            fn to_prim(self) -> Prim {
+                // i64::MIN.wrapping_abs() as u64 is 2^63 as required
+                wide_prim(self < 0, (self as i64).wrapping_abs() as u64)
+            }
+        }
+    )* };
+}
+
+to_prim! { unsigned u8 u16 u32 }
+to_prim! { signed i8 i16 i32 }
+to_prim! { wide unsigned u64 usize }
+to_prim! { wide signed i64 isize }
+
+fn wide_prim(neg: bool, mag: u64) -> Prim {
+    let small = mag as c_ulong;
+    if small as u64 == mag {
+        return Prim::Small(Small { neg, mag: small });
+    }
+    // c_ulong has 32 bits, so set the magnitude in two parts
+    let mut z = Mpz::new();
+    unsafe {
+        let raw = z.as_raw_mut();
+        gmp::mpz_set_ui(raw, (mag >> 32) as c_ulong);
+        gmp::mpz_mul_2exp(raw, raw, 32);
+        gmp::mpz_add_ui(raw, raw, mag as u32 as c_ulong);
+        neg_if(raw, neg);
+    }
+    Prim::Big(z)
+}
+
+unsafe fn neg_if(rop: MpzPtr, neg: bool) {
+    if neg {
+        gmp::mpz_neg(rop, rop);
+    }
+}
+
+unsafe fn add_small(rop: MpzPtr, op: MpzSrcPtr, s: Small) {
+    if s.neg {
+        gmp::mpz_sub_ui(rop, op, s.mag);
+    } else {
+        gmp::mpz_add_ui(rop, op, s.mag);
+    }
+}
+
+unsafe fn small_add(rop: MpzPtr, s: Small, op: MpzSrcPtr) {
+    add_small(rop, op, s);
+}
+
+unsafe fn sub_small(rop: MpzPtr, op: MpzSrcPtr, s: Small) {
+    if s.neg {
+        gmp::mpz_add_ui(rop, op, s.mag);
+    } else {
+        gmp::mpz_sub_ui(rop, op, s.mag);
+    }
+}
+
+unsafe fn small_sub(rop: MpzPtr, s: Small, op: MpzSrcPtr) {
+    if s.neg {
+        // -mag - op = -(op + mag)
+        gmp::mpz_add_ui(rop, op, s.mag);
+        gmp::mpz_neg(rop, rop);
+    } else {
+        gmp::mpz_ui_sub(rop, s.mag, op);
+    }
+}
+
+unsafe fn mul_small(rop: MpzPtr, op: MpzSrcPtr, s: Small) {
+    gmp::mpz_mul_ui(rop, op, s.mag);
+    neg_if(rop, s.neg);
+}
+
+unsafe fn small_mul(rop: MpzPtr, s: Small, op: MpzSrcPtr) {
+    mul_small(rop, op, s);
+}
+
+unsafe fn div_small(rop: MpzPtr, op: MpzSrcPtr, s: Small) {
+    assert!(s.mag != 0, "division by zero");
+    gmp::mpz_tdiv_q_ui(rop, op, s.mag);
+    neg_if(rop, s.neg);
+}
+
+// The quotient has a magnitude of at most mag, so it can be computed
+// without allocating.
+unsafe fn small_div(rop: MpzPtr, s: Small, op: MpzSrcPtr) {
+    check_divisor(op);
+    let q = if gmp::mpz_cmpabs_ui(op, s.mag) > 0 {
+        0
+    } else {
+        s.mag / gmp::mpz_get_ui(op)
+    };
+    let neg = s.neg != (gmp::mpz_sgn(op) < 0);
+    gmp::mpz_set_ui(rop, q);
+    neg_if(rop, neg);
+}
+
+unsafe fn rem_small(rop: MpzPtr, op: MpzSrcPtr, s: Small) {
+    assert!(s.mag != 0, "division by zero");
+    // the remainder has the sign of op
+    gmp::mpz_tdiv_r_ui(rop, op, s.mag);
+}
+
+unsafe fn small_rem(rop: MpzPtr, s: Small, op: MpzSrcPtr) {
+    check_divisor(op);
+    let r = if gmp::mpz_cmpabs_ui(op, s.mag) > 0 {
+        s.mag
+    } else {
+        s.mag % gmp::mpz_get_ui(op)
+    };
+    gmp::mpz_set_ui(rop, r);
+    neg_if(rop, s.neg);
+}
+
+// Implements $Op and $OpAssign with the primitive types using
+// $func(rop, op, small) and $rev(rop, small, op), or $big with a
+// temporary integer if the primitive is not small.
+macro_rules! mpz_op_prim {
+    ($Op:ident $op:ident, $OpAssign:ident $op_assign:ident, $func:path, $rev:path, $big:path) => {
+        mpz_op_prim! {
+            $Op $op, $OpAssign $op_assign, $func, $rev, $big;
+            u8 u16 u32 u64 usize i8 i16 i32 i64 isize
+        }
+    };
+    (
+        $Op:ident $op:ident, $OpAssign:ident $op_assign:ident, $func:path, $rev:path, $big:path;
+        $($T:ty)*
+    ) => { $(
+        impl $OpAssign<$T> for Mpz {
+            #[inline]
+            fn $op_assign(&mut self, rhs: $T) {
+                unsafe {
+                    match rhs.to_prim() {
+                        Prim::Small(s) => $func(self.as_raw_mut(), self.as_raw(), s),
+                        Prim::Big(z) => $big(self.as_raw_mut(), self.as_raw(), z.as_raw()),
+                    }
+                }
+            }
+        }
+
+        impl $Op<$T> for Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(mut self, rhs: $T) -> Mpz {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+
+        impl $Op<$T> for &Mpz {
+            type Output = Mpz;
+            #[inline]
+            fn $op(self, rhs: $T) -> Mpz {
+                let mut ret = Mpz::new();
+                unsafe {
+                    match rhs.to_prim() {
+                        Prim::Small(s) => $func(ret.as_raw_mut(), self.as_raw(), s),
+                        Prim::Big(z) => $big(ret.as_raw_mut(), self.as_raw(), z.as_raw()),
+                    }
+                }
+                ret
+            }
+        }
+
+        impl $Op<Mpz> for $T {
+            type Output = Mpz;
+            #[inline]
+            fn $op(self, mut rhs: Mpz) -> Mpz {
+                unsafe {
+                    match self.to_prim() {
+                        Prim::Small(s) => $rev(rhs.as_raw_mut(), s, rhs.as_raw()),
+                        Prim::Big(z) => $big(rhs.as_raw_mut(), z.as_raw(), rhs.as_raw()),
+                    }
+                }
+                rhs
+            }
+        }
+
+        impl $Op<&Mpz> for $T {
+            type Output = Mpz;
+            #[inline]
+            fn $op(self, rhs: &Mpz) -> Mpz {
+                let mut ret = Mpz::new();
+                unsafe {
+                    match self.to_prim() {
+                        Prim::Small(s) => $rev(ret.as_raw_mut(), s, rhs.as_raw()),
+                        Prim::Big(z) => $big(ret.as_raw_mut(), z.as_raw(), rhs.as_raw()),
+                    }
+                }
+                ret
+            }
+        }
+    )* };
+}
+
+mpz_op_prim! { Add add, AddAssign add_assign, add_small, small_add, gmp::mpz_add }
+mpz_op_prim! { Sub sub, SubAssign sub_assign, sub_small, small_sub, gmp::mpz_sub }
+mpz_op_prim! { Mul mul, MulAssign mul_assign, mul_small, small_mul, gmp::mpz_mul }
+mpz_op_prim! { Div div, DivAssign div_assign, div_small, small_div, tdiv_q }
+mpz_op_prim! { Rem rem, RemAssign rem_assign, rem_small, small_rem, tdiv_r }
+
+impl PartialEq for Mpz {
+    #[inline]
+    fn eq(&self, other: &Mpz) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Mpz {}
+
+impl PartialOrd for Mpz {
+    #[inline]
+    fn partial_cmp(&self, other: &Mpz) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Mpz {
+    #[inline]
+    fn cmp(&self, other: &Mpz) -> Ordering {
+        unsafe { gmp::mpz_cmp(self.as_raw(), other.as_raw()).cmp(&0) }
+    }
+}
+
+unsafe fn cmp_small(op: MpzSrcPtr, s: Small) -> Ordering {
+    if !s.neg {
+        gmp::mpz_cmp_ui(op, s.mag).cmp(&0)
+    } else if gmp::mpz_sgn(op) >= 0 {
+        Ordering::Greater
+    } else {
+        // both are negative, so the larger magnitude is smaller
+        gmp::mpz_cmpabs_ui(op, s.mag).cmp(&0).reverse()
+    }
+}
+
+// Implements comparisons with a primitive type in both directions.
+macro_rules! mpz_cmp_prim {
+    ($($T:ty)*) => { $(
+        impl PartialEq<$T> for Mpz {
+            #[inline]
+            fn eq(&self, other: &$T) -> bool {
+                self.partial_cmp(other) == Some(Ordering::Equal)
+            }
+        }
+
+        impl PartialEq<Mpz> for $T {
+            #[inline]
+            fn eq(&self, other: &Mpz) -> bool {
+                other.eq(self)
+            }
+        }
+
+        impl PartialOrd<$T> for Mpz {
+            #[inline]
+            fn partial_cmp(&self, other: &$T) -> Option<Ordering> {
+                let ord = match other.to_prim() {
+                    Prim::Small(s) => unsafe { cmp_small(self.as_raw(), s) },
+                    Prim::Big(z) => self.cmp(&z),
+                };
+                Some(ord)
+            }
+        }
+
+        impl PartialOrd<Mpz> for $T {
+            #[inline]
+            fn partial_cmp(&self, other: &Mpz) -> Option<Ordering> {
+                other.partial_cmp(self).map(Ordering::reverse)
+            }
+        }
+    )* };
+}
+
+mpz_cmp_prim! { u8 u16 u32 u64 usize i8 i16 i32 i64 isize }
+
+impl Hash for Mpz {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        unsafe {
+            let raw = self.as_raw();
+            (*raw).size.hash(state);
+            let limbs = slice::from_raw_parts(gmp::mpz_limbs_read(raw), gmp::mpz_size(raw));
+            limbs.hash(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gmp::{self, Mpz};
+    use core::hash::{Hash, Hasher};
+
+    // c_long can have 32 bits, so set the value in two parts
+    fn from_i64(val: i64) -> Mpz {
+        let mut z = Mpz::new();
+        unsafe {
+            gmp::mpz_set_si(z.as_raw_mut(), (val >> 32) as i32 as _);
+        }
+        (z << 32) + val as u32
+    }
+
+    #[test]
+    fn check_arith() {
+        let vals = [-1000i32, -7, -3, -1, 0, 1, 3, 7, 1000];
+        for &a in &vals {
+            for &b in &vals {
+                let (za, zb) = (from_i64(a.into()), from_i64(b.into()));
+                let (a, b) = (i64::from(a), i64::from(b));
+                assert_eq!(&za + &zb, from_i64(a + b));
+                assert_eq!(za.clone() - &zb, from_i64(a - b));
+                assert_eq!(&za * zb.clone(), from_i64(a * b));
+                assert_eq!(&za & &zb, from_i64(a & b));
+                assert_eq!(&za | &zb, from_i64(a | b));
+                assert_eq!(&za ^ &zb, from_i64(a ^ b));
+                if b != 0 {
+                    assert_eq!(&za / &zb, from_i64(a / b));
+                    assert_eq!(za.clone() % zb.clone(), from_i64(a % b));
+                }
+                let mut z = za.clone();
+                z += &zb;
+                z *= zb.clone();
+                z -= &za;
+                assert_eq!(z, from_i64((a + b) * b - a));
+                assert_eq!(za.cmp(&zb), a.cmp(&b));
+                assert_eq!(za == zb, a == b);
+            }
+            let za = from_i64(a.into());
+            let a = i64::from(a);
+            assert_eq!(-&za, from_i64(-a));
+            assert_eq!(!za.clone(), from_i64(!a));
+            assert_eq!(&za << 3, from_i64(a << 3));
+            assert_eq!(za.clone() >> 2, from_i64(a >> 2));
+        }
+    }
+
+    #[test]
+    fn check_arith_prim() {
+        let vals = [
+            -0x8000_0000,
+            -1000,
+            -7,
+            -3,
+            -1,
+            0,
+            1,
+            3,
+            7,
+            1000,
+            0x7fff_ffff,
+        ];
+        for &a in &vals {
+            for &b in &vals {
+                let za = from_i64(a.into());
+                let (a64, b64) = (i64::from(a), i64::from(b));
+                assert_eq!(&za + b, from_i64(a64 + b64));
+                assert_eq!(b + &za, from_i64(a64 + b64));
+                assert_eq!(za.clone() - b, from_i64(a64 - b64));
+                assert_eq!(b - za.clone(), from_i64(b64 - a64));
+                assert_eq!(&za * b, from_i64(a64 * b64));
+                assert_eq!(b * &za, from_i64(a64 * b64));
+                if b != 0 {
+                    assert_eq!(&za / b, from_i64(a64 / b64));
+                    assert_eq!(za.clone() % b, from_i64(a64 % b64));
+                }
+                if a != 0 {
+                    assert_eq!(b / &za, from_i64(b64 / a64));
+                    assert_eq!(b % za.clone(), from_i64(b64 % a64));
+                }
+                assert_eq!(za.partial_cmp(&b), Some(a.cmp(&b)));
+                assert_eq!(b.partial_cmp(&za), Some(b.cmp(&a)));
+                assert_eq!(za == b, a == b);
+            }
+        }
+        // MIN has no positive counterpart in the same type
+        let za = from_i64(1000);
+        assert_eq!(&za + i8::MIN, from_i64(1000 - 128));
+        assert_eq!(i8::MIN - &za, from_i64(-128 - 1000));
+        assert_eq!(&za * i16::MIN, from_i64(1000 * -32768));
+        assert_eq!(i16::MIN / &za, from_i64(-32));
+        assert_eq!(i16::MIN % &za, from_i64(-768));
+        assert_eq!(&za * i32::MIN, from_i64(1000 * i64::from(i32::MIN)));
+        assert_eq!(&za / i8::MIN, from_i64(-7));
+        assert_eq!(&za % i8::MIN, from_i64(104));
+        assert!(za > i8::MIN && from_i64(-128) == i8::MIN && from_i64(-129) < i8::MIN);
+
+        let vals64 = [i64::MIN, -(1 << 40), -1, 0, 1, 1 << 40, i64::MAX];
+        for &a in &vals64 {
+            for &b in &vals64 {
+                let za = from_i64(a);
+                let (a128, b128) = (i128::from(a), i128::from(b));
+                let from_i128 = |v: i128| (from_i64((v >> 64) as i64) << 64) + v as u64;
+                assert_eq!(&za + b, from_i128(a128 + b128));
+                assert_eq!(b - za.clone(), from_i128(b128 - a128));
+                assert_eq!(&za * b, from_i128(a128 * b128));
+                if b != 0 {
+                    assert_eq!(&za / b, from_i128(a128 / b128));
+                    assert_eq!(za.clone() % b, from_i128(a128 % b128));
+                }
+                assert_eq!(za.partial_cmp(&b), Some(a.cmp(&b)));
+                assert_eq!(b.partial_cmp(&za), Some(b.cmp(&a)));
+            }
+        }
+        let max = from_i64(-1) + u64::MAX + 1u32;
+        assert_eq!(max, (from_i64(1) << 64) - 1u32);
+        assert_eq!(u64::MAX - max.clone(), 0);
+        assert!(max > u64::MAX - 1 && max == u64::MAX);
+        assert_eq!(from_i64(-6) / 4usize + 3isize, 2);
+
+        let mut z = from_i64(10);
+        z += 5u8;
+        z -= 20i16;
+        z *= 3u16;
+        z /= -2i8;
+        assert_eq!(z, from_i64(7));
+        z %= 4u32;
+        assert_eq!(z, 3u32);
+        assert!(z < 4u8 && z > -1i8);
+        z <<= 100;
+        z >>= 99;
+        assert_eq!(z, 6);
+        z -= 7;
+        assert_eq!(z >> 1, -1);
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn check_div_zero() {
+        let _ = from_i64(1) / &Mpz::new();
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn check_rem_zero_prim() {
+        let _ = from_i64(1) % 0u32;
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn check_prim_div_zero() {
+        let _ = 1 / Mpz::new();
+    }
+
+    #[test]
+    fn check_reuse() {
+        let mut a = from_i64(1) << 1000;
+        let b = from_i64(3);
+        let limbs = unsafe { (*a.as_raw()).d };
+        a = a * &b + 1u32;
+        a = &b - a;
+        assert_eq!(unsafe { (*a.as_raw()).d }, limbs);
+    }
+
+    struct SumHasher(u64);
+
+    impl Hasher for SumHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = self.0.wrapping_mul(31).wrapping_add(u64::from(b));
+            }
+        }
+    }
+
+    fn hash(z: &Mpz) -> u64 {
+        let mut hasher = SumHasher(0);
+        z.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn check_hash() {
+        let a = (from_i64(1) << 200) - 1u32;
+        let mut b = from_i64(1) << 300;
+        b >>= 100;
+        b -= 1;
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(hash(&a), hash(&-&a));
+        assert_eq!(hash(&Mpz::new()), hash(&(a - b)));
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright © 2017–2020 University of Malta
+
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License
+// as published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License and a copy of the GNU General Public License along with
+// this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Helpers for the owned types such as gmp::Mpz, which are only
+// available with the owned feature.
+//
+// Each owned type is a struct with a single field named inner holding
+// the initialized raw value, which is cleared when the owned value is
+// dropped.
+
+// Implements the raw pointer and ownership transfer methods, Drop,
+// Send and Sync for an owned type whose raw value is cleared using
+// $clear.
+macro_rules! impl_owned {
+    ($Owned:ident, $raw:ty, $clear:path) => {
+        impl $Owned {
+            /// Returns a const pointer to the inner raw value, for use
+            /// as a source operand of the functions in this crate.
+            #[inline]
+            pub fn as_raw(&self) -> *const $raw {
+                &self.inner
+            }
+
+            /// Returns a mutable pointer to the inner raw value, for
+            /// use as any operand of the functions in this crate.
+            #[inline]
+            pub fn as_raw_mut(&mut self) -> *mut $raw {
+                &mut self.inner
+            }
+
+            /// Consumes the owned value and returns the inner raw value
+            /// without clearing it.
+            ///
+            /// The caller becomes responsible for clearing the raw
+            /// value, for example by passing it to C code that takes
+            /// ownership, or back to [`from_raw`](#method.from_raw).
+            #[inline]
+            pub fn into_raw(self) -> $raw {
+                let owned = core::mem::ManuallyDrop::new(self);
+                owned.inner
+            }
+
+            /// Creates an owned value from an initialized raw value,
+            /// taking ownership of it.
+            ///
+            /// # Safety
+            ///
+            /// `raw` must be initialized, and it must not be used or
+            /// cleared through any other copy after this call, as it is
+            /// cleared when the returned value is dropped.
+            #[inline]
+            pub unsafe fn from_raw(raw: $raw) -> Self {
+                $Owned { inner: raw }
+            }
+        }
+
+        impl Drop for $Owned {
+            #[inline]
+            fn drop(&mut self) {
+                unsafe {
+                    $clear(&mut self.inner);
+                }
+            }
+        }
+
+        // The raw value owns its memory, and the functions taking a
+        // const pointer do not modify it.
+        unsafe impl Send for $Owned {}
+        unsafe impl Sync for $Owned {}
+    };
+}
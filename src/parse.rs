@@ -0,0 +1,812 @@
+// Copyright © 2017–2020 University of Malta
+
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License
+// as published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License and a copy of the GNU General Public License along with
+// this program. If not, see <https://www.gnu.org/licenses/>.
+
+/*!
+String parsing from byte slices instead of NUL-terminated strings.
+
+Every function parses the whole of a byte slice, which does not need
+to be NUL terminated, using the syntax of the C function with the
+same name. Instead of returning −1, the functions return a
+[`ParseError`] with the byte offset in the input where the error was
+detected and the reason for the error.
+
+Integers and rationals are converted with [`mpn_set_str`] using the
+limbs of the destination as scratch space. Floating-point numbers are
+validated in Rust for [`mpf_set_str`], and parsed using the end
+pointers of [`strtofr`] and [`strtoc`] for [`mpfr_set_str`] and
+[`mpc_set_str`]; these C functions need a NUL-terminated copy of the
+input, which is allocated using the GMP memory functions. So none of
+the functions need a Rust allocator.
+
+The functions panic if the base is not valid for the C function with
+the same name.
+
+# Examples
+
+```rust
+use core::mem::MaybeUninit;
+use gmp_mpfr_sys::gmp;
+use gmp_mpfr_sys::parse::{self, ParseErrorKind};
+unsafe {
+    let mut z = MaybeUninit::uninit();
+    gmp::mpz_init(z.as_mut_ptr());
+    let mut z = z.assume_init();
+    let input = b"x = -0x1f;";
+    parse::mpz_set_str(&mut z, &input[4..9], 0).unwrap();
+    assert_eq!(gmp::mpz_get_si(&z), -31);
+    let err = parse::mpz_set_str(&mut z, b"12a4", 10).unwrap_err();
+    assert_eq!(err.offset, 2);
+    assert_eq!(err.kind, ParseErrorKind::InvalidDigit);
+    gmp::mpz_clear(&mut z);
+}
+```
+
+[`mpc_set_str`]: fn.mpc_set_str.html
+[`mpf_set_str`]: fn.mpf_set_str.html
+[`mpfr_set_str`]: fn.mpfr_set_str.html
+[`mpn_set_str`]: ../gmp/fn.mpn_set_str.html
+[`ParseError`]: struct.ParseError.html
+[`strtoc`]: ../mpc/fn.strtoc.html
+[`strtofr`]: ../mpfr/fn.strtofr.html
+*/
+
+use crate::gmp::{self, mpf_t, mpq_t, mpz_t};
+#[cfg(feature = "mpc")]
+use crate::mpc::{self, mpc_t};
+#[cfg(feature = "mpfr")]
+use crate::mpfr::{self, mpfr_t};
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::{mem, ptr, slice};
+use libc::{c_char, c_int, c_long};
+
+/// The reason why parsing failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParseErrorKind {
+    /// The input ends where more is expected, for example there are
+    /// no digits.
+    Empty,
+    /// A byte is not valid at its position, for example a digit
+    /// which is too large for the base.
+    InvalidDigit,
+    /// The exponent is too large in magnitude for the number type.
+    ExponentOverflow,
+}
+
+/// An error returned by the parsing functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ParseError {
+    /// The byte offset in the input where the error was detected.
+    pub offset: usize,
+    /// The reason for the error.
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let reason = match self.kind {
+            ParseErrorKind::Empty => "missing digits",
+            ParseErrorKind::InvalidDigit => "invalid digit",
+            ParseErrorKind::ExponentOverflow => "exponent overflow",
+        };
+        write!(f, "{} at byte {}", reason, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[inline]
+fn error(offset: usize, kind: ParseErrorKind) -> ParseError {
+    ParseError { offset, kind }
+}
+
+#[inline]
+fn ceil_div(n: usize, d: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        (n - 1) / d + 1
+    }
+}
+
+// Same as isspace in the C locale.
+#[inline]
+fn is_space(b: u8) -> bool {
+    b == b' ' || (b'\t'..=b'\r').contains(&b)
+}
+
+fn skip_space(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && is_space(bytes[pos]) {
+        pos += 1;
+    }
+    pos
+}
+
+// Like the digit tables in GMP: for bases up to 36 letters are
+// case insensitive, for larger bases upper case letters come first.
+// Returns 255 for bytes that are not digits in any base.
+#[inline]
+fn digit_value(b: u8, base: c_int) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'A'..=b'Z' => b - b'A' + 10,
+        b'a'..=b'z' if base > 36 => b - b'a' + 36,
+        b'a'..=b'z' => b - b'a' + 10,
+        _ => 255,
+    }
+}
+
+#[inline]
+fn is_digit(b: u8, base: c_int) -> bool {
+    c_int::from(digit_value(b, base)) < base
+}
+
+// The significant digits of an integer validated by scan_int.
+struct IntDigits {
+    negative: bool,
+    base: c_int,
+    start: usize,
+    count: usize,
+}
+
+// Validates an integer using the syntax of mpz_set_str. The offset is
+// added to the offsets of errors.
+fn scan_int(bytes: &[u8], base: c_int, offset: usize) -> Result<IntDigits, ParseError> {
+    assert!(base == 0 || (2..=62).contains(&base), "base out of range");
+    let mut pos = skip_space(bytes, 0);
+    let negative = bytes.get(pos) == Some(&b'-');
+    if negative {
+        pos += 1;
+    }
+    let first = match bytes.get(pos) {
+        Some(&b) => b,
+        None => return Err(error(offset + pos, ParseErrorKind::Empty)),
+    };
+    let mut base = base;
+    if !is_digit(first, if base == 0 { 10 } else { base }) {
+        return Err(error(offset + pos, ParseErrorKind::InvalidDigit));
+    }
+    if base == 0 {
+        base = 10;
+        if first == b'0' {
+            base = 8;
+            pos += 1;
+            match bytes.get(pos) {
+                Some(b'x') | Some(b'X') => {
+                    base = 16;
+                    pos += 1;
+                }
+                Some(b'b') | Some(b'B') => {
+                    base = 2;
+                    pos += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    while pos < bytes.len() && (bytes[pos] == b'0' || is_space(bytes[pos])) {
+        pos += 1;
+    }
+    let mut count = 0;
+    for (i, &b) in bytes[pos..].iter().enumerate() {
+        if is_space(b) {
+            continue;
+        }
+        if !is_digit(b, base) {
+            return Err(error(offset + pos + i, ParseErrorKind::InvalidDigit));
+        }
+        count += 1;
+    }
+    Ok(IntDigits {
+        negative,
+        base,
+        start: pos,
+        count,
+    })
+}
+
+// Stores an integer validated by scan_int. The digit values are
+// written to the limbs after the space needed for the result, which
+// is what mpn_set_str requires: one more limb than the largest
+// number with the given number of digits.
+unsafe fn set_int(rop: *mut mpz_t, bytes: &[u8], digits: &IntDigits) {
+    if digits.count == 0 {
+        gmp::mpz_set_ui(rop, 0);
+        return;
+    }
+    let limb_bytes = mem::size_of::<gmp::limb_t>();
+    let digit_bits = 32 - (digits.base as u32 - 1).leading_zeros() as usize;
+    let out_limbs = ceil_div(digits.count * digit_bits, gmp::LIMB_BITS as usize) + 1;
+    let scratch_limbs = ceil_div(digits.count, limb_bytes);
+    let d = gmp::mpz_limbs_write(rop, (out_limbs + scratch_limbs) as gmp::size_t);
+    let scratch = slice::from_raw_parts_mut(d.add(out_limbs) as *mut u8, digits.count);
+    let significant = bytes[digits.start..].iter().filter(|&&b| !is_space(b));
+    for (dst, &b) in scratch.iter_mut().zip(significant) {
+        *dst = digit_value(b, digits.base);
+    }
+    let size = gmp::mpn_set_str(d, scratch.as_ptr(), digits.count, digits.base);
+    gmp::mpz_limbs_finish(rop, if digits.negative { -size } else { size });
+}
+
+/// See: [`mpz_set_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Integer-Functions.html#index-mpz_005fset_005fstr)
+///
+/// The value of `rop` is not changed if an error is returned.
+pub unsafe fn mpz_set_str(rop: *mut mpz_t, bytes: &[u8], base: c_int) -> Result<(), ParseError> {
+    let digits = scan_int(bytes, base, 0)?;
+    set_int(rop, bytes, &digits);
+    Ok(())
+}
+
+/// See: [`mpq_set_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Initializing-Rationals.html#index-mpq_005fset_005fstr)
+///
+/// The value of `rop` is not changed if an error is returned. Like
+/// the C function, this function does not canonicalize the result.
+pub unsafe fn mpq_set_str(rop: *mut mpq_t, bytes: &[u8], base: c_int) -> Result<(), ParseError> {
+    match bytes.iter().position(|&b| b == b'/') {
+        None => {
+            let num = scan_int(bytes, base, 0)?;
+            set_int(gmp::mpq_numref(rop), bytes, &num);
+            gmp::mpz_set_ui(gmp::mpq_denref(rop), 1);
+        }
+        Some(slash) => {
+            let (num_bytes, den_bytes) = (&bytes[..slash], &bytes[slash + 1..]);
+            let num = scan_int(num_bytes, base, 0)?;
+            let den = scan_int(den_bytes, base, slash + 1)?;
+            set_int(gmp::mpq_numref(rop), num_bytes, &num);
+            set_int(gmp::mpq_denref(rop), den_bytes, &den);
+        }
+    }
+    Ok(())
+}
+
+// A NUL-terminated copy of the input allocated using the GMP memory
+// functions.
+struct CStrCopy {
+    ptr: *mut c_char,
+    size: usize,
+}
+
+impl CStrCopy {
+    unsafe fn new(bytes: &[u8]) -> CStrCopy {
+        let mut alloc_func = None;
+        gmp::get_memory_functions(&mut alloc_func, ptr::null_mut(), ptr::null_mut());
+        let alloc_func = alloc_func.expect("no GMP allocation function");
+        let size = bytes.len() + 1;
+        let ptr = alloc_func(size) as *mut c_char;
+        ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
+        *ptr.add(bytes.len()) = 0;
+        CStrCopy { ptr, size }
+    }
+}
+
+impl Drop for CStrCopy {
+    fn drop(&mut self) {
+        unsafe {
+            let mut free_func = None;
+            gmp::get_memory_functions(ptr::null_mut(), ptr::null_mut(), &mut free_func);
+            let free_func = free_func.expect("no GMP free function");
+            free_func(self.ptr as *mut _, self.size);
+        }
+    }
+}
+
+// Validates the exponent of an mpf number after the exponent marker,
+// which is at offset marker.
+fn scan_mpf_exp(
+    bytes: &[u8],
+    marker: usize,
+    exp_base: c_int,
+    digit_bits: c_long,
+) -> Result<(), ParseError> {
+    let mut pos = marker + 1;
+    let negative = bytes.get(pos) == Some(&b'-');
+    if negative || bytes.get(pos) == Some(&b'+') {
+        pos += 1;
+    }
+    if pos == bytes.len() {
+        return Err(error(pos, ParseErrorKind::Empty));
+    }
+    let overflow = error(marker, ParseErrorKind::ExponentOverflow);
+    let mut exp: c_long = 0;
+    for (i, &b) in bytes[pos..].iter().enumerate() {
+        if !is_digit(b, exp_base) {
+            return Err(error(pos + i, ParseErrorKind::InvalidDigit));
+        }
+        exp = exp
+            .checked_mul(c_long::from(exp_base))
+            .and_then(|e| e.checked_add(c_long::from(digit_value(b, exp_base))))
+            .ok_or(overflow)?;
+    }
+    // the exponent in bits must fit too
+    exp.checked_mul(digit_bits).ok_or(overflow)?;
+    Ok(())
+}
+
+/// See: [`mpf_set_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/gmp/Simple-Float-Functions.html#index-mpf_005fset_005fstr)
+///
+/// The value of `rop` is not changed if an error is returned. Unlike
+/// the C function, bytes after the digits of the exponent are not
+/// ignored, and an exponent is an error if its value in bits does not
+/// fit in a [`c_long`].
+///
+/// [`c_long`]: https://docs.rs/libc/0.2/libc/type.c_long.html
+pub unsafe fn mpf_set_str(rop: *mut mpf_t, bytes: &[u8], base: c_int) -> Result<(), ParseError> {
+    assert!(
+        base == 0 || (2..=62).contains(&base.wrapping_abs()),
+        "base out of range"
+    );
+    let c_base = base;
+    let (base, exp_base) = match base {
+        0 => (10, 10),
+        b if b < 0 => (-b, 10),
+        b => (b, b),
+    };
+    let mut pos = skip_space(bytes, 0);
+    if bytes.get(pos) == Some(&b'-') {
+        pos += 1;
+    }
+    let start = pos;
+    match bytes.get(pos) {
+        None => return Err(error(pos, ParseErrorKind::Empty)),
+        Some(&b'.') => match bytes.get(pos + 1) {
+            Some(&b) if is_digit(b, base) => {}
+            Some(_) => return Err(error(pos + 1, ParseErrorKind::InvalidDigit)),
+            None => return Err(error(pos + 1, ParseErrorKind::Empty)),
+        },
+        Some(&b) if is_digit(b, base) => {}
+        Some(_) => return Err(error(pos, ParseErrorKind::InvalidDigit)),
+    }
+    let is_marker = |b: u8| b == b'@' || (base <= 10 && (b == b'e' || b == b'E'));
+    let marker = bytes[start + 1..]
+        .iter()
+        .rposition(|&b| is_marker(b))
+        .map(|i| start + 1 + i);
+    let mantissa_end = marker.unwrap_or(bytes.len());
+    let mut seen_point = false;
+    for (i, &b) in bytes[start..mantissa_end].iter().enumerate() {
+        if b == b'.' && !seen_point {
+            seen_point = true;
+        } else if !is_space(b) && !is_digit(b, base) {
+            return Err(error(start + i, ParseErrorKind::InvalidDigit));
+        }
+    }
+    if let Some(marker) = marker {
+        let digit_bits = 32 - (base as u32 - 1).leading_zeros();
+        scan_mpf_exp(bytes, marker, exp_base, digit_bits as c_long)?;
+    }
+    let copy = CStrCopy::new(bytes);
+    let ret = gmp::mpf_set_str(rop, copy.ptr, c_base);
+    debug_assert_eq!(ret, 0);
+    Ok(())
+}
+
+// Returns the error for input that has no number at offset pos.
+#[cfg(feature = "mpfr")]
+fn no_number(bytes: &[u8], pos: usize) -> ParseError {
+    let mut pos = skip_space(bytes, pos);
+    if bytes.get(pos) == Some(&b'-') || bytes.get(pos) == Some(&b'+') {
+        pos += 1;
+    }
+    if pos == bytes.len() {
+        error(pos, ParseErrorKind::Empty)
+    } else {
+        error(pos, ParseErrorKind::InvalidDigit)
+    }
+}
+
+// Returns the offset of the exponent marker in a number parsed by
+// strtofr, or the start of the number if there is no marker.
+#[cfg(feature = "mpfr")]
+fn exp_marker(number: &[u8], base: c_int) -> usize {
+    number
+        .iter()
+        .rposition(|&b| {
+            let lower = b | 0x20;
+            b == b'@'
+                || (base <= 10 && lower == b'e')
+                || ((base == 0 || base == 2 || base == 16) && lower == b'p')
+        })
+        .unwrap_or(0)
+}
+
+// Parses a number starting at offset pos of the copy of bytes, and
+// returns the ternary value and the offset of the end of the number.
+// An overflow or underflow is reported as an exponent overflow; the
+// flags are raised like in the C function.
+#[cfg(feature = "mpfr")]
+unsafe fn strtofr_at(
+    rop: *mut mpfr_t,
+    bytes: &[u8],
+    copy: &CStrCopy,
+    pos: usize,
+    base: c_int,
+    rnd: mpfr::rnd_t,
+) -> Result<(c_int, usize), ParseError> {
+    let start = copy.ptr.add(pos);
+    let mut end = start;
+    let saved = mpfr::flags_save();
+    mpfr::clear_overflow();
+    mpfr::clear_underflow();
+    let inex = mpfr::strtofr(rop, start, &mut end, base, rnd);
+    let range_error = mpfr::overflow_p() != 0 || mpfr::underflow_p() != 0;
+    mpfr::flags_restore(saved | mpfr::flags_save(), !0);
+    let end_pos = pos + (end as usize - start as usize);
+    if end_pos == pos {
+        return Err(no_number(bytes, pos));
+    }
+    if range_error {
+        let marker = pos + exp_marker(&bytes[pos..end_pos], base);
+        return Err(error(marker, ParseErrorKind::ExponentOverflow));
+    }
+    Ok((inex, end_pos))
+}
+
+/// See: [`mpfr_set_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpfr/Assignment-Functions.html#index-mpfr_005fset_005fstr)
+///
+/// Returns the ternary value. If an error is returned, the value of
+/// `rop` may have changed. An exponent is an error if the result
+/// overflows or underflows.
+///
+/// This function is only available if the `mpfr` feature is enabled.
+#[cfg(feature = "mpfr")]
+pub unsafe fn mpfr_set_str(
+    rop: *mut mpfr_t,
+    bytes: &[u8],
+    base: c_int,
+    rnd: mpfr::rnd_t,
+) -> Result<c_int, ParseError> {
+    assert!(base == 0 || (2..=62).contains(&base), "base out of range");
+    let copy = CStrCopy::new(bytes);
+    let (inex, end) = strtofr_at(rop, bytes, &copy, 0, base, rnd)?;
+    if end != bytes.len() {
+        return Err(error(end, ParseErrorKind::InvalidDigit));
+    }
+    Ok(inex)
+}
+
+// Returns the error for a missing byte at offset pos.
+#[cfg(feature = "mpc")]
+fn missing(bytes: &[u8], pos: usize) -> ParseError {
+    if pos == bytes.len() {
+        error(pos, ParseErrorKind::Empty)
+    } else {
+        error(pos, ParseErrorKind::InvalidDigit)
+    }
+}
+
+/// See: [`mpc_set_str`](https://tspiteri.gitlab.io/gmp-mpfr-sys/mpc/String-and-Stream-Input-and-Output.html#index-mpc_005fset_005fstr)
+///
+/// Returns the ternary value. If an error is returned, the value of
+/// `rop` may have changed. An exponent is an error if either part
+/// overflows or underflows.
+///
+/// This function is only available if the `mpc` feature is enabled.
+#[cfg(feature = "mpc")]
+pub unsafe fn mpc_set_str(
+    rop: *mut mpc_t,
+    bytes: &[u8],
+    base: c_int,
+    rnd: mpc::rnd_t,
+) -> Result<c_int, ParseError> {
+    assert!(base == 0 || (2..=36).contains(&base), "base out of range");
+    let copy = CStrCopy::new(bytes);
+    let mut pos = skip_space(bytes, 0);
+    let bracketed = bytes.get(pos) == Some(&b'(');
+    if bracketed {
+        pos += 1;
+    }
+    let (inex_re, end) = strtofr_at(mpc::realref(rop), bytes, &copy, pos, base, mpc::RND_RE(rnd))?;
+    pos = end;
+    let inex_im = if bracketed {
+        if pos == bytes.len() || !is_space(bytes[pos]) {
+            return Err(missing(bytes, pos));
+        }
+        pos = skip_space(bytes, pos);
+        let (inex_im, end) =
+            strtofr_at(mpc::imagref(rop), bytes, &copy, pos, base, mpc::RND_IM(rnd))?;
+        pos = skip_space(bytes, end);
+        if bytes.get(pos) != Some(&b')') {
+            return Err(missing(bytes, pos));
+        }
+        pos += 1;
+        inex_im
+    } else {
+        mpfr::set_ui(mpc::imagref(rop), 0, mpfr::rnd_t::RNDN)
+    };
+    pos = skip_space(bytes, pos);
+    if pos != bytes.len() {
+        return Err(error(pos, ParseErrorKind::InvalidDigit));
+    }
+    Ok(mpc::INEX(inex_re, inex_im))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseError, ParseErrorKind};
+    use crate::gmp;
+    #[cfg(feature = "mpc")]
+    use crate::mpc;
+    #[cfg(feature = "mpfr")]
+    use crate::mpfr;
+    use core::mem::MaybeUninit;
+    use libc::{c_char, c_int};
+
+    const INVALID: ParseErrorKind = ParseErrorKind::InvalidDigit;
+    const EMPTY: ParseErrorKind = ParseErrorKind::Empty;
+    const OVERFLOW: ParseErrorKind = ParseErrorKind::ExponentOverflow;
+
+    fn err(offset: usize, kind: ParseErrorKind) -> Result<(), ParseError> {
+        Err(ParseError { offset, kind })
+    }
+
+    // Copies s into buf with a NUL terminator for the C functions.
+    fn c_str(buf: &mut [u8; 128], s: &[u8]) -> *const c_char {
+        buf[..s.len()].copy_from_slice(s);
+        buf[s.len()] = 0;
+        buf.as_ptr() as *const c_char
+    }
+
+    #[test]
+    fn check_mpz_set_str() {
+        let valid: &[(&[u8], c_int)] = &[
+            (b"0", 10),
+            (b"-0", 10),
+            (b"  123", 10),
+            (b"-1 234 567", 10),
+            (b"ff", 16),
+            (b"FF", 16),
+            (b"0x", 0),
+            (b"0xFf", 0),
+            (b"-0B101", 0),
+            (b"0777", 0),
+            (b"00000000000000000000000000000000001", 0),
+            (b"zZ", 62),
+            (b"Zz", 36),
+            (
+                b"123456789012345678901234567890123456789012345678901234567890",
+                10,
+            ),
+            (b"-123456789abcdef0123456789abcdef0123456789abcdef", 16),
+        ];
+        let mut buf = [0u8; 128];
+        unsafe {
+            let mut a = MaybeUninit::uninit();
+            gmp::mpz_init(a.as_mut_ptr());
+            let mut a = a.assume_init();
+            let mut b = MaybeUninit::uninit();
+            gmp::mpz_init(b.as_mut_ptr());
+            let mut b = b.assume_init();
+            for &(s, base) in valid {
+                assert_eq!(super::mpz_set_str(&mut a, s, base), Ok(()));
+                assert_eq!(gmp::mpz_set_str(&mut b, c_str(&mut buf, s), base), 0);
+                assert_eq!(gmp::mpz_cmp(&a, &b), 0);
+            }
+
+            gmp::mpz_set_si(&mut b, 42);
+            let invalid: &[(&[u8], c_int, usize, ParseErrorKind)] = &[
+                (b"", 10, 0, EMPTY),
+                (b"  ", 10, 2, EMPTY),
+                (b"-", 10, 1, EMPTY),
+                (b"- 1", 10, 1, INVALID),
+                (b"+1", 10, 0, INVALID),
+                (b"12a", 10, 2, INVALID),
+                (b"1\0", 10, 1, INVALID),
+                (b"0x1g", 0, 3, INVALID),
+                (b"08", 0, 1, INVALID),
+                (b"z", 35, 0, INVALID),
+            ];
+            for &(s, base, offset, kind) in invalid {
+                assert_eq!(super::mpz_set_str(&mut b, s, base), err(offset, kind));
+                if !s.contains(&0) {
+                    assert_eq!(gmp::mpz_set_str(&mut a, c_str(&mut buf, s), base), -1);
+                }
+                assert_eq!(gmp::mpz_cmp_si(&b, 42), 0);
+            }
+
+            // a slice in the middle of a larger buffer
+            let input = b"12345";
+            assert_eq!(super::mpz_set_str(&mut a, &input[1..4], 10), Ok(()));
+            assert_eq!(gmp::mpz_cmp_si(&a, 234), 0);
+
+            gmp::mpz_clear(&mut a);
+            gmp::mpz_clear(&mut b);
+        }
+    }
+
+    #[test]
+    fn check_mpq_set_str() {
+        unsafe {
+            let mut q = MaybeUninit::uninit();
+            gmp::mpq_init(q.as_mut_ptr());
+            let mut q = q.assume_init();
+            assert_eq!(super::mpq_set_str(&mut q, b"-6/4", 10), Ok(()));
+            assert_eq!(gmp::mpz_cmp_si(gmp::mpq_numref_const(&q), -6), 0);
+            assert_eq!(gmp::mpz_cmp_si(gmp::mpq_denref_const(&q), 4), 0);
+            assert_eq!(super::mpq_set_str(&mut q, b"0x10", 0), Ok(()));
+            assert_eq!(gmp::mpz_cmp_si(gmp::mpq_numref_const(&q), 16), 0);
+            assert_eq!(gmp::mpz_cmp_si(gmp::mpq_denref_const(&q), 1), 0);
+            assert_eq!(super::mpq_set_str(&mut q, b"12/3x", 10), err(4, INVALID));
+            assert_eq!(super::mpq_set_str(&mut q, b"12/", 10), err(3, EMPTY));
+            assert_eq!(super::mpq_set_str(&mut q, b"/3", 10), err(0, EMPTY));
+            assert_eq!(super::mpq_set_str(&mut q, b"1/2/3", 10), err(3, INVALID));
+            assert_eq!(gmp::mpz_cmp_si(gmp::mpq_numref_const(&q), 16), 0);
+            assert_eq!(gmp::mpz_cmp_si(gmp::mpq_denref_const(&q), 1), 0);
+            gmp::mpq_clear(&mut q);
+        }
+    }
+
+    #[test]
+    fn check_mpf_set_str() {
+        let valid: &[(&[u8], c_int)] = &[
+            (b"0", 10),
+            (b"-1.5", 10),
+            (b" .25e1", 10),
+            (b"1.5e-3", 10),
+            (b"12 34.5", 10),
+            (b"ff.8@2", 16),
+            (b"1.1@-10", -2),
+            (b"Zz@+z", 62),
+        ];
+        let mut buf = [0u8; 128];
+        unsafe {
+            let mut a = MaybeUninit::uninit();
+            gmp::mpf_init2(a.as_mut_ptr(), 128);
+            let mut a = a.assume_init();
+            let mut b = MaybeUninit::uninit();
+            gmp::mpf_init2(b.as_mut_ptr(), 128);
+            let mut b = b.assume_init();
+            for &(s, base) in valid {
+                assert_eq!(super::mpf_set_str(&mut a, s, base), Ok(()));
+                assert_eq!(gmp::mpf_set_str(&mut b, c_str(&mut buf, s), base), 0);
+                assert_eq!(gmp::mpf_cmp(&a, &b), 0);
+            }
+
+            gmp::mpf_set_si(&mut b, 42);
+            let invalid: &[(&[u8], c_int, usize, ParseErrorKind)] = &[
+                (b"", 10, 0, EMPTY),
+                (b"-", 10, 1, EMPTY),
+                (b".", 10, 1, EMPTY),
+                (b".e1", 10, 1, INVALID),
+                (b"1.2.3", 10, 3, INVALID),
+                (b"1e", 10, 2, EMPTY),
+                (b"1e+", 10, 3, EMPTY),
+                (b"1e1.5", 10, 3, INVALID),
+                (b"1ea", 10, 2, INVALID),
+                (b"1@2", 2, 2, INVALID),
+                (b"1e99999999999999999999999", 10, 1, OVERFLOW),
+            ];
+            for &(s, base, offset, kind) in invalid {
+                assert_eq!(super::mpf_set_str(&mut b, s, base), err(offset, kind));
+                assert_eq!(gmp::mpf_cmp_si(&b, 42), 0);
+            }
+
+            gmp::mpf_clear(&mut a);
+            gmp::mpf_clear(&mut b);
+        }
+    }
+
+    #[cfg(feature = "mpfr")]
+    #[test]
+    fn check_mpfr_set_str() {
+        let valid: &[(&[u8], c_int)] = &[
+            (b"0", 10),
+            (b"-1.5", 10),
+            (b" +.25e1", 10),
+            (b"0x1.8p-3", 0),
+            (b"ff.8@2", 16),
+            (b"-inf", 10),
+            (b"@NaN@", 16),
+            (b"1.1", 2),
+        ];
+        let mut buf = [0u8; 128];
+        unsafe {
+            let mut a = MaybeUninit::uninit();
+            mpfr::init2(a.as_mut_ptr(), 64);
+            let mut a = a.assume_init();
+            let mut b = MaybeUninit::uninit();
+            mpfr::init2(b.as_mut_ptr(), 64);
+            let mut b = b.assume_init();
+            for &(s, base) in valid {
+                let inex = super::mpfr_set_str(&mut a, s, base, mpfr::rnd_t::RNDN);
+                let c_inex = mpfr::set_str(&mut b, c_str(&mut buf, s), base, mpfr::rnd_t::RNDN);
+                assert_eq!(c_inex, 0);
+                assert_eq!(inex, Ok(0));
+                assert!(mpfr::equal_p(&a, &b) != 0 || mpfr::nan_p(&a) != 0 && mpfr::nan_p(&b) != 0);
+            }
+            assert_eq!(
+                super::mpfr_set_str(&mut a, b"0.1", 10, mpfr::rnd_t::RNDU),
+                Ok(1)
+            );
+
+            mpfr::clear_flags();
+            let invalid: &[(&[u8], c_int, usize, ParseErrorKind)] = &[
+                (b"", 10, 0, EMPTY),
+                (b" -", 10, 2, EMPTY),
+                (b"x", 10, 0, INVALID),
+                (b"1.5 ", 10, 3, INVALID),
+                (b"1e", 10, 1, INVALID),
+                (b"12\0", 10, 2, INVALID),
+                (b"1e99999999999999999999999", 10, 1, OVERFLOW),
+                (b"-1.0p-9999999999999999", 2, 4, OVERFLOW),
+            ];
+            for &(s, base, offset, kind) in invalid {
+                let res = super::mpfr_set_str(&mut a, s, base, mpfr::rnd_t::RNDN);
+                assert_eq!(res, Err(ParseError { offset, kind }));
+            }
+            assert_ne!(mpfr::overflow_p(), 0);
+            assert_ne!(mpfr::underflow_p(), 0);
+
+            mpfr::clear(&mut a);
+            mpfr::clear(&mut b);
+        }
+    }
+
+    #[cfg(feature = "mpc")]
+    #[test]
+    fn check_mpc_set_str() {
+        let valid: &[(&[u8], c_int)] = &[
+            (b"1.5", 10),
+            (b" -2 ", 10),
+            (b"(1 -2.5)", 10),
+            (b" ( 0x1p4\t  @inf@ ) ", 0),
+            (b"(ff 1@1)", 16),
+        ];
+        let mut buf = [0u8; 128];
+        unsafe {
+            let mut a = MaybeUninit::uninit();
+            mpc::init2(a.as_mut_ptr(), 64);
+            let mut a = a.assume_init();
+            let mut b = MaybeUninit::uninit();
+            mpc::init2(b.as_mut_ptr(), 64);
+            let mut b = b.assume_init();
+            for &(s, base) in valid {
+                assert_eq!(super::mpc_set_str(&mut a, s, base, mpc::RNDNN), Ok(0));
+                assert_eq!(
+                    mpc::set_str(&mut b, c_str(&mut buf, s), base, mpc::RNDNN),
+                    0
+                );
+                assert_eq!(mpc::cmp(&a, &b), 0);
+            }
+            let inex = super::mpc_set_str(&mut a, b"(0.1 -0.1)", 10, mpc::RNDUU).unwrap();
+            assert_eq!(mpc::INEX_RE(inex), 1);
+            assert_eq!(mpc::INEX_IM(inex), 1);
+
+            let invalid: &[(&[u8], c_int, usize, ParseErrorKind)] = &[
+                (b"", 10, 0, EMPTY),
+                (b"(", 10, 1, EMPTY),
+                (b"(1", 10, 2, EMPTY),
+                (b"(1,2)", 10, 2, INVALID),
+                (b"(1 2", 10, 4, EMPTY),
+                (b"(1 2 3)", 10, 5, INVALID),
+                (b"(1 2) x", 10, 6, INVALID),
+                (b"(1 2e99999999999999999999)", 10, 4, OVERFLOW),
+            ];
+            for &(s, base, offset, kind) in invalid {
+                let res = super::mpc_set_str(&mut a, s, base, mpc::RNDNN);
+                assert_eq!(res, Err(ParseError { offset, kind }));
+                if kind != OVERFLOW {
+                    assert_eq!(
+                        mpc::set_str(&mut b, c_str(&mut buf, s), base, mpc::RNDNN),
+                        -1
+                    );
+                }
+            }
+
+            mpc::clear(&mut a);
+            mpc::clear(&mut b);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+// Copyright © 2017–2020 University of Malta
+
+// Copying and distribution of this file, with or without
+// modification, are permitted in any medium without royalty provided
+// the copyright notice and this notice are preserved. This file is
+// offered as-is, without any warranty.
+
+// This test references every symbol declared with #[link_name] in the
+// gmp, mpfr and mpc modules, so that linking it fails with the names
+// of any symbols missing from the libraries that were built or found.
+
+extern crate gmp_mpfr_sys;
+
+include!(concat!(env!("OUT_DIR"), "/link_check.rs"));
+
+#[test]
+fn check_link() {
+    let symbols = symbols();
+    assert!(!symbols.is_empty());
+    for (name, addr) in symbols {
+        assert_ne!(addr, 0, "symbol {} has null address", name);
+    }
+}